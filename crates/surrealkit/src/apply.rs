@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use surrealdb::{Surreal, engine::any::Any};
+
+use crate::core::{exec_surql, sha256_hex, sql_query};
+use crate::setup::APPLY_SETUP;
+
+/// Label recorded for SQL piped in with `surrealkit apply -`.
+pub const STDIN_LABEL: &str = "<stdin>";
+
+/// SQL to apply, with the label and hash recorded when it is tracked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplySource {
+	pub label: String,
+	pub sql: String,
+	pub hash: String,
+}
+
+/// Reads `path`, or `stdin` when the path is `-`.
+pub fn read_apply_source(path: &Path, mut stdin: impl Read) -> Result<ApplySource> {
+	let (label, sql) = if path == Path::new("-") {
+		let mut sql = String::new();
+		stdin
+			.read_to_string(&mut sql)
+			.context("reading SQL from stdin")?;
+		(STDIN_LABEL.to_string(), sql)
+	} else {
+		let sql = fs::read_to_string(path).with_context(|| format!("reading {:?}", path))?;
+		(path.display().to_string(), sql)
+	};
+	Ok(ApplySource {
+		hash: sha256_hex(sql.as_bytes()),
+		label,
+		sql,
+	})
+}
+
+/// Executes the source and, with `track`, records it in `_surrealkit_apply`.
+pub async fn run_apply(db: &Surreal<Any>, source: &ApplySource, track: bool) -> Result<()> {
+	if track {
+		sql_query(db, APPLY_SETUP).await?.check()?;
+	}
+	exec_surql(db, &source.sql).await?;
+	if track {
+		sql_query(
+			db,
+			"CREATE _surrealkit_apply CONTENT { file: $file, hash: $hash, applied_at: time::now() };",
+		)
+		.bind(("file", source.label.clone()))
+		.bind(("hash", source.hash.clone()))
+		.await?
+		.check()?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dash_reads_sql_from_stdin() {
+		let sql = "CREATE person:one SET name = 'Ada';";
+		let source = read_apply_source(Path::new("-"), sql.as_bytes()).expect("read stdin");
+		assert_eq!(source.label, STDIN_LABEL);
+		assert_eq!(source.sql, sql);
+		assert_eq!(source.hash, sha256_hex(sql.as_bytes()));
+	}
+
+	#[tokio::test]
+	async fn tracked_stdin_apply_records_a_row() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+
+		let source = read_apply_source(
+			Path::new("-"),
+			"CREATE person:one SET name = 'Ada';".as_bytes(),
+		)
+		.expect("read stdin");
+		run_apply(&db, &source, true).await.expect("apply");
+
+		let mut resp = db
+			.query("SELECT name FROM person; SELECT file, hash FROM _surrealkit_apply;")
+			.await
+			.expect("query");
+		let people: Vec<serde_json::Value> = resp.take(0).expect("people");
+		let applied: Vec<serde_json::Value> = resp.take(1).expect("apply rows");
+		assert_eq!(people, vec![serde_json::json!({ "name": "Ada" })]);
+		assert_eq!(
+			applied,
+			vec![serde_json::json!({ "file": STDIN_LABEL, "hash": source.hash })]
+		);
+	}
+}
@@ -1,9 +1,130 @@
-use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
 use rust_dotenv::dotenv::DotEnv;
+use serde::Deserialize;
 
-use crate::core::create_surreal_client;
+use crate::core::{DEFAULT_CONNECT_RETRY_DELAY_MS, TlsOptions, connect_with_retry};
 use surrealdb::{Surreal, engine::any::Any, opt::auth::Root};
 
+#[derive(Debug, Clone, Default)]
+pub struct EnvFile {
+	vars: HashMap<String, String>,
+}
+
+impl EnvFile {
+	/// Loads the given env file, or `.env` (preferring `.env.local`) from the CWD when none is given.
+	pub fn load(path: Option<&Path>) -> Result<Self> {
+		let vars = match path {
+			Some(path) => {
+				if !path.is_file() {
+					bail!("env file not found: {}", path.display());
+				}
+				DotEnv::load_env(&path.to_string_lossy())
+					.with_context(|| format!("reading env file {}", path.display()))?
+			}
+			None => DotEnv::new("").all_vars().clone(),
+		};
+		Ok(Self { vars })
+	}
+
+	pub fn get(&self, key: &str) -> Option<String> {
+		self.vars.get(key).cloned()
+	}
+}
+
+pub const PROJECT_CONFIG_PATH: &str = "surrealkit.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+	#[serde(default)]
+	pub database: DatabaseSection,
+	#[serde(default)]
+	pub sync: SyncSection,
+	/// Named overrides selected with `--env`, e.g. `[env.staging.database]`.
+	#[serde(default)]
+	pub env: BTreeMap<String, EnvironmentSection>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentSection {
+	#[serde(default)]
+	pub database: DatabaseSection,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseSection {
+	pub host: Option<String>,
+	pub namespace: Option<String>,
+	pub database: Option<String>,
+	pub user: Option<String>,
+	pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyncSection {
+	/// `<kind>:<name>` globs for entities sync and migrations must never remove.
+	#[serde(default)]
+	pub ignore: Vec<String>,
+}
+
+impl ProjectConfig {
+	/// Loads the given project file, or `surrealkit.toml` from the CWD if present.
+	pub fn load(path: Option<&Path>) -> Result<Self> {
+		let path = match path {
+			Some(path) => {
+				if !path.is_file() {
+					bail!("config file not found: {}", path.display());
+				}
+				path
+			}
+			None => {
+				let path = Path::new(PROJECT_CONFIG_PATH);
+				if !path.is_file() {
+					return Ok(Self::default());
+				}
+				path
+			}
+		};
+
+		let raw =
+			fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+		toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))
+	}
+
+	/// Layers the named `[env.<name>]` section over the base `[database]` section.
+	pub fn select_env(mut self, name: &str) -> Result<Self> {
+		let Some(selected) = self.env.remove(name) else {
+			let available = self.env.keys().cloned().collect::<Vec<_>>();
+			if available.is_empty() {
+				bail!("environment '{name}' not found; no [env.<name>] sections are defined");
+			}
+			bail!(
+				"environment '{name}' not found; available: {}",
+				available.join(", ")
+			);
+		};
+		self.database.merge(selected.database);
+		Ok(self)
+	}
+}
+
+impl DatabaseSection {
+	fn merge(&mut self, other: DatabaseSection) {
+		self.host = other.host.or(self.host.take());
+		self.namespace = other.namespace.or(self.namespace.take());
+		self.database = other.database.or(self.database.take());
+		self.user = other.user.or(self.user.take());
+		self.password = other.password.or(self.password.take());
+	}
+}
+
 #[derive(Debug, Clone)]
 pub struct DbCfg {
 	host: String,
@@ -11,40 +132,104 @@ pub struct DbCfg {
 	db: String,
 	user: String,
 	pass: String,
+	tls: bool,
+	tls_ca_cert: Option<PathBuf>,
+	tls_client_cert: Option<PathBuf>,
+	tls_client_key: Option<PathBuf>,
+	tls_insecure: bool,
+	connect_retries: u32,
+	connect_retry_delay_ms: u64,
+	sync_ignore: Vec<String>,
 }
 
 impl DbCfg {
-	pub fn from_env(_env: &DotEnv) -> Result<Self> {
-		let dotenv = DotEnv::new("");
+	/// Reads `surrealkit.toml` from the CWD (if any) with env vars layered on top.
+	pub fn from_env(env: &EnvFile) -> Result<Self> {
+		Ok(Self::from_project(&ProjectConfig::load(None)?, env))
+	}
+
+	/// Reads the given project file instead of `surrealkit.toml`; env vars still take precedence.
+	pub fn from_file(path: &Path, env: &EnvFile) -> Result<Self> {
+		Ok(Self::from_project(&ProjectConfig::load(Some(path))?, env))
+	}
 
-		// DotEnv has already populated std::env; pull from there.
-		let host = dotenv
-			.get_var("PUBLIC_DATABASE_HOST".to_string())
-			.unwrap_or(String::from("http://localhost:8000"));
+	/// Like [`DbCfg::from_file`], with the named environment merged over `[database]`.
+	pub fn from_file_env(path: Option<&Path>, env_name: &str, env: &EnvFile) -> Result<Self> {
+		let project = ProjectConfig::load(path)?.select_env(env_name)?;
+		Ok(Self::from_project(&project, env))
+	}
 
-		let db = dotenv
-			.get_var("PUBLIC_DATABASE_NAME".to_string())
-			.unwrap_or(String::from("test"));
+	/// Env vars take precedence over the project file, which takes precedence over defaults.
+	fn from_project(project: &ProjectConfig, env: &EnvFile) -> Self {
+		let file = &project.database;
+		let pick = |key: &str, from_file: &Option<String>, default: &str| {
+			env.get(key)
+				.or_else(|| from_file.clone())
+				.unwrap_or(String::from(default))
+		};
 
-		let ns = dotenv
-			.get_var("PUBLIC_DATABASE_NAMESPACE".to_string())
-			.unwrap_or(String::from("db"));
+		Self {
+			host: pick("PUBLIC_DATABASE_HOST", &file.host, "http://localhost:8000"),
+			db: pick("PUBLIC_DATABASE_NAME", &file.database, "test"),
+			ns: pick("PUBLIC_DATABASE_NAMESPACE", &file.namespace, "db"),
+			user: pick("DATABASE_USER", &file.user, "root"),
+			pass: pick("DATABASE_PASSWORD", &file.password, "root"),
+			tls: tls_var(env, "SURREALKIT_TLS").is_some_and(|v| parse_flag(&v)),
+			tls_ca_cert: tls_var(env, "SURREALKIT_TLS_CA").map(PathBuf::from),
+			tls_client_cert: tls_var(env, "SURREALKIT_TLS_CERT").map(PathBuf::from),
+			tls_client_key: tls_var(env, "SURREALKIT_TLS_KEY").map(PathBuf::from),
+			tls_insecure: tls_var(env, "SURREALKIT_TLS_INSECURE").is_some_and(|v| parse_flag(&v)),
+			connect_retries: 0,
+			connect_retry_delay_ms: DEFAULT_CONNECT_RETRY_DELAY_MS,
+			sync_ignore: project
+				.sync
+				.ignore
+				.iter()
+				.cloned()
+				.chain(
+					env.get("SURREALKIT_SYNC_IGNORE")
+						.unwrap_or_default()
+						.split(',')
+						.map(|entry| entry.trim().to_string())
+						.filter(|entry| !entry.is_empty()),
+				)
+				.collect(),
+		}
+	}
 
-		let user = dotenv
-			.get_var("DATABASE_USER".to_string())
-			.unwrap_or(String::from("root"));
+	pub fn tls_options(&self) -> TlsOptions {
+		TlsOptions {
+			enabled: self.tls,
+			ca_file: self.tls_ca_cert.clone(),
+			client_cert: self.tls_client_cert.clone(),
+			client_key: self.tls_client_key.clone(),
+			insecure: self.tls_insecure,
+		}
+	}
 
-		let pass = dotenv
-			.get_var("DATABASE_PASSWORD".to_string())
-			.unwrap_or(String::from("root"));
+	/// Applies CLI overrides, which take precedence over env-derived values.
+	pub fn with_overrides(
+		mut self,
+		host: Option<String>,
+		ns: Option<String>,
+		db: Option<String>,
+	) -> Self {
+		if let Some(host) = host {
+			self.host = host;
+		}
+		if let Some(ns) = ns {
+			self.ns = ns;
+		}
+		if let Some(db) = db {
+			self.db = db;
+		}
+		self
+	}
 
-		Ok(Self {
-			host,
-			ns,
-			db,
-			user,
-			pass,
-		})
+	pub fn with_connect_retry(mut self, retries: u32, delay_ms: u64) -> Self {
+		self.connect_retries = retries;
+		self.connect_retry_delay_ms = delay_ms;
+		self
 	}
 
 	pub fn host(&self) -> &str {
@@ -66,12 +251,34 @@ impl DbCfg {
 	pub fn pass(&self) -> &str {
 		&self.pass
 	}
+
+	/// Entity patterns from `[sync] ignore` and `SURREALKIT_SYNC_IGNORE`.
+	pub fn sync_ignore(&self) -> &[String] {
+		&self.sync_ignore
+	}
+}
+
+/// TLS settings come from the env file, falling back to the process environment.
+fn tls_var(env: &EnvFile, key: &str) -> Option<String> {
+	env.get(key).filter(|v| !v.trim().is_empty())
+}
+
+fn parse_flag(value: &str) -> bool {
+	matches!(
+		value.trim().to_ascii_lowercase().as_str(),
+		"1" | "true" | "yes"
+	)
 }
 
 pub async fn connect(cfg: &DbCfg) -> Result<Surreal<Any>> {
-	let db = create_surreal_client(&cfg.host)
-		.await
-		.with_context(|| format!("Failed connecting to {}", cfg.host))?;
+	let db = connect_with_retry(
+		&cfg.host,
+		cfg,
+		cfg.connect_retries + 1,
+		cfg.connect_retry_delay_ms,
+	)
+	.await
+	.with_context(|| format!("Failed connecting to {}", cfg.host))?;
 
 	db.signin(Root {
 		username: cfg.user.to_string(),
@@ -86,3 +293,138 @@ pub async fn connect(cfg: &DbCfg) -> Result<Surreal<Any>> {
 
 	Ok(db)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn env_file_errors_when_explicit_path_is_missing() {
+		let err = EnvFile::load(Some(Path::new("does/not/exist.env")))
+			.expect_err("missing env file should error");
+		assert!(err.to_string().contains("env file not found"));
+	}
+
+	#[test]
+	fn env_file_loads_explicit_path() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join(".env");
+		std::fs::write(&path, "PUBLIC_DATABASE_NAME=staging\n").expect("write env file");
+
+		let env = EnvFile::load(Some(&path)).expect("env file should load");
+
+		let cfg = DbCfg::from_env(&env).expect("cfg");
+		assert_eq!(cfg.db(), "staging");
+		assert_eq!(cfg.ns(), "db");
+	}
+
+	#[test]
+	fn env_vars_overlay_project_config() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join("surrealkit.toml");
+		std::fs::write(
+			&path,
+			"[database]\nhost = \"ws://db.internal:8000\"\nnamespace = \"app\"\n",
+		)
+		.expect("write project config");
+
+		let from_file = DbCfg::from_file(&path, &EnvFile::default()).expect("cfg from file");
+		let project = ProjectConfig::load(Some(&path)).expect("project config");
+
+		assert_eq!(from_file.host(), "ws://db.internal:8000");
+		assert_eq!(from_file.ns(), "app");
+		assert_eq!(from_file.db(), "test");
+
+		let env = EnvFile {
+			vars: HashMap::from([(
+				"PUBLIC_DATABASE_NAMESPACE".to_string(),
+				"env_ns".to_string(),
+			)]),
+		};
+		let cfg = DbCfg::from_project(&project, &env);
+		assert_eq!(cfg.ns(), "env_ns");
+		assert_eq!(cfg.host(), "ws://db.internal:8000");
+	}
+
+	#[test]
+	fn sync_ignore_combines_project_file_and_env() {
+		let project: ProjectConfig =
+			toml::from_str("[sync]\nignore = [\"table:audit_log\"]\n").expect("project config");
+		let env = EnvFile {
+			vars: HashMap::from([(
+				"SURREALKIT_SYNC_IGNORE".to_string(),
+				"function:fn::legacy_*, ,event:*".to_string(),
+			)]),
+		};
+		let cfg = DbCfg::from_project(&project, &env);
+		assert_eq!(
+			cfg.sync_ignore(),
+			["table:audit_log", "function:fn::legacy_*", "event:*"]
+		);
+	}
+
+	#[test]
+	fn project_config_errors_name_the_invalid_field() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join("surrealkit.toml");
+		std::fs::write(&path, "[database]\nhots = \"x\"\n").expect("write project config");
+
+		let err = ProjectConfig::load(Some(&path)).expect_err("unknown field should error");
+		assert!(format!("{err:#}").contains("hots"));
+	}
+
+	#[test]
+	fn named_environment_merges_over_base_database() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join("surrealkit.toml");
+		std::fs::write(
+			&path,
+			"[database]\nhost = \"ws://localhost:8000\"\nnamespace = \"app\"\n\n\
+			 [env.staging.database]\nhost = \"wss://staging.internal\"\n\n\
+			 [env.production.database]\nhost = \"wss://prod.internal\"\n",
+		)
+		.expect("write project config");
+
+		let cfg = DbCfg::from_file_env(Some(&path), "staging", &EnvFile::default());
+		let err = DbCfg::from_file_env(Some(&path), "qa", &EnvFile::default());
+
+		let cfg = cfg.expect("staging cfg");
+		assert_eq!(cfg.host(), "wss://staging.internal");
+		assert_eq!(cfg.ns(), "app");
+		let err = err
+			.expect_err("unknown environment should error")
+			.to_string();
+		assert!(err.contains("available: production, staging"), "{err}");
+	}
+
+	#[test]
+	fn tls_settings_are_read_from_env_file() {
+		let env = EnvFile {
+			vars: HashMap::from([
+				("SURREALKIT_TLS".to_string(), "true".to_string()),
+				("SURREALKIT_TLS_CA".to_string(), "certs/ca.pem".to_string()),
+				("SURREALKIT_TLS_CERT".to_string(), " ".to_string()),
+			]),
+		};
+		let tls = DbCfg::from_env(&env).expect("cfg").tls_options();
+		assert!(tls.enabled);
+		assert_eq!(tls.ca_file, Some(PathBuf::from("certs/ca.pem")));
+		assert_eq!(tls.client_cert, None);
+		assert!(
+			!DbCfg::from_env(&EnvFile::default())
+				.expect("cfg")
+				.tls_options()
+				.enabled
+		);
+	}
+
+	#[test]
+	fn cli_overrides_take_precedence_over_env() {
+		let cfg = DbCfg::from_env(&EnvFile::default())
+			.expect("cfg")
+			.with_overrides(None, Some("staging_ns".to_string()), None);
+		assert_eq!(cfg.ns(), "staging_ns");
+		assert_eq!(cfg.db(), "test");
+		assert_eq!(cfg.host(), "http://localhost:8000");
+	}
+}
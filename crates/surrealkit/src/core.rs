@@ -1,29 +1,739 @@
+use anyhow::{Context, bail};
+use reqwest::header::HeaderMap;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
 use sha2::{Digest, Sha256};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::future::IntoFuture;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once, OnceLock};
+use std::time::Duration;
 use surrealdb::{
 	Surreal,
 	engine::any::{Any, connect},
+	method::Query,
 	opt::{Config, capabilities::Capabilities},
 };
+use surrealdb_types::{SurrealValue, ToSql};
 
-pub async fn create_surreal_client(address: &String) -> Result<Surreal<Any>, surrealdb::Error> {
-	let config =
+use crate::config::DbCfg;
+use crate::schema_state::split_statements;
+
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+	/// Upgrades `ws://` / `http://` hosts to their TLS scheme.
+	pub enabled: bool,
+	pub ca_file: Option<PathBuf>,
+	pub client_cert: Option<PathBuf>,
+	pub client_key: Option<PathBuf>,
+	pub insecure: bool,
+}
+
+pub async fn create_surreal_client(address: &str, cfg: &DbCfg) -> anyhow::Result<Surreal<Any>> {
+	let tls = cfg.tls_options();
+	let mut config =
 		Config::new().capabilities(Capabilities::all().with_all_experimental_features_allowed());
+	if let Some(rustls) = rustls_config(&tls)? {
+		config = config.rustls(rustls);
+	}
+
+	Ok(connect((tls_address(address, tls.enabled), config)).await?)
+}
+
+pub const DEFAULT_CONNECT_RETRY_DELAY_MS: u64 = 500;
+
+/// Retries `create_surreal_client` with exponential backoff, for servers that are still starting.
+pub async fn connect_with_retry(
+	address: &str,
+	cfg: &DbCfg,
+	max_attempts: u32,
+	base_delay_ms: u64,
+) -> anyhow::Result<Surreal<Any>> {
+	retry_with_backoff(max_attempts, base_delay_ms, || {
+		create_surreal_client(address, cfg)
+	})
+	.await
+}
+
+async fn retry_with_backoff<T, F, Fut>(
+	max_attempts: u32,
+	base_delay_ms: u64,
+	mut op: F,
+) -> anyhow::Result<T>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = anyhow::Result<T>>,
+{
+	let max_attempts = max_attempts.max(1);
+	let mut attempt = 0;
+	loop {
+		match op().await {
+			Ok(value) => return Ok(value),
+			Err(err) if attempt + 1 < max_attempts => {
+				let delay = base_delay_ms.saturating_mul(1 << attempt.min(16));
+				attempt += 1;
+				eprintln!(
+					"connection attempt {}/{} failed ({err:#}); retrying in {}ms",
+					attempt, max_attempts, delay
+				);
+				tokio::time::sleep(Duration::from_millis(delay)).await;
+			}
+			Err(err) => return Err(err),
+		}
+	}
+}
+
+fn tls_address(address: &str, enabled: bool) -> String {
+	if enabled {
+		if let Some(rest) = address.strip_prefix("ws://") {
+			return format!("wss://{rest}");
+		}
+		if let Some(rest) = address.strip_prefix("http://") {
+			return format!("https://{rest}");
+		}
+	}
+	address.to_string()
+}
+
+fn rustls_config(opts: &TlsOptions) -> anyhow::Result<Option<ClientConfig>> {
+	if opts.ca_file.is_none()
+		&& opts.client_cert.is_none()
+		&& opts.client_key.is_none()
+		&& !opts.insecure
+	{
+		return Ok(None);
+	}
+
+	let provider = Arc::new(ring::default_provider());
+	let builder = ClientConfig::builder_with_provider(provider.clone())
+		.with_safe_default_protocol_versions()
+		.context("configuring TLS protocol versions")?;
+
+	let builder = if opts.insecure {
+		static WARN: Once = Once::new();
+		WARN.call_once(|| {
+			eprintln!(
+				"WARNING: SURREALKIT_TLS_INSECURE is set. TLS certificate verification is DISABLED \
+and connections are open to interception. Use this for local development only, never in production."
+			);
+		});
+		builder
+			.dangerous()
+			.with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+	} else {
+		builder.with_root_certificates(load_root_store(opts.ca_file.as_deref())?)
+	};
+
+	let config = match (&opts.client_cert, &opts.client_key) {
+		(Some(cert_file), Some(key_file)) => {
+			let certs = load_certs(cert_file, "TLS client certificate")?;
+			let key = PrivateKeyDer::from_pem_file(key_file)
+				.with_context(|| format!("reading TLS client key {}", key_file.display()))?;
+			builder
+				.with_client_auth_cert(certs, key)
+				.context("configuring TLS client certificate")?
+		}
+		(None, None) => builder.with_no_client_auth(),
+		_ => bail!("SURREALKIT_TLS_CERT and SURREALKIT_TLS_KEY must be set together"),
+	};
+	Ok(Some(config))
+}
+
+/// Uses the given CA bundle as the only trust anchor, or the bundled webpki roots otherwise.
+fn load_root_store(ca_file: Option<&Path>) -> anyhow::Result<RootCertStore> {
+	let Some(ca_file) = ca_file else {
+		return Ok(RootCertStore {
+			roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+		});
+	};
+
+	let mut roots = RootCertStore::empty();
+	for cert in load_certs(ca_file, "TLS CA bundle")? {
+		roots
+			.add(cert)
+			.with_context(|| format!("adding CA from {}", ca_file.display()))?;
+	}
+	Ok(roots)
+}
+
+fn load_certs(path: &Path, what: &str) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+	let certs = CertificateDer::pem_file_iter(path)
+		.with_context(|| format!("reading {} {}", what, path.display()))?
+		.collect::<Result<Vec<_>, _>>()
+		.with_context(|| format!("parsing {} {}", what, path.display()))?;
+	if certs.is_empty() {
+		bail!("no certificates found in {} {}", what, path.display());
+	}
+	Ok(certs)
+}
+
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+	fn verify_server_cert(
+		&self,
+		_end_entity: &CertificateDer<'_>,
+		_intermediates: &[CertificateDer<'_>],
+		_server_name: &ServerName<'_>,
+		_ocsp_response: &[u8],
+		_now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		Ok(ServerCertVerified::assertion())
+	}
 
-	connect((address, config)).await
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		verify_tls12_signature(
+			message,
+			cert,
+			dss,
+			&self.0.signature_verification_algorithms,
+		)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		verify_tls13_signature(
+			message,
+			cert,
+			dss,
+			&self.0.signature_verification_algorithms,
+		)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.0.signature_verification_algorithms.supported_schemes()
+	}
+}
+
+static PRINT_SQL: AtomicBool = AtomicBool::new(false);
+
+/// Longest statement text echoed by `--print-sql`.
+const PRINT_SQL_MAX_CHARS: usize = 2_000;
+
+pub fn set_print_sql(enabled: bool) {
+	PRINT_SQL.store(enabled, Ordering::Relaxed);
+}
+
+/// Echoes `sql` and its bound parameters to stderr when `--print-sql` is set.
+fn log_sql(sql: &str, bindings: &[(String, surrealdb_types::Value)]) {
+	if PRINT_SQL.load(Ordering::Relaxed) {
+		let _ = writeln!(io::stderr(), "{}", format_sql_log(sql, bindings));
+	}
+}
+
+/// The `--print-sql` echo: the redacted SQL followed by one `-- $name = value` line per
+/// binding.
+fn format_sql_log(sql: &str, bindings: &[(String, surrealdb_types::Value)]) -> String {
+	let mut out = format!("-- sql\n{}", format_sql_text(sql));
+	for (name, value) in bindings {
+		out.push_str(&format!(
+			"\n-- ${name} = {}",
+			format_sql_text(&value.to_sql())
+		));
+	}
+	out
+}
+
+/// Redacts `PASSWORD`/`PASSHASH` literals and truncates long SQL.
+fn format_sql_text(sql: &str) -> String {
+	static SECRET: OnceLock<regex::Regex> = OnceLock::new();
+	let secret = SECRET.get_or_init(|| {
+		regex::Regex::new(r#"(?i)\b(PASSWORD|PASSHASH)(\s*[:=]?\s*)('[^']*'|"[^"]*")"#)
+			.expect("valid secret pattern")
+	});
+	let redacted = secret.replace_all(sql.trim(), "$1$2'***'");
+	let total = redacted.chars().count();
+	if total <= PRINT_SQL_MAX_CHARS {
+		return redacted.into_owned();
+	}
+	let head: String = redacted.chars().take(PRINT_SQL_MAX_CHARS).collect();
+	format!(
+		"{head}\n-- ... {} more characters",
+		total - PRINT_SQL_MAX_CHARS
+	)
+}
+
+/// A query that `--print-sql` echoes, with its bindings, when it is awaited.
+pub struct SqlQuery<'a> {
+	query: Query<'a, Any>,
+	sql: String,
+	bindings: Vec<(String, surrealdb_types::Value)>,
+}
+
+/// Starts a query on `db`. Every query goes through here so `--print-sql` sees it.
+pub fn sql_query(db: &Surreal<Any>, sql: impl Into<String>) -> SqlQuery<'_> {
+	let sql = sql.into();
+	SqlQuery {
+		query: db.query(sql.as_str()),
+		sql,
+		bindings: Vec::new(),
+	}
+}
+
+impl SqlQuery<'_> {
+	pub fn bind(mut self, (name, value): (impl Into<String>, impl SurrealValue)) -> Self {
+		let name = name.into();
+		let value = value.into_value();
+		self.query = self.query.bind((name.clone(), value.clone()));
+		self.bindings.push((name, value));
+		self
+	}
+}
+
+impl<'a> IntoFuture for SqlQuery<'a> {
+	type Output = <Query<'a, Any> as IntoFuture>::Output;
+	type IntoFuture = <Query<'a, Any> as IntoFuture>::IntoFuture;
+
+	fn into_future(self) -> Self::IntoFuture {
+		log_sql(&self.sql, &self.bindings);
+		self.query.into_future()
+	}
 }
 
 pub async fn exec_surql(db: &Surreal<Any>, sql: &str) -> anyhow::Result<()> {
-	db.query(sql).await?.check()?;
+	sql_query(db, sql).await?.check()?;
 	Ok(())
 }
 
+/// Runs `sql` and returns the first statement's result as JSON.
+pub async fn query_json(db: &Surreal<Any>, sql: &str) -> anyhow::Result<serde_json::Value> {
+	query_json_indexed(db, sql, 0).await
+}
+
+/// Runs `sql`, failing if any statement errors, and returns statement `idx`'s result as
+/// JSON. Values that have no JSON form become `null`.
+pub async fn query_json_indexed(
+	db: &Surreal<Any>,
+	sql: &str,
+	idx: usize,
+) -> anyhow::Result<serde_json::Value> {
+	let mut response = sql_query(db, sql).await?.check()?;
+	let raw: surrealdb_types::Value = response.take(idx)?;
+	Ok(serde_json::Value::from_value(raw).unwrap_or(serde_json::Value::Null))
+}
+
+/// Where [`exec_surql_with_headers`] sends SQL over HTTP: the connection host, the target
+/// namespace/database and request headers such as `authorization`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpSqlTarget {
+	pub host: String,
+	pub namespace: String,
+	pub database: String,
+	pub headers: BTreeMap<String, String>,
+	pub tls: TlsOptions,
+}
+
+/// Runs `sql` like [`query_json`] and also returns the response headers. The SDK hides
+/// headers, so `http(s)://` hosts get the SQL POSTed to `/sql` directly; other transports
+/// run through `db` and return no headers.
+pub async fn exec_surql_with_headers(
+	db: &Surreal<Any>,
+	target: &HttpSqlTarget,
+	sql: &str,
+) -> anyhow::Result<(serde_json::Value, HeaderMap)> {
+	let url = tls_address(&target.host, target.tls.enabled);
+	if !(url.starts_with("http://") || url.starts_with("https://")) {
+		return Ok((query_json(db, sql).await?, HeaderMap::new()));
+	}
+	log_sql(sql, &[]);
+	post_sql(&url, target, sql).await
+}
+
+async fn post_sql(
+	url: &str,
+	target: &HttpSqlTarget,
+	sql: &str,
+) -> anyhow::Result<(serde_json::Value, HeaderMap)> {
+	let mut builder = reqwest::Client::builder();
+	if let Some(rustls) = rustls_config(&target.tls)? {
+		builder = builder.use_preconfigured_tls(rustls);
+	}
+	let client = builder.build().context("building SQL HTTP client")?;
+	let endpoint = format!("{}/sql", url.trim_end_matches('/'));
+	let mut request = client
+		.post(&endpoint)
+		.header("accept", "application/json")
+		.header("surreal-ns", &target.namespace)
+		.header("surreal-db", &target.database)
+		.body(sql.to_string());
+	for (name, value) in &target.headers {
+		request = request.header(name.as_str(), value.as_str());
+	}
+	let response = request
+		.send()
+		.await
+		.with_context(|| format!("request to {endpoint} failed"))?;
+	let status = response.status();
+	let headers = response.headers().clone();
+	let body = response.text().await.context("reading SQL response")?;
+	if !status.is_success() {
+		bail!("SQL request failed with status {}: {}", status, body.trim());
+	}
+	let body: serde_json::Value =
+		serde_json::from_str(&body).with_context(|| format!("parsing SQL response: {body}"))?;
+	Ok((sql_response_result(&body)?, headers))
+}
+
+/// The first statement's result from a `/sql` response, or the first failed statement's
+/// error.
+fn sql_response_result(body: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+	let Some(statements) = body.as_array() else {
+		bail!("unexpected SQL response: {body}");
+	};
+	if let Some(failed) = statements
+		.iter()
+		.find(|stmt| stmt.get("status").and_then(|s| s.as_str()) != Some("OK"))
+	{
+		match failed.get("result") {
+			Some(serde_json::Value::String(message)) => bail!("{message}"),
+			Some(other) => bail!("{other}"),
+			None => bail!("statement failed: {failed}"),
+		}
+	}
+	Ok(statements
+		.first()
+		.and_then(|stmt| stmt.get("result"))
+		.cloned()
+		.unwrap_or(serde_json::Value::Null))
+}
+
+/// Runs `sql` inside a single transaction so a failing statement leaves no partial changes;
+/// the server rolls it back on failure. Falls back to plain execution when the server
+/// rejects transactions, or when `sql` already begins or ends its own.
+pub async fn exec_surql_transactional(db: &Surreal<Any>, sql: &str) -> anyhow::Result<()> {
+	if manages_own_transaction(sql) {
+		eprintln!(
+			"warning: SQL contains its own BEGIN/COMMIT/CANCEL; applying it without another transaction"
+		);
+		return exec_surql(db, sql).await;
+	}
+	match exec_surql(db, &wrap_in_transaction(sql)).await {
+		Err(err) if is_transaction_unsupported(&err) => {
+			eprintln!(
+				"warning: transactions are not supported here ({err:#}); applying without one"
+			);
+			exec_surql(db, sql).await
+		}
+		result => result,
+	}
+}
+
+/// Wraps the statements of `sql` in `BEGIN`/`COMMIT`. Comments are dropped first, so a
+/// trailing `-- comment` cannot swallow the terminator of the last statement.
+pub fn wrap_in_transaction(sql: &str) -> String {
+	let mut out = String::from("BEGIN TRANSACTION;\n");
+	for stmt in split_statements(sql) {
+		out.push_str(&stmt);
+		out.push_str(";\n");
+	}
+	out.push_str("COMMIT TRANSACTION;");
+	out
+}
+
+/// True when a statement of `sql` is a `BEGIN`, `COMMIT` or `CANCEL`.
+fn manages_own_transaction(sql: &str) -> bool {
+	split_statements(sql).iter().any(|stmt| {
+		let keyword = stmt
+			.split(|c: char| c.is_whitespace() || c == ';')
+			.next()
+			.unwrap_or_default();
+		["BEGIN", "COMMIT", "CANCEL"]
+			.iter()
+			.any(|kw| keyword.eq_ignore_ascii_case(kw))
+	})
+}
+
+fn is_transaction_unsupported(err: &anyhow::Error) -> bool {
+	let text = format!("{err:#}").to_ascii_lowercase();
+	text.contains("transaction") && (text.contains("not supported") || text.contains("unsupported"))
+}
+
+/// Asks a y/N question on stdin, defaulting to no. Errors when stdin is not a terminal.
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+	let stdin = io::stdin();
+	if !stdin.is_terminal() {
+		bail!("{question} Confirmation is required; pass --yes in non-interactive runs");
+	}
+	confirm_from(question, &mut stdin.lock())
+}
+
+/// Asks `question` and reads the y/N answer from `input`; anything but `y`/`yes` is no.
+pub fn confirm_from(question: &str, input: &mut dyn BufRead) -> anyhow::Result<bool> {
+	print!("{question} [y/N] ");
+	io::stdout().flush()?;
+	let mut answer = String::new();
+	input.read_line(&mut answer)?;
+	Ok(matches!(
+		answer.trim().to_ascii_lowercase().as_str(),
+		"y" | "yes"
+	))
+}
+
 pub fn display(p: &Path) -> String {
 	p.to_string_lossy().into_owned()
 }
 
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+	let p: Vec<char> = pattern.chars().collect();
+	let t: Vec<char> = text.chars().collect();
+	let mut dp = vec![vec![false; t.len() + 1]; p.len() + 1];
+	dp[0][0] = true;
+
+	for i in 1..=p.len() {
+		if p[i - 1] == '*' {
+			dp[i][0] = dp[i - 1][0];
+		}
+	}
+
+	for i in 1..=p.len() {
+		for j in 1..=t.len() {
+			if p[i - 1] == '*' {
+				dp[i][j] = dp[i - 1][j] || dp[i][j - 1];
+			} else if p[i - 1] == '?' || p[i - 1] == t[j - 1] {
+				dp[i][j] = dp[i - 1][j - 1];
+			}
+		}
+	}
+
+	dp[p.len()][t.len()]
+}
+
 pub fn sha256_hex(bytes: &[u8]) -> String {
 	let mut hasher = Sha256::new();
 	hasher.update(bytes);
 	hex::encode(hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn print_sql_echoes_redacted_statements() {
+		let sql = "DEFINE USER admin ON ROOT PASSWORD 'hunter2' ROLES OWNER;";
+		assert_eq!(
+			format_sql_log(sql, &[]),
+			"-- sql\nDEFINE USER admin ON ROOT PASSWORD '***' ROLES OWNER;"
+		);
+
+		let long = "x".repeat(PRINT_SQL_MAX_CHARS + 5);
+		assert!(format_sql_log(&long, &[]).ends_with("-- ... 5 more characters"));
+	}
+
+	#[test]
+	fn print_sql_lists_bound_parameters() {
+		let bindings = vec![
+			(
+				"path".to_string(),
+				"schema/a.surql".to_string().into_value(),
+			),
+			("hash".to_string(), Option::<String>::None.into_value()),
+		];
+		let printed = format_sql_log("UPSERT _surrealkit_sync SET hash = $hash;", &bindings);
+		assert_eq!(
+			printed,
+			"-- sql\nUPSERT _surrealkit_sync SET hash = $hash;\n\
+			 -- $path = 'schema/a.surql'\n-- $hash = NONE"
+		);
+	}
+
+	#[tokio::test]
+	async fn query_json_returns_statement_result() {
+		let db = connect("mem://").await.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		assert_eq!(
+			query_json(&db, "RETURN { a: 1 + 1 };")
+				.await
+				.expect("query"),
+			serde_json::json!({ "a": 2 })
+		);
+		assert_eq!(
+			query_json_indexed(&db, "RETURN 1; RETURN 'two';", 1)
+				.await
+				.expect("query"),
+			serde_json::json!("two")
+		);
+	}
+
+	#[tokio::test]
+	async fn retry_with_backoff_retries_until_success() {
+		let calls = std::cell::Cell::new(0);
+		let result = retry_with_backoff(3, 1, || {
+			calls.set(calls.get() + 1);
+			let n = calls.get();
+			async move {
+				if n < 3 {
+					anyhow::bail!("connection refused")
+				}
+				Ok(n)
+			}
+		})
+		.await
+		.expect("third attempt should connect");
+		assert_eq!(result, 3);
+
+		calls.set(0);
+		let result: anyhow::Result<()> = retry_with_backoff(2, 1, || {
+			calls.set(calls.get() + 1);
+			async { anyhow::bail!("connection refused") }
+		})
+		.await;
+		assert!(result.is_err());
+		assert_eq!(calls.get(), 2);
+	}
+
+	#[test]
+	fn sql_response_result_surfaces_failed_statements() {
+		let ok = serde_json::json!([{ "status": "OK", "time": "1ms", "result": [{ "a": 1 }] }]);
+		assert_eq!(
+			sql_response_result(&ok).expect("ok response"),
+			serde_json::json!([{ "a": 1 }])
+		);
+
+		let err = serde_json::json!([
+			{ "status": "OK", "result": null },
+			{ "status": "ERR", "result": "Table 'missing' does not exist" }
+		]);
+		let message = sql_response_result(&err).unwrap_err().to_string();
+		assert_eq!(message, "Table 'missing' does not exist");
+	}
+
+	#[tokio::test]
+	async fn post_sql_returns_response_headers() {
+		use std::io::Read;
+		use std::net::TcpListener;
+
+		let listener = TcpListener::bind("127.0.0.1:0").expect("bind test listener");
+		let addr = listener.local_addr().expect("listener addr");
+		let server = std::thread::spawn(move || {
+			let (mut stream, _) = listener.accept().expect("accept connection");
+			let mut buf = [0u8; 4096];
+			let n = stream.read(&mut buf).expect("read request");
+			let body = r#"[{"status":"OK","time":"1ms","result":[{"a":1}]}]"#;
+			let response = format!(
+				"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nx-query-time: 1ms\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+				body.len()
+			);
+			stream
+				.write_all(response.as_bytes())
+				.expect("write response");
+			String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase()
+		});
+
+		let target = HttpSqlTarget {
+			host: format!("http://{addr}"),
+			namespace: "app".to_string(),
+			database: "main".to_string(),
+			headers: BTreeMap::from([("authorization".to_string(), "Bearer t".to_string())]),
+			tls: TlsOptions::default(),
+		};
+		let (result, headers) = post_sql(&target.host, &target, "SELECT * FROM a;")
+			.await
+			.expect("post sql");
+		assert_eq!(result, serde_json::json!([{ "a": 1 }]));
+		assert_eq!(headers.get("x-query-time").unwrap(), "1ms");
+
+		let request = server.join().expect("server thread");
+		assert!(request.starts_with("post /sql "), "{request}");
+		assert!(request.contains("surreal-ns: app"), "{request}");
+		assert!(request.contains("authorization: bearer t"), "{request}");
+	}
+
+	#[test]
+	fn glob_match_handles_wildcards() {
+		assert!(glob_match("*", "abc"));
+		assert!(glob_match("a*", "abc"));
+		assert!(glob_match("a?c", "abc"));
+		assert!(!glob_match("a?d", "abc"));
+	}
+
+	#[test]
+	fn tls_config_is_only_built_when_requested() {
+		assert!(rustls_config(&TlsOptions::default()).unwrap().is_none());
+
+		let insecure = TlsOptions {
+			insecure: true,
+			..TlsOptions::default()
+		};
+		assert!(rustls_config(&insecure).unwrap().is_some());
+	}
+
+	#[test]
+	fn wraps_sql_in_a_single_transaction() {
+		assert_eq!(
+			wrap_in_transaction("DEFINE TABLE a;\nDEFINE TABLE b\n"),
+			"BEGIN TRANSACTION;\nDEFINE TABLE a;\nDEFINE TABLE b;\nCOMMIT TRANSACTION;"
+		);
+		assert_eq!(
+			wrap_in_transaction("DEFINE TABLE a;\nDEFINE TABLE b -- keep b\n"),
+			"BEGIN TRANSACTION;\nDEFINE TABLE a;\nDEFINE TABLE b;\nCOMMIT TRANSACTION;"
+		);
+		assert!(manages_own_transaction(
+			"begin transaction;\nDEFINE TABLE a;\nCOMMIT;"
+		));
+		assert!(!manages_own_transaction(
+			"-- BEGIN here\nDEFINE FIELD begin ON a TYPE string;"
+		));
+		assert!(is_transaction_unsupported(&anyhow::anyhow!(
+			"Transactions are not supported by this engine"
+		)));
+		assert!(!is_transaction_unsupported(&anyhow::anyhow!(
+			"The query was not executed due to a failed transaction"
+		)));
+	}
+
+	#[test]
+	fn tls_config_errors_on_missing_ca_bundle() {
+		let opts = TlsOptions {
+			ca_file: Some(PathBuf::from("does/not/exist.pem")),
+			..TlsOptions::default()
+		};
+		let err = rustls_config(&opts).expect_err("missing CA should error");
+		assert!(format!("{err:#}").contains("reading TLS CA bundle"));
+	}
+
+	#[test]
+	fn tls_config_rejects_malformed_ca_and_half_client_auth() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join("ca.pem");
+		std::fs::write(&path, "not a certificate\n").expect("write CA file");
+		let err = rustls_config(&TlsOptions {
+			ca_file: Some(path.clone()),
+			..TlsOptions::default()
+		})
+		.expect_err("empty bundle should error");
+		assert!(format!("{err:#}").contains("no certificates found"));
+
+		let err = rustls_config(&TlsOptions {
+			client_cert: Some(PathBuf::from("client.pem")),
+			..TlsOptions::default()
+		})
+		.expect_err("cert without key should error");
+		assert!(err.to_string().contains("must be set together"));
+	}
+
+	#[test]
+	fn tls_flag_upgrades_plain_schemes() {
+		assert_eq!(tls_address("ws://db:8000", true), "wss://db:8000");
+		assert_eq!(tls_address("http://db:8000", true), "https://db:8000");
+		assert_eq!(tls_address("ws://db:8000", false), "ws://db:8000");
+		assert_eq!(tls_address("wss://db:8000", true), "wss://db:8000");
+	}
+}
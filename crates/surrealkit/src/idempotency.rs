@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use surrealdb::opt::{Config, capabilities::Capabilities};
+use surrealdb::{
+	Surreal,
+	engine::any::{Any, connect},
+};
+
+use crate::config::DbCfg;
+use crate::core::query_json;
+use crate::schema_state::{SchemaFile, collect_schema_files};
+use crate::sync::{apply_schema_file, matches_only};
+
+/// A schema file whose second application errored or changed the catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyFailure {
+	pub file: String,
+	pub problem: String,
+}
+
+/// Applies every schema file twice in an in-memory database and fails if any re-run
+/// errors or changes `INFO` output. The configured server is never touched.
+pub async fn run_idempotency_check(
+	cfg: &DbCfg,
+	only: Option<&str>,
+	transactional: bool,
+) -> Result<()> {
+	let mut files = collect_schema_files()?;
+	if let Some(pattern) = only {
+		files.retain(|file| matches_only(pattern, &file.path));
+	}
+	if files.is_empty() {
+		println!("No schema files found in database/schema");
+		return Ok(());
+	}
+
+	let config =
+		Config::new().capabilities(Capabilities::all().with_all_experimental_features_allowed());
+	let db = connect(("mem://", config))
+		.await
+		.context("starting in-memory database")?;
+	db.use_ns(cfg.ns()).use_db(cfg.db()).await?;
+
+	let failures = check_idempotent(&db, &files, transactional).await?;
+	if failures.is_empty() {
+		println!("{} schema file(s) are idempotent.", files.len());
+		return Ok(());
+	}
+	for failure in &failures {
+		println!("not idempotent: {}\n  {}", failure.file, failure.problem);
+	}
+	bail!(
+		"{} schema file(s) are not idempotent; use OVERWRITE or IF NOT EXISTS",
+		failures.len()
+	)
+}
+
+/// Applies `files` in order against the current database, re-applying each one and
+/// comparing the catalog before and after the second run.
+pub async fn check_idempotent(
+	db: &Surreal<Any>,
+	files: &[SchemaFile],
+	transactional: bool,
+) -> Result<Vec<IdempotencyFailure>> {
+	let mut failures = Vec::new();
+	for file in files {
+		apply_schema_file(db, &file.sql, transactional)
+			.await
+			.with_context(|| format!("applying {}", file.path))?;
+		let before = catalog_definitions(db).await?;
+		if let Err(err) = apply_schema_file(db, &file.sql, transactional).await {
+			failures.push(IdempotencyFailure {
+				file: file.path.clone(),
+				problem: format!("second apply failed: {err:#}"),
+			});
+			continue;
+		}
+		let changes = describe_catalog_changes(&before, &catalog_definitions(db).await?);
+		if !changes.is_empty() {
+			failures.push(IdempotencyFailure {
+				file: file.path.clone(),
+				problem: format!("second apply changed {}", changes.join(", ")),
+			});
+		}
+	}
+	Ok(failures)
+}
+
+/// Flattens `INFO FOR DB` and `INFO FOR TABLE` into `group.name -> definition`.
+async fn catalog_definitions(db: &Surreal<Any>) -> Result<BTreeMap<String, String>> {
+	let info = query_json(db, "INFO FOR DB;").await?;
+	let mut definitions = BTreeMap::new();
+	flatten_info(&info, "", &mut definitions);
+	let tables: Vec<String> = info
+		.get("tables")
+		.and_then(Value::as_object)
+		.map(|tables| tables.keys().cloned().collect())
+		.unwrap_or_default();
+	for table in tables {
+		let info = query_json(
+			db,
+			&format!("INFO FOR TABLE `{}`;", table.replace('`', "\\`")),
+		)
+		.await?;
+		flatten_info(&info, &format!("{table}:"), &mut definitions);
+	}
+	Ok(definitions)
+}
+
+fn flatten_info(info: &Value, prefix: &str, out: &mut BTreeMap<String, String>) {
+	let Some(groups) = info.as_object() else {
+		return;
+	};
+	for (group, definitions) in groups {
+		let Some(definitions) = definitions.as_object() else {
+			continue;
+		};
+		for (name, definition) in definitions {
+			let text = match definition {
+				Value::String(text) => text.clone(),
+				other => other.to_string(),
+			};
+			out.insert(format!("{prefix}{group}.{name}"), text);
+		}
+	}
+}
+
+/// Lists added (`+`), removed (`-`) and modified (`~`) catalog entries.
+fn describe_catalog_changes(
+	before: &BTreeMap<String, String>,
+	after: &BTreeMap<String, String>,
+) -> Vec<String> {
+	let mut changes = Vec::new();
+	for (key, definition) in after {
+		match before.get(key) {
+			None => changes.push(format!("+{key}")),
+			Some(previous) if previous != definition => changes.push(format!("~{key}")),
+			Some(_) => {}
+		}
+	}
+	for key in before.keys().filter(|key| !after.contains_key(*key)) {
+		changes.push(format!("-{key}"));
+	}
+	changes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn schema_file(path: &str, sql: &str) -> SchemaFile {
+		SchemaFile {
+			path: path.to_string(),
+			sql: sql.to_string(),
+			hash: String::new(),
+		}
+	}
+
+	#[test]
+	fn catalog_changes_report_added_removed_and_modified_entries() {
+		let mut before = BTreeMap::new();
+		flatten_info(
+			&serde_json::json!({
+				"tables": { "person": "DEFINE TABLE person SCHEMAFULL" },
+				"params": { "limit": "DEFINE PARAM $limit VALUE 1" }
+			}),
+			"",
+			&mut before,
+		);
+		let mut after = before.clone();
+		after.insert(
+			"tables.person".to_string(),
+			"DEFINE TABLE person SCHEMALESS".to_string(),
+		);
+		after.remove("params.limit");
+		after.insert(
+			"person:fields.name".to_string(),
+			"DEFINE FIELD name".to_string(),
+		);
+
+		assert_eq!(
+			describe_catalog_changes(&before, &after),
+			vec!["+person:fields.name", "~tables.person", "-params.limit"]
+		);
+		assert!(describe_catalog_changes(&before, &before).is_empty());
+	}
+
+	#[tokio::test]
+	async fn define_without_overwrite_is_not_idempotent() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+
+		let failures = check_idempotent(
+			&db,
+			&[
+				schema_file(
+					"overwrite.surql",
+					"DEFINE TABLE OVERWRITE person SCHEMALESS;",
+				),
+				schema_file("create.surql", "DEFINE TABLE post SCHEMALESS;"),
+			],
+			true,
+		)
+		.await
+		.expect("check");
+		assert_eq!(failures.len(), 1, "{failures:?}");
+		assert_eq!(failures[0].file, "create.surql");
+		assert!(failures[0].problem.starts_with("second apply failed"));
+	}
+
+	#[tokio::test]
+	async fn overwrite_and_if_not_exists_definitions_are_idempotent() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+
+		let failures = check_idempotent(
+			&db,
+			&[
+				schema_file(
+					"person.surql",
+					"DEFINE TABLE OVERWRITE person SCHEMAFULL;\n\
+					 DEFINE FIELD OVERWRITE name ON person TYPE string;",
+				),
+				schema_file(
+					"post.surql",
+					"DEFINE TABLE IF NOT EXISTS post SCHEMALESS;\n\
+					 DEFINE INDEX IF NOT EXISTS post_title ON post FIELDS title;",
+				),
+			],
+			true,
+		)
+		.await
+		.expect("check");
+		assert!(failures.is_empty(), "{failures:?}");
+	}
+}
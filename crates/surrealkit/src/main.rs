@@ -1,11 +1,12 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use rust_dotenv::dotenv::DotEnv;
-use surrealdb::{Surreal, engine::any::Any};
 
+mod apply;
 mod config;
 mod core;
+mod idempotency;
+mod migrate;
 mod rollout;
 mod scaffold;
 mod schema_state;
@@ -13,13 +14,15 @@ mod seed;
 mod setup;
 mod sync;
 mod tester;
+mod verify;
 
-use config::{DbCfg, connect};
-use core::exec_surql;
-use rollout::{RolloutExecutionOpts, RolloutPlanOpts};
+use config::{DbCfg, EnvFile, connect};
+use core::DEFAULT_CONNECT_RETRY_DELAY_MS;
+use migrate::MigrateGenerateOpts;
+use rollout::{DEFAULT_LOCK_TIMEOUT_SECS, RolloutExecutionOpts, RolloutPlanOpts};
 use setup::run_setup;
 use sync::SyncOpts;
-use tester::{TestOpts, run_test};
+use tester::{OutputFormat, TestOpts, clean_test_databases, run_test};
 
 #[derive(Parser, Debug)]
 #[command(version, about = "SurrealKit CLI")]
@@ -28,10 +31,52 @@ pub struct Cli {
 	#[arg(short, long, global = true)]
 	verbose: bool,
 
+	/// Load environment variables from this file instead of `.env`
+	#[arg(long, global = true)]
+	env_file: Option<PathBuf>,
+
+	/// Read project settings from this file instead of `surrealkit.toml`
+	#[arg(long, global = true)]
+	config: Option<PathBuf>,
+
+	/// Merge the `[env.<name>]` section of the project file over `[database]`
+	#[arg(long, global = true)]
+	env: Option<String>,
+
+	/// Override the database host from the environment
+	#[arg(long, global = true)]
+	host: Option<String>,
+
+	/// Override the namespace from the environment
+	#[arg(long, global = true)]
+	ns: Option<String>,
+
+	/// Override the database name from the environment
+	#[arg(long, global = true)]
+	db: Option<String>,
+
+	/// Treat a held `_surrealkit_lock` as stale after this many seconds
+	#[arg(long, global = true, default_value_t = DEFAULT_LOCK_TIMEOUT_SECS)]
+	lock_timeout_secs: u64,
+
+	/// Print every SQL statement sent by exec/sync/seed/test to stderr (passwords redacted)
+	#[arg(long, global = true)]
+	print_sql: bool,
+
+	/// Retry the initial database connection this many times
+	#[arg(long, global = true, default_value_t = 0)]
+	connect_retries: u32,
+
+	/// Base delay before the first connection retry, doubled on each attempt
+	#[arg(long, global = true, default_value_t = DEFAULT_CONNECT_RETRY_DELAY_MS)]
+	connect_retry_delay_ms: u64,
+
 	#[command(subcommand)]
 	command: Commands,
 }
 
+// Parsed once per process, so the size of the `Test` variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 enum Commands {
 	Init,
@@ -43,21 +88,99 @@ enum Commands {
 		debounce_ms: u64,
 		#[arg(long)]
 		dry_run: bool,
+		/// Print entity-level differences against the live database before syncing
+		#[arg(long)]
+		diff: bool,
 		#[arg(long, default_value_t = true)]
 		fail_fast: bool,
 		#[arg(long)]
 		no_prune: bool,
 		#[arg(long)]
 		allow_shared_prune: bool,
+		/// List stale entities and ask before pruning them
+		#[arg(long)]
+		confirm_prune: bool,
+		/// Answer yes to prompts (required for --confirm-prune without a TTY)
+		#[arg(long)]
+		yes: bool,
+		/// Apply each schema file without wrapping it in a transaction
+		#[arg(long)]
+		no_transaction: bool,
+		/// Only sync schema files matching this glob (disables prune)
+		#[arg(long)]
+		only: Option<String>,
+		/// Fail a schema file that takes longer than this to apply; its hash is not recorded
+		#[arg(long)]
+		file_timeout_ms: Option<u64>,
+		/// Apply each schema file twice in an in-memory database and fail if re-running it
+		/// errors or changes the catalog
+		#[arg(long, conflicts_with_all = ["watch", "dry_run", "diff"])]
+		check_idempotent: bool,
+		/// Write the files a dry run would apply, with their hashes and tracking state, as JSON
+		/// (`--diff --dry-run` stops after the diff, so it cannot write one)
+		#[arg(
+			long,
+			requires = "dry_run",
+			conflicts_with = "diff",
+			value_name = "PATH"
+		)]
+		dry_run_out: Option<PathBuf>,
+	},
+	Baseline {
+		#[arg(long)]
+		strict: bool,
 	},
 	Rollout {
 		#[command(subcommand)]
 		command: RolloutCommands,
 	},
-	Seed,
-	Status,
+	Migrate {
+		#[command(subcommand)]
+		command: MigrateCommands,
+	},
+	Seed {
+		#[arg(long)]
+		dry_run: bool,
+		/// Run `CREATE table:id` statements as `UPSERT` so seeding can be repeated
+		#[arg(long)]
+		upsert: bool,
+	},
+	Status {
+		/// Keep polling and print schema files as other processes sync them
+		#[arg(long)]
+		watch: bool,
+		#[arg(long, default_value_t = 1000)]
+		interval_ms: u64,
+	},
+	Verify,
+	/// Remove all SurrealKit tracking tables and local snapshots
+	Reset {
+		#[arg(long)]
+		confirm: bool,
+	},
+	/// Drop SurrealKit's bookkeeping tables, keeping application schema and data
+	Clean {
+		/// Skip the confirmation prompt
+		#[arg(long)]
+		yes: bool,
+		#[arg(long)]
+		allow_shared: bool,
+		/// Remove namespaces left behind by interrupted `test` runs instead
+		#[arg(long)]
+		test_dbs: bool,
+		/// With --test-dbs, only remove namespaces from runs older than this
+		#[arg(long, requires = "test_dbs")]
+		older_than_hours: Option<u64>,
+		/// With --test-dbs, list namespaces without removing them
+		#[arg(long, requires = "test_dbs")]
+		dry_run: bool,
+	},
 	Apply {
+		/// SQL file to run, or `-` to read it from stdin
 		path: PathBuf,
+		/// Record the applied SQL and its hash in `_surrealkit_apply`
+		#[arg(long)]
+		track: bool,
 	},
 	Test {
 		#[arg(long)]
@@ -66,24 +189,74 @@ enum Commands {
 		case: Option<String>,
 		#[arg(long)]
 		tag: Vec<String>,
+		/// Skip cases tagged with this, on the case or its suite (repeatable)
+		#[arg(long)]
+		exclude_tag: Vec<String>,
+		/// Print why each suite and case was selected or filtered out
+		#[arg(long)]
+		explain: bool,
+		/// Print the selected suites and cases without connecting to SurrealDB
+		#[arg(long)]
+		list: bool,
+		/// Only run suites affected by files changed since this git ref
+		#[arg(long)]
+		changed_since: Option<String>,
 		#[arg(long)]
 		fail_fast: bool,
+		/// Stop the whole run after this many failing cases
+		#[arg(long, value_name = "N")]
+		bail: Option<usize>,
 		#[arg(long, default_value_t = 1)]
 		parallel: usize,
 		#[arg(long)]
 		json_out: Option<PathBuf>,
+		/// Write the --json-out report on a single line
+		#[arg(long, requires = "json_out")]
+		json_compact: bool,
+		#[arg(long)]
+		junit_out: Option<PathBuf>,
+		/// Write run metrics in Prometheus textfile format
+		#[arg(long)]
+		metrics_out: Option<PathBuf>,
+		/// Report format printed to stdout
+		#[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+		output: OutputFormat,
+		/// Write the TAP report to this file instead of stdout
+		#[arg(long)]
+		tap_out: Option<PathBuf>,
 		#[arg(long)]
 		no_setup: bool,
 		#[arg(long)]
 		no_sync: bool,
 		#[arg(long)]
 		no_seed: bool,
+		/// Run only the selected preparation steps (combine with --only-sync/--only-seed)
+		#[arg(long, conflicts_with_all = ["no_setup", "no_sync", "no_seed"])]
+		only_setup: bool,
+		#[arg(long, conflicts_with_all = ["no_setup", "no_sync", "no_seed"])]
+		only_sync: bool,
+		#[arg(long, conflicts_with_all = ["no_setup", "no_sync", "no_seed"])]
+		only_seed: bool,
 		#[arg(long)]
 		base_url: Option<String>,
 		#[arg(long)]
 		timeout_ms: Option<u64>,
 		#[arg(long)]
 		keep_db: bool,
+		/// Run every suite against the configured database instead of a fresh one.
+		#[arg(long)]
+		no_ephemeral: bool,
+		#[arg(long)]
+		retry_transient: bool,
+		/// Minimum `retries` for every case; failed results are re-run as well as errors
+		#[arg(long, default_value_t = 0)]
+		global_retry: u32,
+		/// Reuse the root connection, and unchanged actor sessions, across suites
+		#[arg(long)]
+		reuse_connections: bool,
+		/// Rewrite sql_expect snapshot files with the actual results
+		#[arg(long)]
+		update_snapshots: bool,
 	},
 }
 
@@ -113,135 +286,258 @@ enum RolloutCommands {
 	},
 }
 
-fn load_env() -> DotEnv {
-	// Load .env in CWD if present, ignore missing
-	let env = DotEnv::new("");
-	env
+#[derive(Subcommand, Debug)]
+enum MigrateCommands {
+	/// Write schema changes since the last snapshot to a file in database/migrations
+	Generate {
+		#[arg(long)]
+		name: Option<String>,
+		/// Print the migration without writing it or updating the snapshot
+		#[arg(long)]
+		dry_run: bool,
+	},
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
 	let args = Cli::parse();
-	let env = load_env();
+	core::set_print_sql(args.print_sql);
+	let env = EnvFile::load(args.env_file.as_deref())?;
+	let cfg = match (args.config.as_deref(), args.env.as_deref()) {
+		(path, Some(name)) => DbCfg::from_file_env(path, name, &env)?,
+		(Some(path), None) => DbCfg::from_file(path, &env)?,
+		(None, None) => DbCfg::from_env(&env)?,
+	}
+	.with_overrides(args.host, args.ns, args.db)
+	.with_connect_retry(args.connect_retries, args.connect_retry_delay_ms);
+	if args.verbose {
+		println!("Using host={} ns={} db={}", cfg.host(), cfg.ns(), cfg.db());
+	}
 
 	match args.command {
 		Commands::Init => scaffold::scaffold()?,
 		Commands::Setup => {
-			let db = connect_from_env(&env).await?;
+			let db = connect(&cfg).await?;
 			run_setup(&db).await?;
 		}
 		Commands::Sync {
 			watch,
 			debounce_ms,
 			dry_run,
+			diff,
 			fail_fast,
 			no_prune,
 			allow_shared_prune,
+			confirm_prune,
+			yes,
+			no_transaction,
+			only,
+			file_timeout_ms,
+			check_idempotent,
+			dry_run_out,
 		} => {
-			let db = connect_from_env(&env).await?;
+			if check_idempotent {
+				idempotency::run_idempotency_check(&cfg, only.as_deref(), !no_transaction).await?;
+				return Ok(());
+			}
+			let db = connect(&cfg).await?;
 			sync::run_sync(
 				&db,
 				SyncOpts {
 					watch,
 					debounce_ms,
 					dry_run,
+					diff,
 					fail_fast,
 					prune: !no_prune,
 					allow_shared_prune,
+					confirm_prune,
+					yes,
+					transactional: !no_transaction,
+					only,
+					lock_timeout_secs: args.lock_timeout_secs,
+					file_timeout_ms,
+					ignore: cfg.sync_ignore().to_vec(),
+					dry_run_out,
 				},
 			)
 			.await?;
 		}
+		Commands::Baseline { strict } => {
+			let db = connect(&cfg).await?;
+			sync::run_sync_baseline(&db, strict).await?;
+		}
+		Commands::Migrate { command } => match command {
+			MigrateCommands::Generate { name, dry_run } => {
+				migrate::run_generate(MigrateGenerateOpts {
+					name,
+					dry_run,
+					ignore: cfg.sync_ignore().to_vec(),
+				})?;
+			}
+		},
 		Commands::Rollout { command } => match command {
 			RolloutCommands::Baseline => {
-				let db = connect_from_env(&env).await?;
+				let db = connect(&cfg).await?;
 				rollout::run_baseline(&db).await?;
 			}
 			RolloutCommands::Plan { name, dry_run } => {
 				rollout::run_plan(RolloutPlanOpts { name, dry_run }).await?;
 			}
 			RolloutCommands::Start { target } => {
-				let db = connect_from_env(&env).await?;
+				let db = connect(&cfg).await?;
 				rollout::run_start(
 					&db,
 					RolloutExecutionOpts {
 						selector: Some(target),
+						lock_timeout_secs: args.lock_timeout_secs,
 					},
 				)
 				.await?;
 			}
 			RolloutCommands::Complete { target } => {
-				let db = connect_from_env(&env).await?;
+				let db = connect(&cfg).await?;
 				rollout::run_complete(
 					&db,
 					RolloutExecutionOpts {
 						selector: Some(target),
+						lock_timeout_secs: args.lock_timeout_secs,
 					},
 				)
 				.await?;
 			}
 			RolloutCommands::Rollback { target } => {
-				let db = connect_from_env(&env).await?;
+				let db = connect(&cfg).await?;
 				rollout::run_rollback(
 					&db,
 					RolloutExecutionOpts {
 						selector: Some(target),
+						lock_timeout_secs: args.lock_timeout_secs,
 					},
 				)
 				.await?;
 			}
 			RolloutCommands::Status { target } => {
-				let db = connect_from_env(&env).await?;
+				let db = connect(&cfg).await?;
 				rollout::run_status(&db, target).await?;
 			}
 			RolloutCommands::Lint { target } => {
 				rollout::run_lint(RolloutExecutionOpts {
 					selector: Some(target),
+					lock_timeout_secs: args.lock_timeout_secs,
 				})
 				.await?;
 			}
 		},
-		Commands::Seed => {
-			let db = connect_from_env(&env).await?;
-			seed::seed(&db).await?;
+		Commands::Seed { dry_run, upsert } => {
+			if dry_run {
+				seed::seed_dry_run(upsert)?;
+			} else {
+				let db = connect(&cfg).await?;
+				seed::seed(&db, upsert).await?;
+			}
 		}
-		Commands::Status => {
-			let db = connect_from_env(&env).await?;
+		Commands::Status { watch, interval_ms } => {
+			let db = connect(&cfg).await?;
 			rollout::run_status(&db, None).await?;
+			if watch {
+				sync::run_sync_status_watch(&db, interval_ms).await?;
+			}
+		}
+		Commands::Verify => {
+			let db = connect(&cfg).await?;
+			verify::run_verify(&db).await?;
 		}
-		Commands::Apply { path } => {
-			let db = connect_from_env(&env).await?;
-			let sql = std::fs::read_to_string(&path)?;
-			exec_surql(&db, &sql).await?;
+		Commands::Reset { confirm } => {
+			let db = connect(&cfg).await?;
+			setup::reset_surrealkit_state(&db, confirm).await?;
+		}
+		Commands::Clean {
+			yes,
+			allow_shared,
+			test_dbs,
+			older_than_hours,
+			dry_run,
+		} => {
+			let db = connect(&cfg).await?;
+			if test_dbs {
+				clean_test_databases(&db, older_than_hours, dry_run).await?;
+			} else {
+				setup::clean_surrealkit_tables(&db, yes, allow_shared).await?;
+			}
+		}
+		Commands::Apply { path, track } => {
+			let source = apply::read_apply_source(&path, std::io::stdin().lock())?;
+			let db = connect(&cfg).await?;
+			apply::run_apply(&db, &source, track).await?;
 		}
 		Commands::Test {
 			suite,
 			case,
 			tag,
+			exclude_tag,
+			explain,
+			list,
+			changed_since,
 			fail_fast,
+			bail,
 			parallel,
 			json_out,
+			json_compact,
+			junit_out,
+			metrics_out,
+			output,
+			tap_out,
 			no_setup,
 			no_sync,
 			no_seed,
+			only_setup,
+			only_sync,
+			only_seed,
 			base_url,
 			timeout_ms,
 			keep_db,
+			no_ephemeral,
+			retry_transient,
+			global_retry,
+			reuse_connections,
+			update_snapshots,
 		} => {
-			run_test(TestOpts {
-				suite,
-				case,
-				tags: tag,
-				fail_fast,
-				parallel,
-				json_out,
-				no_setup,
-				no_sync,
-				no_seed,
-				base_url,
-				timeout_ms,
-				keep_db,
-			})
+			run_test(
+				cfg,
+				TestOpts {
+					suite,
+					case,
+					tags: tag,
+					exclude_tags: exclude_tag,
+					explain,
+					list,
+					changed_since,
+					fail_fast,
+					bail,
+					parallel,
+					json_out,
+					json_compact,
+					junit_out,
+					metrics_out,
+					output,
+					tap_out,
+					no_setup,
+					no_sync,
+					no_seed,
+					only_setup,
+					only_sync,
+					only_seed,
+					base_url,
+					timeout_ms,
+					keep_db,
+					no_ephemeral,
+					retry_transient,
+					global_retry,
+					reuse_connections,
+					update_snapshots,
+				},
+			)
 			.await?;
 		}
 	}
@@ -249,7 +545,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 	Ok(())
 }
 
-async fn connect_from_env(env: &DotEnv) -> anyhow::Result<Surreal<Any>> {
-	let cfg = DbCfg::from_env(env)?;
-	connect(&cfg).await
+#[cfg(test)]
+mod tests {
+	use clap::Parser;
+	use clap::error::ErrorKind;
+
+	use super::Cli;
+
+	#[test]
+	fn dry_run_out_is_rejected_with_diff() {
+		let err = Cli::try_parse_from([
+			"surrealkit",
+			"sync",
+			"--diff",
+			"--dry-run",
+			"--dry-run-out",
+			"plan.json",
+		])
+		.expect_err("--dry-run-out conflicts with --diff");
+		assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+
+		Cli::try_parse_from([
+			"surrealkit",
+			"sync",
+			"--dry-run",
+			"--dry-run-out",
+			"plan.json",
+		])
+		.expect("--dry-run-out with --dry-run");
+	}
 }
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use time::{OffsetDateTime, macros::format_description};
+
+use crate::rollout::slugify;
+use crate::schema_state::{
+	CatalogDiff, CatalogEntity, FileDiff, MIGRATIONS_DIR, SchemaFile, SyncIgnore,
+	build_catalog_snapshot, collect_schema_files, diff_catalog, diff_schema,
+	ensure_local_state_dirs, load_catalog_snapshot, load_schema_snapshot, render_remove_sql,
+	save_catalog_snapshot, save_schema_snapshot, snapshot_from_files,
+};
+
+#[derive(Debug, Clone)]
+pub struct MigrateGenerateOpts {
+	pub name: Option<String>,
+	pub dry_run: bool,
+	/// `<kind>:<name>` patterns for entities that are never removed.
+	pub ignore: Vec<String>,
+}
+
+/// Writes the schema changes since the last snapshot to a timestamped SQL file in
+/// `database/migrations` and advances the snapshot.
+pub fn run_generate(opts: MigrateGenerateOpts) -> Result<()> {
+	ensure_local_state_dirs()?;
+	let files = collect_schema_files()?;
+	let new_schema = snapshot_from_files(&files);
+	let new_catalog = build_catalog_snapshot(&files)?;
+	let file_diff = diff_schema(&load_schema_snapshot()?, &new_schema);
+	let catalog_diff = diff_catalog(&load_catalog_snapshot()?, &new_catalog);
+
+	let ignore = SyncIgnore::parse(&opts.ignore)?;
+	let migration = render_migration(&files, &file_diff, &catalog_diff, &ignore)?;
+	if migration.statements == 0 {
+		println!("No schema changes since the last snapshot.");
+		return Ok(());
+	}
+
+	let slug = slugify(opts.name.as_deref().unwrap_or("schema_migration"));
+	let ts = OffsetDateTime::now_utc().format(&format_description!(
+		"[year][month][day][hour][minute][second]"
+	))?;
+	let path = Path::new(MIGRATIONS_DIR).join(format!("{ts}__{slug}.surql"));
+
+	if migration.removals > 0 {
+		eprintln!(
+			"warning: migration includes {} destructive REMOVE statement(s); review it before applying",
+			migration.removals
+		);
+	}
+	if opts.dry_run {
+		println!("-- would create {}", path.display());
+		print!("{}", migration.sql);
+		return Ok(());
+	}
+
+	fs::create_dir_all(MIGRATIONS_DIR).with_context(|| format!("creating {}", MIGRATIONS_DIR))?;
+	fs::write(&path, &migration.sql)
+		.with_context(|| format!("writing migration file {}", path.display()))?;
+	save_schema_snapshot(&new_schema)?;
+	save_catalog_snapshot(&new_catalog)?;
+
+	println!("Generated migration {}", path.display());
+	Ok(())
+}
+
+#[derive(Debug)]
+struct Migration {
+	sql: String,
+	/// Schema files and REMOVE statements included.
+	statements: usize,
+	removals: usize,
+}
+
+/// Added and modified schema files in full, followed by REMOVE statements for entities
+/// that no longer exist and are not ignored.
+fn render_migration(
+	files: &[SchemaFile],
+	file_diff: &FileDiff,
+	catalog_diff: &CatalogDiff,
+	ignore: &SyncIgnore,
+) -> Result<Migration> {
+	let mut sql = String::from("-- Generated by `surrealkit migrate generate`.\n");
+	let mut statements = 0;
+	for (label, paths) in [
+		("added", &file_diff.added),
+		("modified", &file_diff.modified),
+	] {
+		for file in files.iter().filter(|file| paths.contains(&file.path)) {
+			sql.push_str(&format!("\n-- {label}: {}\n", file.path));
+			sql.push_str(file.sql.trim_end());
+			sql.push('\n');
+			statements += 1;
+		}
+	}
+
+	let removed: Vec<_> = catalog_diff
+		.removed
+		.iter()
+		.map(CatalogEntity::key)
+		.filter(|key| !ignore.matches(key))
+		.collect();
+	let removals = render_remove_sql(&removed, true)?;
+	if !removals.is_empty() {
+		sql.push_str("\n-- WARNING: destructive; removes entities deleted from the schema.\n");
+		for stmt in &removals {
+			sql.push_str(stmt);
+			sql.push('\n');
+		}
+	}
+
+	Ok(Migration {
+		sql,
+		statements: statements + removals.len(),
+		removals: removals.len(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::sha256_hex;
+
+	fn schema_file(path: &str, sql: &str) -> SchemaFile {
+		SchemaFile {
+			path: path.to_string(),
+			sql: sql.to_string(),
+			hash: sha256_hex(sql.as_bytes()),
+		}
+	}
+
+	#[test]
+	fn migration_removes_deleted_entities() {
+		let old_files = vec![
+			schema_file(
+				"database/schema/person.surql",
+				"DEFINE TABLE OVERWRITE person SCHEMAFULL;\nDEFINE FIELD OVERWRITE nickname ON person TYPE string;\n",
+			),
+			schema_file(
+				"database/schema/legacy.surql",
+				"DEFINE TABLE OVERWRITE legacy SCHEMALESS;\n",
+			),
+		];
+		let new_files = vec![
+			schema_file(
+				"database/schema/person.surql",
+				"DEFINE TABLE OVERWRITE person SCHEMAFULL;\n",
+			),
+			schema_file(
+				"database/schema/post.surql",
+				"DEFINE TABLE OVERWRITE post SCHEMALESS;\n",
+			),
+		];
+		let file_diff = diff_schema(
+			&snapshot_from_files(&old_files),
+			&snapshot_from_files(&new_files),
+		);
+		let catalog_diff = diff_catalog(
+			&build_catalog_snapshot(&old_files).expect("old catalog"),
+			&build_catalog_snapshot(&new_files).expect("new catalog"),
+		);
+
+		let migration = render_migration(
+			&new_files,
+			&file_diff,
+			&catalog_diff,
+			&SyncIgnore::default(),
+		)
+		.expect("render migration");
+		assert_eq!(migration.removals, 2);
+		assert_eq!(migration.statements, 4);
+		assert!(
+			migration.sql.contains(
+				"-- added: database/schema/post.surql\nDEFINE TABLE OVERWRITE post SCHEMALESS;\n"
+			),
+			"{}",
+			migration.sql
+		);
+		assert!(
+			migration
+				.sql
+				.contains("-- modified: database/schema/person.surql\n"),
+			"{}",
+			migration.sql
+		);
+		assert!(
+			migration.sql.contains("REMOVE TABLE legacy;"),
+			"{}",
+			migration.sql
+		);
+		assert!(
+			migration.sql.contains("REMOVE FIELD nickname ON person;"),
+			"{}",
+			migration.sql
+		);
+	}
+}
@@ -6,11 +6,10 @@ use anyhow::{Context, Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use surrealdb::{Surreal, engine::any::Any};
-use surrealdb_types::SurrealValue;
 use time::{OffsetDateTime, format_description::well_known::Rfc3339, macros::format_description};
 
-use crate::core::exec_surql;
 use crate::core::sha256_hex;
+use crate::core::{exec_surql, query_json, sql_query};
 use crate::schema_state::{
 	CATALOG_SNAPSHOT_PATH, CatalogDiff, CatalogEntity, CatalogSnapshot, EntityKey, FileDiff,
 	ROLLOUTS_DIR, SchemaFile, build_catalog_snapshot, collect_schema_files, diff_catalog,
@@ -26,9 +25,12 @@ pub struct RolloutPlanOpts {
 	pub dry_run: bool,
 }
 
+pub const DEFAULT_LOCK_TIMEOUT_SECS: u64 = 300;
+
 #[derive(Debug, Clone)]
 pub struct RolloutExecutionOpts {
 	pub selector: Option<String>,
+	pub lock_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -239,7 +241,7 @@ pub async fn run_status(db: &Surreal<Any>, selector: Option<String>) -> Result<(
 	}
 	query.push_str(" ORDER BY started_at DESC;");
 
-	let mut req = db.query(query);
+	let mut req = sql_query(db, query);
 	if let Some(id) = selector {
 		req = req.bind(("id", id));
 	}
@@ -265,13 +267,13 @@ pub async fn run_status(db: &Surreal<Any>, selector: Option<String>) -> Result<(
 			println!("  last_error: {}", last_error);
 		}
 
-		let mut step_resp = db
-			.query(
-				"SELECT step_id, phase, kind, status, error FROM _surrealkit_rollout_step \
+		let mut step_resp = sql_query(
+			db,
+			"SELECT step_id, phase, kind, status, error FROM _surrealkit_rollout_step \
 				 WHERE rollout_id = $rollout_id ORDER BY started_at, step_id;",
-			)
-			.bind(("rollout_id", id.clone()))
-			.await?;
+		)
+		.bind(("rollout_id", id.clone()))
+		.await?;
 		let steps: Vec<Value> = step_resp.take(0)?;
 		for step in steps {
 			let step_id = string_field(&step, "step_id").unwrap_or_else(|| "<step>".to_string());
@@ -313,7 +315,7 @@ pub async fn run_start(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Result<
 			.collect(),
 	};
 
-	acquire_lock(db, "global").await?;
+	let lock = acquire_lock(db, "global", opts.lock_timeout_secs).await?;
 	let result = async {
 		ensure_no_conflicting_active_rollout(db, &rollout.spec.id).await?;
 		let record = load_rollout_record(db, &rollout.spec.id).await?;
@@ -373,7 +375,7 @@ pub async fn run_start(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Result<
 		Ok(())
 	}
 	.await;
-	let release = release_lock(db, "global").await;
+	let release = lock.release(db).await;
 	match (result, release) {
 		(Err(err), _) => Err(err),
 		(Ok(_), Err(err)) => Err(err),
@@ -385,7 +387,7 @@ pub async fn run_complete(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Resu
 	run_setup(db).await?;
 	let rollout = load_rollout_spec(resolve_rollout_path(opts.selector.as_deref())?)?;
 	validate_rollout_spec(&rollout.spec)?;
-	acquire_lock(db, "global").await?;
+	let lock = acquire_lock(db, "global", opts.lock_timeout_secs).await?;
 	let result = async {
 		let row = load_rollout_record(db, &rollout.spec.id)
 			.await?
@@ -435,7 +437,7 @@ pub async fn run_complete(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Resu
 		Ok(())
 	}
 	.await;
-	let release = release_lock(db, "global").await;
+	let release = lock.release(db).await;
 	match (result, release) {
 		(Err(err), _) => Err(err),
 		(Ok(_), Err(err)) => Err(err),
@@ -447,7 +449,7 @@ pub async fn run_rollback(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Resu
 	run_setup(db).await?;
 	let rollout = load_rollout_spec(resolve_rollout_path(opts.selector.as_deref())?)?;
 	validate_rollout_spec(&rollout.spec)?;
-	acquire_lock(db, "global").await?;
+	let lock = acquire_lock(db, "global", opts.lock_timeout_secs).await?;
 	let result = async {
 		let row = load_rollout_record(db, &rollout.spec.id)
 			.await?
@@ -495,7 +497,7 @@ pub async fn run_rollback(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Resu
 		Ok(())
 	}
 	.await;
-	let release = release_lock(db, "global").await;
+	let release = lock.release(db).await;
 	match (result, release) {
 		(Err(err), _) => Err(err),
 		(Ok(_), Err(err)) => Err(err),
@@ -504,8 +506,7 @@ pub async fn run_rollback(db: &Surreal<Any>, opts: RolloutExecutionOpts) -> Resu
 }
 
 pub async fn load_active_rollout_id(db: &Surreal<Any>) -> Result<Option<String>> {
-	let mut resp = db
-		.query(
+	let mut resp = sql_query(db,
 			"SELECT id, status FROM _surrealkit_rollout \
 			 WHERE status INSIDE ['planned', 'running_start', 'ready_to_complete', 'running_complete', 'running_rollback', 'failed'] \
 			 ORDER BY started_at DESC LIMIT 1;",
@@ -516,12 +517,12 @@ pub async fn load_active_rollout_id(db: &Surreal<Any>) -> Result<Option<String>>
 }
 
 pub async fn load_managed_entities(db: &Surreal<Any>) -> Result<Vec<ManagedEntityRecord>> {
-	let mut resp = db
-		.query(
-			"SELECT kind, scope, name, source_path, statement_hash, file_hash, active_rollout_id, state \
+	let mut resp = sql_query(
+		db,
+		"SELECT kind, scope, name, source_path, statement_hash, file_hash, active_rollout_id, state \
 			 FROM _surrealkit_managed_entity;",
-		)
-		.await?;
+	)
+	.await?;
 	let rows: Vec<Value> = resp.take(0)?;
 	let mut out = Vec::with_capacity(rows.len());
 	for row in rows {
@@ -563,7 +564,8 @@ pub async fn upsert_managed_entities(
 	state: &str,
 ) -> Result<()> {
 	for entity in entities {
-		db.query(
+		sql_query(
+			db,
 			"DELETE _surrealkit_managed_entity \
 			 WHERE kind = $kind AND scope = $scope AND name = $name; \
 			 CREATE _surrealkit_managed_entity CONTENT { \
@@ -594,7 +596,8 @@ pub async fn upsert_managed_entities(
 
 pub async fn delete_managed_entities(db: &Surreal<Any>, entities: &[EntityKey]) -> Result<()> {
 	for entity in entities {
-		db.query(
+		sql_query(
+			db,
 			"DELETE _surrealkit_managed_entity \
 			 WHERE kind = $kind AND scope = $scope AND name = $name;",
 		)
@@ -613,16 +616,17 @@ pub async fn replace_managed_entities(
 	active_rollout_id: Option<&str>,
 	state: &str,
 ) -> Result<()> {
-	db.query("DELETE _surrealkit_managed_entity;")
+	sql_query(db, "DELETE _surrealkit_managed_entity;")
 		.await?
 		.check()?;
 	upsert_managed_entities(db, entities, active_rollout_id, state).await
 }
 
 pub async fn replace_sync_hashes(db: &Surreal<Any>, files: &[SchemaFile]) -> Result<()> {
-	db.query("DELETE _surrealkit_sync;").await?.check()?;
+	sql_query(db, "DELETE _surrealkit_sync;").await?.check()?;
 	for file in files {
-		db.query(
+		sql_query(
+			db,
 			"CREATE _surrealkit_sync CONTENT { path: $path, hash: $hash, synced_at: time::now() };",
 		)
 		.bind(("path", file.path.clone()))
@@ -635,7 +639,7 @@ pub async fn replace_sync_hashes(db: &Surreal<Any>, files: &[SchemaFile]) -> Res
 
 pub async fn delete_sync_hashes(db: &Surreal<Any>, paths: &[String]) -> Result<()> {
 	for path in paths {
-		db.query("DELETE _surrealkit_sync WHERE path = $path;")
+		sql_query(db, "DELETE _surrealkit_sync WHERE path = $path;")
 			.bind(("path", path.clone()))
 			.await?
 			.check()?;
@@ -885,7 +889,7 @@ async fn execute_step(db: &Surreal<Any>, step: &RolloutStep) -> Result<()> {
 				.expect
 				.as_deref()
 				.ok_or_else(|| anyhow!("missing expect"))?;
-			let actual = execute_sql_value(db, sql).await?;
+			let actual = query_json(db, sql).await?;
 			if value_to_expect_string(&actual) != expect.trim() {
 				bail!(
 					"assert step '{}' failed: expected {}, got {}",
@@ -906,12 +910,6 @@ async fn execute_step(db: &Surreal<Any>, step: &RolloutStep) -> Result<()> {
 	}
 }
 
-async fn execute_sql_value(db: &Surreal<Any>, sql: &str) -> Result<Value> {
-	let mut response = db.query(sql).await?.check()?;
-	let raw: surrealdb_types::Value = response.take(0)?;
-	Ok(Value::from_value(raw).unwrap_or(Value::Null))
-}
-
 fn value_to_expect_string(value: &Value) -> String {
 	match value {
 		Value::Null => "null".to_string(),
@@ -923,9 +921,7 @@ fn value_to_expect_string(value: &Value) -> String {
 }
 
 async fn rollout_rows_exist(db: &Surreal<Any>) -> Result<bool> {
-	let mut resp = db
-		.query("SELECT id FROM _surrealkit_rollout LIMIT 1;")
-		.await?;
+	let mut resp = sql_query(db, "SELECT id FROM _surrealkit_rollout LIMIT 1;").await?;
 	let row: Option<Value> = resp.take(0)?;
 	Ok(row.is_some())
 }
@@ -951,7 +947,8 @@ async fn create_rollout_record(
 	status: RolloutStatus,
 ) -> Result<()> {
 	let started_at = OffsetDateTime::now_utc().format(&Rfc3339)?;
-	db.query(
+	sql_query(
+		db,
 		"DELETE _surrealkit_rollout WHERE id = $id; \
 		 CREATE _surrealkit_rollout CONTENT { \
 		 	id: $id, \
@@ -990,10 +987,12 @@ async fn create_rollout_record(
 }
 
 async fn load_rollout_record(db: &Surreal<Any>, rollout_id: &str) -> Result<Option<Value>> {
-	let mut resp = db
-		.query("SELECT * FROM _surrealkit_rollout WHERE id = $id LIMIT 1;")
-		.bind(("id", rollout_id.to_string()))
-		.await?;
+	let mut resp = sql_query(
+		db,
+		"SELECT * FROM _surrealkit_rollout WHERE id = $id LIMIT 1;",
+	)
+	.bind(("id", rollout_id.to_string()))
+	.await?;
 	let row: Option<Value> = resp.take(0)?;
 	Ok(row)
 }
@@ -1031,7 +1030,8 @@ async fn set_rollout_status(
 	last_error: Option<&str>,
 	completed_at: Option<String>,
 ) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"UPDATE _surrealkit_rollout SET \
 		 	status = $status, \
 		 	last_error = $last_error, \
@@ -1053,14 +1053,14 @@ async fn step_already_completed(
 	rollout_id: &str,
 	step_id: &str,
 ) -> Result<bool> {
-	let mut resp = db
-		.query(
-			"SELECT status FROM _surrealkit_rollout_step \
+	let mut resp = sql_query(
+		db,
+		"SELECT status FROM _surrealkit_rollout_step \
 			 WHERE rollout_id = $rollout_id AND step_id = $step_id LIMIT 1;",
-		)
-		.bind(("rollout_id", rollout_id.to_string()))
-		.bind(("step_id", step_id.to_string()))
-		.await?;
+	)
+	.bind(("rollout_id", rollout_id.to_string()))
+	.bind(("step_id", step_id.to_string()))
+	.await?;
 	let row: Option<Value> = resp.take(0)?;
 	Ok(matches!(
 		row.as_ref()
@@ -1071,7 +1071,8 @@ async fn step_already_completed(
 }
 
 async fn record_step_start(db: &Surreal<Any>, rollout_id: &str, step: &RolloutStep) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"DELETE _surrealkit_rollout_step WHERE rollout_id = $rollout_id AND step_id = $step_id; \
 		 CREATE _surrealkit_rollout_step CONTENT { \
 		 	rollout_id: $rollout_id, \
@@ -1100,7 +1101,8 @@ async fn record_step_complete(
 	rollout_id: &str,
 	step: &RolloutStep,
 ) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"UPDATE _surrealkit_rollout_step SET \
 		 	status = 'completed', \
 		 	finished_at = time::now(), \
@@ -1120,7 +1122,8 @@ async fn record_step_failure(
 	step: &RolloutStep,
 	error: &str,
 ) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"UPDATE _surrealkit_rollout_step SET \
 		 	status = 'failed', \
 		 	finished_at = time::now(), \
@@ -1140,28 +1143,101 @@ fn step_checksum(step: &RolloutStep) -> Result<String> {
 	Ok(sha256_hex(&raw))
 }
 
-pub async fn acquire_lock(db: &Surreal<Any>, lock_key: &str) -> Result<()> {
+#[derive(Debug)]
+#[must_use = "locks must be released with `release`"]
+pub struct DbLock {
+	key: String,
+	lock_id: String,
+}
+
+impl DbLock {
+	pub async fn release(self, db: &Surreal<Any>) -> Result<()> {
+		sql_query(
+			db,
+			"DELETE _surrealkit_lock WHERE key = $key AND lock_id = $lock_id;",
+		)
+		.bind(("key", self.key))
+		.bind(("lock_id", self.lock_id))
+		.await?
+		.check()?;
+		Ok(())
+	}
+}
+
+pub async fn acquire_lock(db: &Surreal<Any>, lock_key: &str, timeout_secs: u64) -> Result<DbLock> {
 	let owner = std::env::var("SURREALKIT_OWNER").unwrap_or_else(|_| "surrealkit".to_string());
-	db.query(
-		"CREATE ONLY _surrealkit_lock:global CONTENT { \
+	let mut resp = sql_query(
+		db,
+		"SELECT lock_id, owner, created_at, \
+			 	time::unix(time::now()) - time::unix(created_at) AS age_secs \
+			 FROM _surrealkit_lock WHERE key = $key LIMIT 1;",
+	)
+	.bind(("key", lock_key.to_string()))
+	.await?;
+	let existing: Option<Value> = resp.take(0)?;
+	if let Some(stale) = existing.as_ref()
+		&& lock_is_stale(lock_key, stale, timeout_secs)?
+	{
+		eprintln!(
+			"warning: replacing stale lock '{}' older than {}s",
+			lock_key, timeout_secs
+		);
+		sql_query(
+			db,
+			"DELETE _surrealkit_lock WHERE key = $key AND lock_id = $lock_id;",
+		)
+		.bind(("key", lock_key.to_string()))
+		.bind(("lock_id", string_field(stale, "lock_id")))
+		.await?
+		.check()?;
+	}
+
+	let lock_id = format!(
+		"{}-{}",
+		std::process::id(),
+		OffsetDateTime::now_utc().unix_timestamp_nanos()
+	);
+	sql_query(
+		db,
+		"CREATE _surrealkit_lock CONTENT { \
 		 	key: $key, \
+		 	lock_id: $lock_id, \
 		 	owner: $owner, \
 		 	created_at: time::now() \
 		 };",
 	)
 	.bind(("key", lock_key.to_string()))
+	.bind(("lock_id", lock_id.clone()))
 	.bind(("owner", owner))
 	.await?
-	.check()?;
-	Ok(())
+	.check()
+	.with_context(|| {
+		format!("lock '{lock_key}' was acquired concurrently by another SurrealKit run")
+	})?;
+	Ok(DbLock {
+		key: lock_key.to_string(),
+		lock_id,
+	})
 }
 
-pub async fn release_lock(db: &Surreal<Any>, _lock_key: &str) -> Result<()> {
-	db.query("DELETE _surrealkit_lock:global;").await?.check()?;
-	Ok(())
+/// Errors while an existing lock is younger than the timeout; otherwise it is stale and may be replaced.
+fn lock_is_stale(lock_key: &str, row: &Value, timeout_secs: u64) -> Result<bool> {
+	let age_secs = row.get("age_secs").and_then(Value::as_i64).unwrap_or(0);
+	if age_secs >= 0 && (age_secs as u64) < timeout_secs {
+		bail!(
+			"lock '{}' is held by '{}' since {} ({}s ago); another SurrealKit run may be in progress. \
+Retry later, or wait {}s (--lock-timeout-secs) for it to expire.",
+			lock_key,
+			string_field(row, "owner").unwrap_or_else(|| "<unknown>".to_string()),
+			string_field(row, "created_at").unwrap_or_else(|| "<unknown>".to_string()),
+			age_secs,
+			timeout_secs
+		);
+	}
+	Ok(true)
 }
 
-fn slugify(input: &str) -> String {
+pub fn slugify(input: &str) -> String {
 	let mut out = String::new();
 	let mut prev_dash = false;
 	for ch in input.chars() {
@@ -1293,6 +1369,21 @@ mod tests {
 		}));
 	}
 
+	#[test]
+	fn existing_lock_blocks_until_timeout() {
+		let fresh = serde_json::json!({
+			"lock_id": "1-1",
+			"owner": "ci",
+			"created_at": "2026-01-01T00:00:00Z",
+			"age_secs": 12
+		});
+		let err = lock_is_stale("global", &fresh, 300).expect_err("lock is held");
+		assert!(err.to_string().contains("is held by 'ci'"));
+
+		let stale = serde_json::json!({ "lock_id": "1-1", "owner": "ci", "age_secs": 301 });
+		assert!(lock_is_stale("global", &stale, 300).expect("stale lock"));
+	}
+
 	#[test]
 	fn rollout_lint_rejects_non_idempotent_run_sql() {
 		let spec = RolloutSpec {
@@ -206,6 +206,9 @@ DEFINE TABLE OVERWRITE _surrealkit_lock SCHEMAFULL
 DEFINE FIELD OVERWRITE key ON _surrealkit_lock
 	TYPE string;
 
+DEFINE FIELD OVERWRITE lock_id ON _surrealkit_lock
+	TYPE option<string>;
+
 DEFINE FIELD OVERWRITE owner ON _surrealkit_lock
 	TYPE string;
 
@@ -6,10 +6,11 @@ use anyhow::{Context, Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
-use crate::core::sha256_hex;
+use crate::core::{glob_match, sha256_hex};
 
 pub const SCHEMA_DIR: &str = "database/schema";
 pub const ROLLOUTS_DIR: &str = "database/rollouts";
+pub const MIGRATIONS_DIR: &str = "database/migrations";
 pub const STATE_DIR: &str = "database/.surrealkit";
 pub const SCHEMA_SNAPSHOT_PATH: &str = "database/.surrealkit/schema_snapshot.json";
 pub const CATALOG_SNAPSHOT_PATH: &str = "database/.surrealkit/catalog_snapshot.json";
@@ -70,6 +71,13 @@ pub struct CatalogDiff {
 	pub modified: Vec<CatalogChange>,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LiveCatalogDiff {
+	pub added: Vec<EntityKey>,
+	pub modified: Vec<EntityKey>,
+	pub removed: Vec<EntityKey>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CatalogChange {
 	pub old: CatalogEntity,
@@ -221,8 +229,16 @@ pub fn build_catalog_snapshot(files: &[SchemaFile]) -> Result<CatalogSnapshot> {
 }
 
 pub fn parse_schema_statements(file: &SchemaFile) -> Result<Vec<CatalogEntity>> {
+	Ok(parse_schema_definitions(file)?
+		.into_iter()
+		.map(|(entity, _)| entity)
+		.collect())
+}
+
+/// Parses each DEFINE into its entity, alongside the normalized statement it was hashed from.
+fn parse_schema_definitions(file: &SchemaFile) -> Result<Vec<(CatalogEntity, String)>> {
 	let mut entities = Vec::new();
-	for stmt in split_statements(&strip_line_comments(&file.sql)) {
+	for stmt in split_statements(&file.sql) {
 		let normalized = stmt.trim();
 		if normalized.is_empty() {
 			continue;
@@ -250,8 +266,9 @@ pub fn parse_schema_statements(file: &SchemaFile) -> Result<Vec<CatalogEntity>>
 		};
 		entity.source_path = file.path.clone();
 		entity.file_hash = file.hash.clone();
-		entity.statement_hash = sha256_hex(normalize_statement(normalized).as_bytes());
-		entities.push(entity);
+		let statement = normalize_statement(normalized);
+		entity.statement_hash = sha256_hex(statement.as_bytes());
+		entities.push((entity, statement));
 	}
 	Ok(entities)
 }
@@ -295,6 +312,128 @@ pub fn diff_catalog(old: &CatalogSnapshot, new: &CatalogSnapshot) -> CatalogDiff
 	diff
 }
 
+/// Pairs each removed entity with an added entity of the same kind and scope whose DEFINE
+/// statement is identical apart from the name, so callers can avoid a drop and recreate.
+pub fn detect_renames(
+	removed: &[CatalogEntity],
+	added: &BTreeSet<EntityKey>,
+	files: &[SchemaFile],
+) -> Result<Vec<(EntityKey, EntityKey)>> {
+	let mut renames = Vec::new();
+	let mut matched = BTreeSet::new();
+	for file in files {
+		for (entity, statement) in parse_schema_definitions(file)? {
+			if !added.contains(&entity.key()) {
+				continue;
+			}
+			let candidate = removed.iter().find(|old| {
+				old.kind == entity.kind
+					&& old.scope == entity.scope
+					&& !matched.contains(&old.key())
+					&& sha256_hex(
+						rename_in_statement(&statement, &entity.name, &old.name).as_bytes(),
+					) == old.statement_hash
+			});
+			if let Some(old) = candidate {
+				matched.insert(old.key());
+				renames.push((old.key(), entity.key()));
+			}
+		}
+	}
+	Ok(renames)
+}
+
+/// Rewrites the entity name in a normalized DEFINE statement, leaving the rest untouched.
+fn rename_in_statement(statement: &str, from: &str, to: &str) -> String {
+	let mut tokens: Vec<String> = tokenize(statement).into_iter().map(String::from).collect();
+	if let Some(token) = tokens
+		.iter_mut()
+		.skip(2)
+		.find(|token| clean_ident(token) == from)
+	{
+		*token = token.replacen(from, to, 1);
+	}
+	normalize_statement(&tokens.join(" "))
+}
+
+/// Parses a definition returned by `INFO FOR ...` into the key its local DEFINE would produce.
+pub fn parse_definition_key(stmt: &str) -> Option<EntityKey> {
+	parse_define_entity(stmt.trim()).map(|entity| entity.key())
+}
+
+/// Compares local desired state with entities defined on the server. The server re-renders
+/// definitions, so modifications are detected against the statement hash sync last applied.
+pub fn diff_live_catalog(
+	local: &CatalogSnapshot,
+	live: &BTreeSet<EntityKey>,
+	managed: &BTreeMap<EntityKey, String>,
+) -> LiveCatalogDiff {
+	let local_map = catalog_snapshot_to_map(local);
+	let mut diff = LiveCatalogDiff::default();
+
+	for (key, entity) in &local_map {
+		if !live.contains(key) {
+			diff.added.push(key.clone());
+		} else if managed
+			.get(key)
+			.is_some_and(|hash| hash != &entity.statement_hash)
+		{
+			diff.modified.push(key.clone());
+		}
+	}
+	for key in live {
+		if managed.contains_key(key) && !local_map.contains_key(key) {
+			diff.removed.push(key.clone());
+		}
+	}
+	diff
+}
+
+/// Entities SurrealKit does not own and must never remove, e.g. a table created by another
+/// service. Patterns are `<kind>:<name>` globs; fields, events and indexes are named
+/// `<table>.<name>` and are also covered by an ignored table.
+#[derive(Debug, Clone, Default)]
+pub struct SyncIgnore {
+	patterns: Vec<(String, String)>,
+}
+
+impl SyncIgnore {
+	pub fn parse(entries: &[String]) -> Result<Self> {
+		let patterns = entries
+			.iter()
+			.map(|entry| match entry.split_once(':') {
+				Some((kind, name)) if !kind.is_empty() && !name.is_empty() => {
+					Ok((kind.to_string(), name.to_string()))
+				}
+				_ => bail!("invalid sync ignore pattern '{entry}': expected <kind>:<name>"),
+			})
+			.collect::<Result<_>>()?;
+		Ok(Self { patterns })
+	}
+
+	pub fn matches(&self, key: &EntityKey) -> bool {
+		let table_scoped = matches!(key.kind.as_str(), "field" | "event" | "index");
+		let name = match (&key.scope, table_scoped) {
+			(Some(scope), true) => format!("{}.{}", scope, key.name),
+			_ => key.name.clone(),
+		};
+		self.patterns.iter().any(|(kind, pattern)| {
+			(glob_match(kind, &key.kind) && glob_match(pattern, &name))
+				|| (table_scoped
+					&& glob_match(kind, "table")
+					&& key
+						.scope
+						.as_deref()
+						.is_some_and(|table| glob_match(pattern, table)))
+		})
+	}
+
+	/// Splits `entities` into those that may be removed and those that are ignored.
+	pub fn partition(&self, entities: Vec<EntityKey>) -> (Vec<EntityKey>, Vec<EntityKey>) {
+		entities.into_iter().partition(|key| !self.matches(key))
+	}
+}
+
 pub fn render_remove_sql(entities: &[EntityKey], api_supported: bool) -> Result<Vec<String>> {
 	let mut ordered = entities.to_vec();
 	ordered.sort_by_key(removal_sort_key);
@@ -304,30 +443,32 @@ pub fn render_remove_sql(entities: &[EntityKey], api_supported: bool) -> Result<
 		let stmt = match entity.kind.as_str() {
 			"field" => format!(
 				"REMOVE FIELD {} ON {};",
-				entity.name,
+				quote_ident(&entity.name),
 				scope_or_err(&entity, "FIELD")?
 			),
 			"event" => format!(
 				"REMOVE EVENT {} ON {};",
-				entity.name,
+				quote_ident(&entity.name),
 				scope_or_err(&entity, "EVENT")?
 			),
 			"index" => format!(
 				"REMOVE INDEX {} ON {};",
-				entity.name,
+				quote_ident(&entity.name),
 				scope_or_err(&entity, "INDEX")?
 			),
-			"table" => format!("REMOVE TABLE {};", entity.name),
+			"table" => format!("REMOVE TABLE {};", quote_ident(&entity.name)),
 			"function" => format!("REMOVE FUNCTION {};", entity.name),
 			"param" => format!("REMOVE PARAM {};", entity.name),
 			"access" => match &entity.scope {
-				Some(scope) => format!("REMOVE ACCESS {} ON {};", entity.name, scope),
-				None => format!("REMOVE ACCESS {};", entity.name),
+				Some(scope) => format!("REMOVE ACCESS {} ON {};", quote_ident(&entity.name), scope),
+				None => format!("REMOVE ACCESS {};", quote_ident(&entity.name)),
 			},
-			"analyzer" => format!("REMOVE ANALYZER {};", entity.name),
+			"analyzer" => format!("REMOVE ANALYZER {};", quote_ident(&entity.name)),
+			"model" => format!("REMOVE MODEL {};", entity.name),
+			"config" => format!("REMOVE CONFIG {};", entity.name),
 			"user" => match &entity.scope {
-				Some(scope) => format!("REMOVE USER {} ON {};", entity.name, scope),
-				None => format!("REMOVE USER {};", entity.name),
+				Some(scope) => format!("REMOVE USER {} ON {};", quote_ident(&entity.name), scope),
+				None => format!("REMOVE USER {};", quote_ident(&entity.name)),
 			},
 			"api" => {
 				if api_supported {
@@ -348,7 +489,7 @@ Use a manual migration or upgrade server support.",
 }
 
 fn scope_or_err(entity: &EntityKey, object: &str) -> Result<String> {
-	entity.scope.clone().ok_or_else(|| {
+	entity.scope.as_deref().map(quote_ident).ok_or_else(|| {
 		anyhow!(
 			"cannot render REMOVE {} for '{}' because scope is missing",
 			object,
@@ -367,9 +508,11 @@ fn removal_sort_key(entity: &EntityKey) -> (usize, Option<String>, String, Strin
 		"function" => 5,
 		"param" => 6,
 		"api" => 7,
-		"analyzer" => 8,
-		"table" => 9,
-		_ => 10,
+		"config" => 8,
+		"model" => 9,
+		"analyzer" => 10,
+		"table" => 11,
+		_ => 12,
 	};
 	(
 		weight,
@@ -412,17 +555,8 @@ where
 	Ok(())
 }
 
-fn strip_line_comments(sql: &str) -> String {
-	sql.lines()
-		.filter(|line| {
-			let t = line.trim_start();
-			!(t.starts_with("--") || t.starts_with("//"))
-		})
-		.collect::<Vec<_>>()
-		.join("\n")
-}
-
-fn split_statements(sql: &str) -> Vec<String> {
+/// Splits on top-level `;`, dropping `--`, `//` and `/* */` comments outside quoted text.
+pub fn split_statements(sql: &str) -> Vec<String> {
 	let mut out = Vec::new();
 	let mut buf = String::new();
 	let mut in_single = false;
@@ -430,15 +564,44 @@ fn split_statements(sql: &str) -> Vec<String> {
 	let mut in_backtick = false;
 	let mut prev_escape = false;
 	let mut brace_depth = 0usize;
+	let mut chars = sql.chars().peekable();
+
+	while let Some(ch) = chars.next() {
+		let quoted = in_single || in_double || in_backtick;
+		if !quoted {
+			match (ch, chars.peek()) {
+				('-', Some('-')) | ('/', Some('/')) => {
+					for next in chars.by_ref() {
+						if next == '\n' {
+							buf.push('\n');
+							break;
+						}
+					}
+					continue;
+				}
+				('/', Some('*')) => {
+					chars.next();
+					let mut prev = '\0';
+					for next in chars.by_ref() {
+						if prev == '*' && next == '/' {
+							break;
+						}
+						prev = next;
+					}
+					buf.push(' ');
+					continue;
+				}
+				_ => {}
+			}
+		}
 
-	for ch in sql.chars() {
 		match ch {
 			'\'' if !in_double && !in_backtick && !prev_escape => in_single = !in_single,
 			'"' if !in_single && !in_backtick && !prev_escape => in_double = !in_double,
 			'`' if !in_single && !in_double && !prev_escape => in_backtick = !in_backtick,
-			'{' if !in_single && !in_double && !in_backtick => brace_depth += 1,
-			'}' if !in_single && !in_double && !in_backtick && brace_depth > 0 => brace_depth -= 1,
-			';' if !in_single && !in_double && !in_backtick && brace_depth == 0 => {
+			'{' if !quoted => brace_depth += 1,
+			'}' if !quoted && brace_depth > 0 => brace_depth -= 1,
+			';' if !quoted && brace_depth == 0 => {
 				let stmt = buf.trim();
 				if !stmt.is_empty() {
 					out.push(stmt.to_string());
@@ -490,6 +653,8 @@ fn parse_define_entity(stmt: &str) -> Option<CatalogEntity> {
 			(Some(clean_ident(tokens[scope_idx])), name)
 		}
 		"function" | "param" | "analyzer" | "api" => (None, clean_ident(tokens[idx])),
+		"model" => (None, parse_model_name(&clean_ident(tokens[idx]))?),
+		"config" => (None, clean_ident(tokens[idx]).to_ascii_uppercase()),
 		"access" | "user" => {
 			let name = clean_ident(tokens[idx]);
 			let scope = find_token(&tokens, idx + 1, "ON").and_then(|on_idx| {
@@ -515,11 +680,44 @@ fn parse_define_entity(stmt: &str) -> Option<CatalogEntity> {
 	})
 }
 
-fn tokenize(stmt: &str) -> Vec<&str> {
-	stmt.split_whitespace().collect()
+/// Splits on whitespace, keeping `` `...` `` and `⟨...⟩` quoted identifiers in one token.
+pub fn tokenize(stmt: &str) -> Vec<&str> {
+	let mut tokens = Vec::new();
+	let mut start = None;
+	let mut close = None;
+	for (i, ch) in stmt.char_indices() {
+		match close {
+			Some(end) if ch == end => close = None,
+			Some(_) => {}
+			None if ch.is_whitespace() => {
+				if let Some(s) = start.take() {
+					tokens.push(&stmt[s..i]);
+				}
+			}
+			None => {
+				start.get_or_insert(i);
+				close = match ch {
+					'`' => Some('`'),
+					'⟨' => Some('⟩'),
+					_ => None,
+				};
+			}
+		}
+	}
+	if let Some(s) = start {
+		tokens.push(&stmt[s..]);
+	}
+	tokens
 }
 
 fn clean_ident(token: &str) -> String {
+	for (open, close) in [('`', '`'), ('⟨', '⟩')] {
+		if let Some(rest) = token.strip_prefix(open)
+			&& let Some(end) = rest.find(close)
+		{
+			return rest[..end].to_string();
+		}
+	}
 	let trimmed = token.trim_matches(|c: char| {
 		c == ',' || c == ';' || c == '(' || c == ')' || c == '{' || c == '}'
 	});
@@ -530,6 +728,28 @@ fn clean_ident(token: &str) -> String {
 	core.to_string()
 }
 
+/// Backtick-quotes names that would not parse as bare identifiers in `REMOVE` SQL.
+fn quote_ident(name: &str) -> String {
+	let bare = name
+		.chars()
+		.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '[' | ']' | '*'));
+	if bare && !name.is_empty() {
+		name.to_string()
+	} else {
+		format!("`{}`", name.replace('`', "\\`"))
+	}
+}
+
+/// Accepts `ml::<name><<version>>`; anything else is skipped rather than guessed at.
+fn parse_model_name(ident: &str) -> Option<String> {
+	let rest = ident.strip_prefix("ml::")?;
+	let (name, version) = rest.strip_suffix('>')?.split_once('<')?;
+	if name.is_empty() || version.is_empty() || version.contains(['<', '>']) {
+		return None;
+	}
+	Some(ident.to_string())
+}
+
 fn skip_modifiers(tokens: &[&str], mut idx: usize) -> usize {
 	while idx < tokens.len()
 		&& (eq(tokens[idx], "OVERWRITE")
@@ -615,6 +835,94 @@ mod tests {
 		assert_eq!(diff.removed, vec!["database/schema/a.surql"]);
 	}
 
+	#[test]
+	fn live_diff_uses_managed_hashes_for_modifications() {
+		let entity = |kind: &str, scope: Option<&str>, name: &str, hash: &str| CatalogEntity {
+			kind: kind.to_string(),
+			scope: scope.map(str::to_string),
+			name: name.to_string(),
+			source_path: "database/schema/person.surql".to_string(),
+			statement_hash: hash.to_string(),
+			file_hash: "f".to_string(),
+		};
+		let local = CatalogSnapshot {
+			version: 2,
+			entities: vec![
+				entity("table", None, "person", "t1"),
+				entity("field", Some("person"), "name", "n2"),
+				entity("field", Some("person"), "email", "e1"),
+			],
+		};
+		let live: BTreeSet<EntityKey> = [
+			"DEFINE TABLE person TYPE NORMAL SCHEMAFULL PERMISSIONS NONE",
+			"DEFINE FIELD name ON person TYPE string PERMISSIONS FULL",
+			"DEFINE FIELD legacy ON person TYPE string PERMISSIONS FULL",
+			"DEFINE FIELD notes ON person TYPE string PERMISSIONS FULL",
+		]
+		.into_iter()
+		.filter_map(parse_definition_key)
+		.collect();
+		let managed: BTreeMap<EntityKey, String> = [
+			entity("table", None, "person", "t1"),
+			entity("field", Some("person"), "name", "n1"),
+			entity("field", Some("person"), "legacy", "l1"),
+		]
+		.into_iter()
+		.map(|e| (e.key(), e.statement_hash))
+		.collect();
+
+		let diff = diff_live_catalog(&local, &live, &managed);
+		let names = |keys: &[EntityKey]| keys.iter().map(|k| k.name.clone()).collect::<Vec<_>>();
+		assert_eq!(names(&diff.added), vec!["email"]);
+		assert_eq!(names(&diff.modified), vec!["name"]);
+		assert_eq!(names(&diff.removed), vec!["legacy"]);
+	}
+
+	#[test]
+	fn ignored_live_entities_are_left_out_of_the_prune_set() {
+		let entity = |kind: &str, scope: Option<&str>, name: &str, hash: &str| CatalogEntity {
+			kind: kind.to_string(),
+			scope: scope.map(str::to_string),
+			name: name.to_string(),
+			source_path: "database/schema/audit.surql".to_string(),
+			statement_hash: hash.to_string(),
+			file_hash: "f".to_string(),
+		};
+		let local = CatalogSnapshot {
+			version: 2,
+			entities: vec![entity("table", None, "person", "t1")],
+		};
+		let live: BTreeSet<EntityKey> = [
+			"DEFINE TABLE person TYPE NORMAL SCHEMALESS PERMISSIONS NONE",
+			"DEFINE TABLE audit_log TYPE NORMAL SCHEMALESS PERMISSIONS NONE",
+			"DEFINE FIELD actor ON audit_log TYPE string PERMISSIONS FULL",
+			"DEFINE TABLE old_posts TYPE NORMAL SCHEMALESS PERMISSIONS NONE",
+		]
+		.into_iter()
+		.filter_map(parse_definition_key)
+		.collect();
+		let managed: BTreeMap<EntityKey, String> = [
+			entity("table", None, "person", "t1"),
+			entity("table", None, "audit_log", "a1"),
+			entity("field", Some("audit_log"), "actor", "a2"),
+			entity("table", None, "old_posts", "o1"),
+		]
+		.into_iter()
+		.map(|e| (e.key(), e.statement_hash))
+		.collect();
+
+		let removed = diff_live_catalog(&local, &live, &managed).removed;
+		assert_eq!(removed.len(), 3);
+		let ignore = SyncIgnore::parse(&["table:audit_*".to_string()]).expect("patterns");
+		let (prune, ignored) = ignore.partition(removed);
+		assert_eq!(
+			prune.iter().map(|k| k.name.as_str()).collect::<Vec<_>>(),
+			vec!["old_posts"]
+		);
+		assert_eq!(ignored.len(), 2, "fields of an ignored table are kept too");
+		assert!(SyncIgnore::parse(&["audit_log".to_string()]).is_err());
+	}
+
 	#[test]
 	fn catalog_extracts_supported_entities() {
 		let files = vec![SchemaFile {
@@ -631,6 +939,8 @@ mod tests {
 				DEFINE ANALYZER english TOKENIZERS blank, class;
 				DEFINE USER app ON DATABASE PASSHASH "x";
 				DEFINE API v1;
+				DEFINE MODEL ml::recommender<1.0.0> COMMENT "x";
+				DEFINE CONFIG graphql AUTO;
 			"#
 			.to_string(),
 		}];
@@ -656,6 +966,101 @@ mod tests {
 				.iter()
 				.any(|entity| entity.kind == "api" && entity.name == "v1")
 		);
+
+		let models: Vec<_> = catalog
+			.entities
+			.iter()
+			.filter(|entity| entity.kind == "model")
+			.map(|entity| entity.key())
+			.collect();
+		assert_eq!(models.len(), 1);
+		assert_eq!(models[0].name, "ml::recommender<1.0.0>");
+		assert!(parse_definition_key("DEFINE MODEL ml::broken COMMENT 'x'").is_none());
+		assert!(parse_definition_key("DEFINE MODEL ml::<1.0.0>").is_none());
+		let config = catalog
+			.entities
+			.iter()
+			.find(|entity| entity.kind == "config")
+			.expect("config entity");
+		assert_eq!(config.name, "GRAPHQL");
+
+		let sql = render_remove_sql(&[models[0].clone(), config.key()], true).expect("render");
+		assert_eq!(
+			sql,
+			vec![
+				"REMOVE CONFIG GRAPHQL;",
+				"REMOVE MODEL ml::recommender<1.0.0>;"
+			]
+		);
+	}
+
+	#[test]
+	fn renamed_entities_are_paired_by_statement_body() {
+		let old_files = vec![SchemaFile {
+			path: "database/schema/person.surql".to_string(),
+			hash: "old".to_string(),
+			sql: "DEFINE TABLE person SCHEMAFULL;\n\
+				DEFINE FIELD nickname ON person TYPE option<string>;\n\
+				DEFINE FIELD age ON person TYPE int;"
+				.to_string(),
+		}];
+		let new_files = vec![SchemaFile {
+			path: "database/schema/person.surql".to_string(),
+			hash: "new".to_string(),
+			sql: "DEFINE TABLE person SCHEMAFULL;\n\
+				DEFINE FIELD alias ON person TYPE option<string>;\n\
+				DEFINE FIELD years ON person TYPE number;"
+				.to_string(),
+		}];
+
+		let diff = diff_catalog(
+			&build_catalog_snapshot(&old_files).expect("old catalog"),
+			&build_catalog_snapshot(&new_files).expect("new catalog"),
+		);
+		let added = diff.added.iter().map(CatalogEntity::key).collect();
+		let renames = detect_renames(&diff.removed, &added, &new_files).expect("renames");
+
+		assert_eq!(renames.len(), 1);
+		assert_eq!(renames[0].0.name, "nickname");
+		assert_eq!(renames[0].1.name, "alias");
+	}
+
+	#[test]
+	fn catalog_keeps_quoted_identifiers_whole() {
+		let files = vec![SchemaFile {
+			path: "database/schema/quoted.surql".to_string(),
+			hash: "x".to_string(),
+			sql: "DEFINE TABLE `my table` SCHEMALESS;\n\
+				DEFINE FIELD `my field` ON TABLE `my table` TYPE string;\n\
+				DEFINE TABLE ⟨select⟩ SCHEMALESS;\n\
+				DEFINE INDEX ⟨by name⟩ ON ⟨select⟩ FIELDS name;"
+				.to_string(),
+		}];
+
+		let catalog = build_catalog_snapshot(&files).expect("catalog build");
+		let keys: Vec<_> = catalog
+			.entities
+			.iter()
+			.map(|entity| {
+				(
+					entity.kind.as_str(),
+					entity.scope.as_deref(),
+					entity.name.as_str(),
+				)
+			})
+			.collect();
+		assert!(keys.contains(&("table", None, "my table")));
+		assert!(keys.contains(&("field", Some("my table"), "my field")));
+		assert!(keys.contains(&("table", None, "select")));
+		assert!(keys.contains(&("index", Some("select"), "by name")));
+
+		let field = catalog
+			.entities
+			.iter()
+			.find(|entity| entity.kind == "field")
+			.expect("field entity");
+		let sql = render_remove_sql(&[field.key()], true).expect("render");
+		assert_eq!(sql, vec!["REMOVE FIELD `my field` ON `my table`;"]);
 	}
 
 	#[test]
@@ -752,4 +1157,38 @@ mod tests {
 		assert_eq!(snap.files[0].path, "database/schema/a.surql");
 		assert_eq!(snap.files[1].path, "database/schema/z.surql");
 	}
+
+	#[test]
+	fn split_statements_ignores_comment_markers_inside_strings() {
+		let sql = "DEFINE FIELD note ON person VALUE 'a -- b; c // d /* e */';\n\
+			DEFINE FIELD url ON person DEFAULT \"http://x;y\"; -- trailing; comment\n";
+		let stmts = split_statements(sql);
+		assert_eq!(
+			stmts,
+			vec![
+				"DEFINE FIELD note ON person VALUE 'a -- b; c // d /* e */'",
+				"DEFINE FIELD url ON person DEFAULT \"http://x;y\"",
+			]
+		);
+	}
+
+	#[test]
+	fn split_statements_skips_block_and_line_comments() {
+		let sql = "/* header; DEFINE TABLE ghost; */\n\
+			DEFINE TABLE person SCHEMAFULL; // inline; note\n\
+			-- DEFINE TABLE hidden;\n\
+			DEFINE /* kind; */ INDEX by_name ON person FIELDS name;";
+		let file = SchemaFile {
+			path: "database/schema/person.surql".to_string(),
+			sql: sql.to_string(),
+			hash: "h".to_string(),
+		};
+
+		let names: Vec<_> = parse_schema_statements(&file)
+			.expect("statements should parse")
+			.into_iter()
+			.map(|entity| entity.name)
+			.collect();
+		assert_eq!(names, vec!["person", "by_name"]);
+	}
 }
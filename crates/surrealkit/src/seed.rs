@@ -3,14 +3,162 @@ use std::{fs, path::Path};
 use surrealdb::{Surreal, engine::any::Any};
 
 use crate::core::{display, exec_surql};
+use crate::schema_state::{split_statements, tokenize};
 
-pub async fn seed(db: &Surreal<Any>) -> Result<()> {
-	let path = Path::new("database/seed.surql");
+pub const SEED_PATH: &str = "database/seed.surql";
 
+/// Record id generators that make `UPSERT` create a new record on every run.
+const ID_GENERATORS: &[&str] = &["rand()", "ulid()", "uuid()"];
+
+pub async fn seed(db: &Surreal<Any>, upsert: bool) -> Result<()> {
+	let sql = read_seed(Path::new(SEED_PATH), upsert)?;
+	exec_surql(db, &sql).await
+}
+
+pub fn seed_dry_run(upsert: bool) -> Result<()> {
+	print!("{}", render_dry_run(Path::new(SEED_PATH), upsert)?);
+	Ok(())
+}
+
+fn read_seed(path: &Path, upsert: bool) -> Result<String> {
 	if !path.exists() {
 		return Err(anyhow!("seed file not found: {}", display(path)));
 	}
 
 	let sql = fs::read_to_string(path)?;
-	exec_surql(db, &sql).await
+	if !upsert {
+		return Ok(sql);
+	}
+	let (sql, warnings) = rewrite_creates_as_upserts(&sql);
+	for warning in warnings {
+		eprintln!("warning: {warning}");
+	}
+	Ok(sql)
+}
+
+/// Rewrites top-level `CREATE table:id ...` statements as `UPSERT` so the seed can run
+/// repeatedly. Other statements are kept; `CREATE`s without a fixed record id are kept
+/// with a warning.
+fn rewrite_creates_as_upserts(sql: &str) -> (String, Vec<String>) {
+	let mut out = String::new();
+	let mut warnings = Vec::new();
+	for stmt in split_statements(sql) {
+		let tokens = tokenize(&stmt);
+		if tokens
+			.first()
+			.is_some_and(|keyword| keyword.eq_ignore_ascii_case("CREATE"))
+		{
+			let target_idx = match tokens.get(1) {
+				Some(token) if token.eq_ignore_ascii_case("ONLY") => 2,
+				_ => 1,
+			};
+			if fixed_record_target(&tokens[target_idx..]) {
+				out.push_str("UPSERT");
+				out.push_str(&stmt["CREATE".len()..]);
+				out.push_str(";\n");
+				continue;
+			}
+			warnings.push(format!(
+				"left CREATE without a fixed record id unchanged: {}",
+				stmt.lines().next().unwrap_or_default()
+			));
+		}
+		out.push_str(&stmt);
+		out.push_str(";\n");
+	}
+	(out, warnings)
+}
+
+/// Whether the statement targets exactly one `table:id` that is not generated.
+fn fixed_record_target(tokens: &[&str]) -> bool {
+	let Some(target) = tokens.first() else {
+		return false;
+	};
+	let Some((table, id)) = target.split_once(':') else {
+		return false;
+	};
+	!table.is_empty()
+		&& !id.is_empty()
+		&& !id.starts_with(':')
+		&& !target.ends_with(',')
+		&& !tokens.get(1).is_some_and(|next| next.starts_with(','))
+		&& !ID_GENERATORS.contains(&id.trim_end_matches(','))
+}
+
+fn render_dry_run(path: &Path, upsert: bool) -> Result<String> {
+	let sql = read_seed(path, upsert)?;
+	let mut out = format!("DRY RUN: would apply {}\n", display(path));
+	for line in sql.trim_end().lines() {
+		out.push_str("  ");
+		out.push_str(line);
+		out.push('\n');
+	}
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dry_run_prints_seed_sql_without_executing() {
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let path = dir.path().join("seed.surql");
+		fs::write(&path, "CREATE person:alice SET name = 'Alice';\n").expect("write seed");
+
+		let out = render_dry_run(&path, false).expect("dry run should render");
+		assert!(out.starts_with("DRY RUN: would apply "));
+		assert!(out.contains("  CREATE person:alice SET name = 'Alice';"));
+	}
+
+	#[test]
+	fn dry_run_reports_missing_seed_file() {
+		let err = render_dry_run(Path::new("database/does_not_exist.surql"), false)
+			.expect_err("missing file should error");
+		assert!(err.to_string().contains("seed file not found"));
+	}
+
+	#[test]
+	fn upsert_rewrites_only_fixed_id_creates() {
+		let sql = "CREATE person:alice CONTENT { name: 'Alice' };\n\
+			create ONLY `person`:bob SET name = 'CREATE person:x';\n\
+			CREATE person SET name = 'anon';\n\
+			CREATE person:rand() SET name = 'random';\n\
+			CREATE person:a, person:b;\n\
+			UPDATE person SET seen = true;";
+		let (out, warnings) = rewrite_creates_as_upserts(sql);
+		assert_eq!(
+			out,
+			"UPSERT person:alice CONTENT { name: 'Alice' };\n\
+			UPSERT ONLY `person`:bob SET name = 'CREATE person:x';\n\
+			CREATE person SET name = 'anon';\n\
+			CREATE person:rand() SET name = 'random';\n\
+			CREATE person:a, person:b;\n\
+			UPDATE person SET seen = true;\n"
+		);
+		assert_eq!(warnings.len(), 3, "{warnings:?}");
+		assert!(warnings[0].contains("CREATE person SET name = 'anon'"));
+	}
+
+	#[tokio::test]
+	async fn upsert_seed_can_run_twice() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+
+		let (sql, _) = rewrite_creates_as_upserts("CREATE person:alice CONTENT { name: 'Alice' };");
+		for _ in 0..2 {
+			exec_surql(&db, &sql).await.expect("seed should apply");
+		}
+		let mut resp = db
+			.query("SELECT count() AS count FROM person GROUP ALL;")
+			.await
+			.expect("count");
+		let rows: Vec<serde_json::Value> = resp.take(0).expect("rows");
+		assert_eq!(rows, vec![serde_json::json!({ "count": 1 })]);
+	}
 }
@@ -1,8 +1,21 @@
-use anyhow::{Context, Result};
-use std::{fs, path::Path};
+use anyhow::{Context, Result, bail};
+use std::{fs, io::ErrorKind, path::Path};
 use surrealdb::{Surreal, engine::any::Any};
 
+use crate::core::{confirm, sql_query};
 use crate::scaffold::DEFAULT_SETUP;
+use crate::schema_state::{CATALOG_SNAPSHOT_PATH, SCHEMA_SNAPSHOT_PATH};
+use crate::sync::detect_shared_db;
+
+pub const SURREALKIT_TABLES: &[&str] = &[
+	"_surrealkit_sync",
+	"_surrealkit_sync_meta",
+	"_surrealkit_managed_entity",
+	"_surrealkit_rollout",
+	"_surrealkit_rollout_step",
+	"_surrealkit_lock",
+	"_surrealkit_apply",
+];
 
 pub async fn run_setup(db: &Surreal<Any>) -> Result<()> {
 	let setup_file = Path::new("database/setup.surql");
@@ -21,11 +34,91 @@ pub async fn run_setup(db: &Surreal<Any>) -> Result<()> {
 	let sql =
 		fs::read_to_string(setup_file).with_context(|| format!("reading {:?}", setup_file))?;
 
-	db.query(&sql).await?.check()?;
-	db.query(EXTRA_SETUP).await?.check()?;
+	sql_query(db, &sql).await?.check()?;
+	sql_query(db, EXTRA_SETUP).await?.check()?;
+	sql_query(db, APPLY_SETUP).await?.check()?;
 	Ok(())
 }
 
+pub async fn reset_surrealkit_state(db: &Surreal<Any>, confirm: bool) -> Result<()> {
+	if !confirm {
+		println!("Would remove these tables (rerun with --confirm to proceed):");
+		for table in SURREALKIT_TABLES {
+			println!(
+				"  {} ({} record(s))",
+				table,
+				count_records(db, table).await?
+			);
+		}
+		println!(
+			"and delete {} and {}",
+			SCHEMA_SNAPSHOT_PATH, CATALOG_SNAPSHOT_PATH
+		);
+		return Ok(());
+	}
+
+	drop_surrealkit_tables(db).await?;
+	for path in [SCHEMA_SNAPSHOT_PATH, CATALOG_SNAPSHOT_PATH] {
+		match fs::remove_file(path) {
+			Ok(()) => println!("deleted {}", path),
+			Err(err) if err.kind() == ErrorKind::NotFound => {}
+			Err(err) => return Err(err).with_context(|| format!("deleting {}", path)),
+		}
+	}
+	Ok(())
+}
+
+/// Drops SurrealKit's bookkeeping tables, leaving application schema and data alone.
+pub async fn clean_surrealkit_tables(
+	db: &Surreal<Any>,
+	yes: bool,
+	allow_shared: bool,
+) -> Result<()> {
+	if detect_shared_db(db).await? && !allow_shared {
+		bail!("database is marked shared; refusing to clean without --allow-shared");
+	}
+	if !yes
+		&& !confirm(&format!(
+			"Remove SurrealKit bookkeeping tables ({})?",
+			SURREALKIT_TABLES.join(", ")
+		))? {
+		println!("Aborted; nothing removed.");
+		return Ok(());
+	}
+	drop_surrealkit_tables(db).await
+}
+
+async fn drop_surrealkit_tables(db: &Surreal<Any>) -> Result<()> {
+	for table in SURREALKIT_TABLES {
+		let count = count_records(db, table).await?;
+		sql_query(db, remove_table_sql(table))
+			.await?
+			.check()
+			.with_context(|| format!("removing {table}"))?;
+		println!("removed {} ({} record(s))", table, count);
+	}
+	Ok(())
+}
+
+fn remove_table_sql(table: &str) -> String {
+	format!("DELETE {table}; REMOVE TABLE IF EXISTS {table};")
+}
+
+async fn count_records(db: &Surreal<Any>, table: &str) -> Result<u64> {
+	let mut resp = sql_query(
+		db,
+		"SELECT count() AS count FROM type::table($table) GROUP ALL;",
+	)
+	.bind(("table", table.to_string()))
+	.await?;
+	let rows: Vec<serde_json::Value> = resp.take(0)?;
+	Ok(rows
+		.first()
+		.and_then(|row| row.get("count"))
+		.and_then(|count| count.as_u64())
+		.unwrap_or(0))
+}
+
 const EXTRA_SETUP: &str = r#"
 DEFINE TABLE OVERWRITE _surrealkit_sync SCHEMAFULL
 	PERMISSIONS NONE;
@@ -186,6 +279,9 @@ DEFINE TABLE OVERWRITE _surrealkit_lock SCHEMAFULL
 DEFINE FIELD OVERWRITE key ON _surrealkit_lock
 	TYPE string;
 
+DEFINE FIELD OVERWRITE lock_id ON _surrealkit_lock
+	TYPE option<string>;
+
 DEFINE FIELD OVERWRITE owner ON _surrealkit_lock
 	TYPE string;
 
@@ -197,3 +293,75 @@ DEFINE INDEX OVERWRITE by_lock_key ON _surrealkit_lock
 	FIELDS key
 	UNIQUE;
 "#;
+
+/// Log of SQL run through `surrealkit apply --track`.
+pub const APPLY_SETUP: &str = r#"
+DEFINE TABLE OVERWRITE _surrealkit_apply SCHEMAFULL
+	PERMISSIONS NONE;
+
+DEFINE FIELD OVERWRITE file ON _surrealkit_apply
+	TYPE string;
+
+DEFINE FIELD OVERWRITE hash ON _surrealkit_apply
+	TYPE string;
+
+DEFINE FIELD OVERWRITE applied_at ON _surrealkit_apply
+	TYPE datetime
+	DEFAULT time::now();
+"#;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reset_covers_every_table_defined_by_setup() {
+		for sql in [DEFAULT_SETUP, EXTRA_SETUP, APPLY_SETUP] {
+			for line in sql.lines() {
+				if let Some(rest) = line.trim().strip_prefix("DEFINE TABLE OVERWRITE ") {
+					let table = rest.split_whitespace().next().unwrap_or_default();
+					assert!(
+						SURREALKIT_TABLES.contains(&table),
+						"{table} is missing from SURREALKIT_TABLES"
+					);
+				}
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn clean_only_removes_bookkeeping_tables() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		for sql in [DEFAULT_SETUP, EXTRA_SETUP, APPLY_SETUP] {
+			sql_query(&db, sql)
+				.await
+				.and_then(|resp| resp.check())
+				.expect("setup");
+		}
+		sql_query(
+			&db,
+			"DEFINE TABLE person SCHEMALESS; CREATE person:one SET name = 'Ada';\
+			 CREATE _surrealkit_sync SET path = 'a.surql', hash = 'x';",
+		)
+		.await
+		.and_then(|resp| resp.check())
+		.expect("seed tables");
+
+		clean_surrealkit_tables(&db, true, false)
+			.await
+			.expect("clean");
+
+		let info = crate::core::query_json(&db, "INFO FOR DB;")
+			.await
+			.expect("info");
+		let tables: Vec<&String> = info["tables"].as_object().expect("tables").keys().collect();
+		assert_eq!(tables, vec!["person"]);
+		assert_eq!(count_records(&db, "person").await.expect("count"), 1);
+	}
+}
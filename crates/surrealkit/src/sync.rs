@@ -1,19 +1,28 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::env;
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
 use surrealdb::{Surreal, engine::any::Any};
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::sync::mpsc;
 
-use crate::core::exec_surql;
+use crate::core::{
+	confirm, confirm_from, exec_surql, exec_surql_transactional, glob_match, sql_query,
+};
 use crate::rollout::{
-	acquire_lock, delete_managed_entities, delete_sync_hashes, load_active_rollout_id,
-	load_managed_entities, release_lock, upsert_managed_entities,
+	ManagedEntityRecord, acquire_lock, delete_managed_entities, delete_sync_hashes,
+	load_active_rollout_id, load_managed_entities, upsert_managed_entities,
 };
 use crate::schema_state::{
-	CatalogEntity, EntityKey, build_catalog_snapshot, collect_schema_files,
-	ensure_local_state_dirs, render_remove_sql,
+	CatalogEntity, EntityKey, LiveCatalogDiff, SCHEMA_DIR, SchemaFile, SyncIgnore,
+	build_catalog_snapshot, collect_schema_files, detect_renames, diff_live_catalog,
+	ensure_local_state_dirs, parse_definition_key, render_remove_sql,
 };
 use crate::setup::run_setup;
 
@@ -22,45 +31,366 @@ pub struct SyncOpts {
 	pub watch: bool,
 	pub debounce_ms: u64,
 	pub dry_run: bool,
+	pub diff: bool,
 	pub fail_fast: bool,
 	pub prune: bool,
 	pub allow_shared_prune: bool,
+	/// Prompt before pruning stale entities.
+	pub confirm_prune: bool,
+	/// Answer yes to prompts, required for `confirm_prune` without a TTY.
+	pub yes: bool,
+	pub transactional: bool,
+	/// Restricts sync to schema files matching this glob and disables prune.
+	pub only: Option<String>,
+	pub lock_timeout_secs: u64,
+	/// Fails a schema file whose apply takes longer than this.
+	pub file_timeout_ms: Option<u64>,
+	/// `<kind>:<name>` patterns for entities that are never pruned.
+	pub ignore: Vec<String>,
+	/// Writes the files a dry run would apply to this path as JSON.
+	pub dry_run_out: Option<PathBuf>,
+}
+
+/// The `--dry-run-out` artifact: every schema file considered and the tracked files
+/// whose sources were deleted.
+#[derive(Debug, Serialize)]
+pub struct DryRunPlan {
+	pub files: Vec<PlannedFile>,
+	pub removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct PlannedFile {
+	pub path: String,
+	pub hash: String,
+	/// A hash is recorded for this path in `_surrealkit_sync`.
+	pub tracked: bool,
+	/// The recorded hash matches, so sync would skip the file.
+	pub applied: bool,
+}
+
+fn dry_run_plan(
+	files: &[SchemaFile],
+	tracked: &BTreeMap<String, String>,
+	removed_paths: &[String],
+) -> DryRunPlan {
+	DryRunPlan {
+		files: files
+			.iter()
+			.map(|file| {
+				let tracked_hash = tracked.get(&file.path);
+				PlannedFile {
+					path: file.path.clone(),
+					hash: file.hash.clone(),
+					tracked: tracked_hash.is_some(),
+					applied: tracked_hash == Some(&file.hash),
+				}
+			})
+			.collect(),
+		removed: removed_paths.to_vec(),
+	}
+}
+
+fn write_dry_run_plan(path: &Path, plan: &DryRunPlan) -> Result<()> {
+	let raw = serde_json::to_string_pretty(plan).context("serializing dry-run plan")?;
+	fs::write(path, format!("{raw}\n")).with_context(|| format!("writing {}", path.display()))
 }
 
 pub async fn run_sync(db: &Surreal<Any>, opts: SyncOpts) -> Result<()> {
+	if opts.diff {
+		print_live_diff(db).await?;
+		if opts.dry_run {
+			return Ok(());
+		}
+	}
+
 	run_setup(db).await?;
 	ensure_local_state_dirs()?;
 
 	if opts.watch {
 		run_sync_once(db, &opts, true).await?;
-		println!(
-			"Watch mode active ({}ms interval). Waiting for schema changes... (Ctrl+C to stop)",
-			opts.debounce_ms.max(250)
-		);
-		loop {
-			tokio::select! {
-				_ = tokio::signal::ctrl_c() => {
-					println!("Stopping schema watch.");
-					break;
-				}
-				_ = tokio::time::sleep(Duration::from_millis(opts.debounce_ms.max(250))) => {
-					if let Err(err) = run_sync_once(db, &opts, true).await {
-						if opts.fail_fast {
-							return Err(err);
-						}
-						eprintln!("sync iteration error: {err:#}");
-					}
-				}
+		match schema_watcher() {
+			Ok((_watcher, events)) => watch_schema_events(db, &opts, events).await,
+			Err(err) => {
+				eprintln!("filesystem watcher unavailable ({err:#}); falling back to polling");
+				poll_schema(db, &opts).await
 			}
 		}
-		Ok(())
 	} else {
 		run_sync_once(db, &opts, false).await
 	}
 }
 
+fn schema_watcher() -> Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>)> {
+	let (tx, rx) = mpsc::unbounded_channel();
+	let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+		if let Ok(event) = res
+			&& is_schema_change(&event)
+		{
+			let _ = tx.send(());
+		}
+	})?;
+	watcher.watch(Path::new(SCHEMA_DIR), RecursiveMode::Recursive)?;
+	Ok((watcher, rx))
+}
+
+fn is_schema_change(event: &Event) -> bool {
+	!matches!(event.kind, EventKind::Access(_))
+		&& event
+			.paths
+			.iter()
+			.any(|path| path.extension().is_some_and(|ext| ext == "surql"))
+}
+
+async fn watch_schema_events(
+	db: &Surreal<Any>,
+	opts: &SyncOpts,
+	mut events: mpsc::UnboundedReceiver<()>,
+) -> Result<()> {
+	let debounce = Duration::from_millis(opts.debounce_ms);
+	println!(
+		"Watch mode active ({}ms debounce). Waiting for schema changes... (Ctrl+C to stop)",
+		opts.debounce_ms
+	);
+	loop {
+		tokio::select! {
+			_ = tokio::signal::ctrl_c() => {
+				println!("Stopping schema watch.");
+				break;
+			}
+			received = events.recv() => {
+				if received.is_none() {
+					bail!("schema watcher stopped unexpectedly");
+				}
+				// Collapse a burst of events (editor save, git checkout) into one sync.
+				while let Ok(Some(())) = tokio::time::timeout(debounce, events.recv()).await {}
+				sync_iteration(db, opts).await?;
+			}
+		}
+	}
+	Ok(())
+}
+
+async fn poll_schema(db: &Surreal<Any>, opts: &SyncOpts) -> Result<()> {
+	println!(
+		"Watch mode active ({}ms interval). Waiting for schema changes... (Ctrl+C to stop)",
+		opts.debounce_ms.max(250)
+	);
+	loop {
+		tokio::select! {
+			_ = tokio::signal::ctrl_c() => {
+				println!("Stopping schema watch.");
+				break;
+			}
+			_ = tokio::time::sleep(Duration::from_millis(opts.debounce_ms.max(250))) => {
+				sync_iteration(db, opts).await?;
+			}
+		}
+	}
+	Ok(())
+}
+
+async fn sync_iteration(db: &Surreal<Any>, opts: &SyncOpts) -> Result<()> {
+	if let Err(err) = run_sync_once(db, opts, true).await {
+		if opts.fail_fast {
+			return Err(err);
+		}
+		eprintln!("sync iteration error: {err:#}");
+	}
+	Ok(())
+}
+
 async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) -> Result<()> {
+	if opts.dry_run {
+		return reconcile_once(db, opts, watch_mode, None).await;
+	}
+
+	// Ask before taking the lock, so an unanswered prompt never blocks other syncs.
+	let approved = if opts.prune && opts.confirm_prune {
+		Some(approve_prune(db, opts).await?)
+	} else {
+		None
+	};
+	let lock = acquire_lock(db, "global", opts.lock_timeout_secs).await?;
+	let result = reconcile_once(db, opts, watch_mode, approved.as_ref()).await;
+	let release = lock.release(db).await;
+	match (result, release) {
+		(Err(err), _) => Err(err),
+		(Ok(_), Err(err)) => Err(err),
+		(Ok(()), Ok(())) => Ok(()),
+	}
+}
+
+/// Previews the stale entities without applying anything and returns the ones the user
+/// agreed to prune. Entities that only turn out stale once the lock is held are kept.
+async fn approve_prune(db: &Surreal<Any>, opts: &SyncOpts) -> Result<BTreeSet<EntityKey>> {
+	if opts.only.is_some() {
+		return Ok(BTreeSet::new());
+	}
 	let files = collect_schema_files()?;
+	let desired_keys = build_catalog_snapshot(&files)?
+		.entities
+		.iter()
+		.map(CatalogEntity::key)
+		.collect();
+	let managed = load_managed_entities(db).await?;
+	let plan = plan_stale(&managed, &desired_keys, &BTreeSet::new(), &files, opts)?;
+	if plan.prune.is_empty() {
+		return Ok(BTreeSet::new());
+	}
+	let remove_sql = render_remove_sql(&plan.prune, true)?;
+	if confirm_prune(&remove_sql, opts.yes, None)? {
+		Ok(plan.prune.into_iter().collect())
+	} else {
+		Ok(BTreeSet::new())
+	}
+}
+
+/// Lists the pending REMOVE statements and asks before running them, unless `yes` is set.
+/// The answer is read from `input`, or from an interactive stdin when it is `None`.
+fn confirm_prune(
+	remove_sql: &[String],
+	yes: bool,
+	input: Option<&mut dyn BufRead>,
+) -> Result<bool> {
+	println!(
+		"sync will prune {} stale managed entities:",
+		remove_sql.len()
+	);
+	for stmt in remove_sql {
+		println!("  {}", stmt);
+	}
+	if yes {
+		return Ok(true);
+	}
+	let question = "Run these REMOVE statements?";
+	match input {
+		Some(input) => confirm_from(question, input),
+		None => confirm(question),
+	}
+}
+
+/// Stale managed entities: those to prune, those released because they were renamed,
+/// and those kept by sync ignore rules.
+struct StalePlan {
+	renames: Vec<(EntityKey, EntityKey)>,
+	released: Vec<EntityKey>,
+	prune: Vec<EntityKey>,
+	ignored: Vec<EntityKey>,
+}
+
+fn plan_stale(
+	managed: &[ManagedEntityRecord],
+	effective_keys: &BTreeSet<EntityKey>,
+	failed_paths: &BTreeSet<String>,
+	files: &[SchemaFile],
+	opts: &SyncOpts,
+) -> Result<StalePlan> {
+	// With --only, unscanned files are not "removed" and their entities are not stale.
+	let filtered = opts.only.is_some();
+	let stale_records: Vec<&ManagedEntityRecord> = managed
+		.iter()
+		.filter(|record| {
+			!filtered
+				&& !effective_keys.contains(&record.entity.key())
+				&& !failed_paths.contains(&record.entity.source_path)
+		})
+		.collect();
+	// A stale entity whose DEFINE reappears under a new name is reported, not pruned,
+	// along with anything scoped to a renamed table.
+	let managed_keys: BTreeSet<EntityKey> = managed.iter().map(|r| r.entity.key()).collect();
+	let added_keys: BTreeSet<EntityKey> =
+		effective_keys.difference(&managed_keys).cloned().collect();
+	let stale_catalog: Vec<CatalogEntity> =
+		stale_records.iter().map(|r| r.entity.clone()).collect();
+	let renames = detect_renames(&stale_catalog, &added_keys, files)?;
+	let renamed_from: BTreeSet<&EntityKey> = renames.iter().map(|(from, _)| from).collect();
+	let renamed_tables: BTreeSet<&str> = renamed_from
+		.iter()
+		.filter(|key| key.kind == "table")
+		.map(|key| key.name.as_str())
+		.collect();
+	// Renamed entities stop being managed, so a later sync cannot prune them either.
+	let (released, stale_entities): (Vec<EntityKey>, Vec<EntityKey>) = stale_records
+		.iter()
+		.map(|record| record.entity.key())
+		.partition(|key| {
+			renamed_from.contains(key)
+				|| key
+					.scope
+					.as_deref()
+					.is_some_and(|scope| renamed_tables.contains(scope))
+		});
+	let (prune, ignored) = SyncIgnore::parse(&opts.ignore)?.partition(stale_entities);
+	Ok(StalePlan {
+		renames,
+		released,
+		prune,
+		ignored,
+	})
+}
+
+pub fn matches_only(pattern: &str, path: &str) -> bool {
+	let relative = path
+		.strip_prefix(SCHEMA_DIR)
+		.map(|rest| rest.trim_start_matches('/'))
+		.unwrap_or(path);
+	glob_match(pattern, path) || glob_match(pattern, relative)
+}
+
+/// Bounds `apply` by `timeout_ms`. When it expires, sync stops waiting and drops the
+/// future, but the server keeps running the query, so the file may still be applied,
+/// or its transaction committed, after it was reported as failed.
+async fn with_file_timeout(
+	timeout_ms: Option<u64>,
+	apply: impl Future<Output = Result<()>>,
+) -> Result<()> {
+	let Some(timeout_ms) = timeout_ms else {
+		return apply.await;
+	};
+	match tokio::time::timeout(Duration::from_millis(timeout_ms), apply).await {
+		Ok(result) => result,
+		Err(_) => Err(anyhow!("timed out after {timeout_ms}ms")),
+	}
+}
+
+pub async fn apply_schema_file(db: &Surreal<Any>, sql: &str, transactional: bool) -> Result<()> {
+	if transactional {
+		exec_surql_transactional(db, sql).await
+	} else {
+		exec_surql(db, sql).await
+	}
+}
+
+async fn reconcile_once(
+	db: &Surreal<Any>,
+	opts: &SyncOpts,
+	watch_mode: bool,
+	approved: Option<&BTreeSet<EntityKey>>,
+) -> Result<()> {
+	let mut files = collect_schema_files()?;
+	if let Some(pattern) = &opts.only {
+		files.retain(|file| matches_only(pattern, &file.path));
+		if !watch_mode {
+			println!(
+				"--only '{}' matched {} schema file(s); prune skipped because of the filter",
+				pattern,
+				files.len()
+			);
+		}
+	}
+	reconcile_files(db, opts, watch_mode, files, approved).await
+}
+
+/// `approved` limits pruning to entities confirmed by [`approve_prune`]; `None` prunes
+/// every stale entity.
+async fn reconcile_files(
+	db: &Surreal<Any>,
+	opts: &SyncOpts,
+	watch_mode: bool,
+	files: Vec<SchemaFile>,
+	approved: Option<&BTreeSet<EntityKey>>,
+) -> Result<()> {
 	let desired_catalog = build_catalog_snapshot(&files)?;
 	let tracked = load_sync_hashes(db).await?;
 	let managed = load_managed_entities(db).await?;
@@ -69,12 +399,19 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 		println!("No schema files found in database/schema");
 	}
 
+	// With --only, unscanned files are not "removed".
+	let filtered = opts.only.is_some();
 	let file_paths: BTreeSet<String> = files.iter().map(|file| file.path.clone()).collect();
 	let removed_paths: Vec<String> = tracked
 		.keys()
-		.filter(|path| !file_paths.contains(*path))
+		.filter(|path| !filtered && !file_paths.contains(*path))
 		.cloned()
 		.collect();
+	if opts.dry_run
+		&& let Some(out) = &opts.dry_run_out
+	{
+		write_dry_run_plan(out, &dry_run_plan(&files, &tracked, &removed_paths))?;
+	}
 
 	let mut changed_count = 0usize;
 	let mut apply_errors = 0usize;
@@ -95,7 +432,8 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 			continue;
 		}
 
-		match exec_surql(db, &file.sql).await {
+		let apply = apply_schema_file(db, &file.sql, opts.transactional);
+		match with_file_timeout(opts.file_timeout_ms, apply).await {
 			Ok(_) => {
 				if !watch_mode {
 					println!("applied {}", file.path);
@@ -123,18 +461,29 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 	let effective_keys: BTreeSet<EntityKey> =
 		effective_entities.iter().map(CatalogEntity::key).collect();
 
-	let stale_records: Vec<_> = managed
-		.iter()
-		.filter(|record| {
-			!effective_keys.contains(&record.entity.key())
-				&& !failed_paths.contains(&record.entity.source_path)
-		})
-		.cloned()
-		.collect();
-	let stale_entities: Vec<EntityKey> = stale_records
-		.iter()
-		.map(|record| record.entity.key())
-		.collect();
+	let StalePlan {
+		renames,
+		released,
+		prune: stale_entities,
+		ignored,
+	} = plan_stale(&managed, &effective_keys, &failed_paths, &files, opts)?;
+	for (from, to) in &renames {
+		println!(
+			"renamed {} {} -> {} (manual migration recommended)",
+			from.kind,
+			entity_label(from),
+			entity_label(to)
+		);
+	}
+	if !watch_mode {
+		for key in &ignored {
+			println!(
+				"ignored stale {} {} (sync ignore)",
+				key.kind,
+				entity_label(key)
+			);
+		}
+	}
 	let stale_count = stale_entities.len();
 	let destructive_change = stale_count > 0;
 
@@ -154,6 +503,9 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 
 	if !opts.dry_run {
 		upsert_managed_entities(db, &effective_entities, None, "active").await?;
+		if !released.is_empty() {
+			delete_managed_entities(db, &released).await?;
+		}
 		if !removed_paths.is_empty() {
 			delete_sync_hashes(db, &removed_paths).await?;
 		}
@@ -172,19 +524,20 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 					println!("  {}", stmt);
 				}
 			}
-		} else if shared {
-			acquire_lock(db, "global").await?;
-			let result = prune_managed_entities(db, &stale_entities).await;
-			let release = release_lock(db, "global").await;
-			match (result, release) {
-				(Err(err), _) => return Err(err),
-				(Ok(_), Err(err)) => return Err(err),
-				(Ok(()), Ok(())) => {}
-			}
-			pruned_count = stale_count;
 		} else {
-			prune_managed_entities(db, &stale_entities).await?;
-			pruned_count = stale_count;
+			let (confirmed, declined): (Vec<EntityKey>, Vec<EntityKey>) = stale_entities
+				.into_iter()
+				.partition(|key| approved.is_none_or(|approved| approved.contains(key)));
+			if !confirmed.is_empty() {
+				prune_managed_entities(db, &confirmed).await?;
+				pruned_count = confirmed.len();
+			}
+			if !declined.is_empty() {
+				println!(
+					"prune declined; {} stale entities left in place",
+					declined.len()
+				);
+			}
 		}
 	}
 
@@ -229,6 +582,65 @@ async fn run_sync_once(db: &Surreal<Any>, opts: &SyncOpts, watch_mode: bool) ->
 	Ok(())
 }
 
+#[derive(Debug, Default)]
+struct BaselinePlan<'a> {
+	untracked: Vec<&'a SchemaFile>,
+	already_tracked: usize,
+	mismatched: Vec<(String, String, String)>,
+}
+
+pub async fn run_sync_baseline(db: &Surreal<Any>, strict: bool) -> Result<()> {
+	run_setup(db).await?;
+	ensure_local_state_dirs()?;
+	let files = collect_schema_files()?;
+	let tracked = load_sync_hashes(db).await?;
+	let plan = plan_baseline(&files, &tracked);
+
+	for (path, stored, current) in &plan.mismatched {
+		eprintln!(
+			"warning: {} changed since it was tracked (stored {}, current {})",
+			path, stored, current
+		);
+	}
+	if strict && !plan.mismatched.is_empty() {
+		bail!(
+			"{} tracked schema file(s) changed since they were applied; refusing baseline with --strict",
+			plan.mismatched.len()
+		);
+	}
+
+	for file in &plan.untracked {
+		store_sync_hash(db, &file.path, &file.hash).await?;
+	}
+
+	println!(
+		"Baselined {} schema file(s); {} already tracked.",
+		plan.untracked.len(),
+		plan.already_tracked
+	);
+	Ok(())
+}
+
+fn plan_baseline<'a>(
+	files: &'a [SchemaFile],
+	tracked: &BTreeMap<String, String>,
+) -> BaselinePlan<'a> {
+	let mut plan = BaselinePlan::default();
+	for file in files {
+		match tracked.get(&file.path) {
+			None => plan.untracked.push(file),
+			Some(stored) => {
+				plan.already_tracked += 1;
+				if stored != &file.hash {
+					plan.mismatched
+						.push((file.path.clone(), stored.clone(), file.hash.clone()));
+				}
+			}
+		}
+	}
+	plan
+}
+
 async fn prune_managed_entities(db: &Surreal<Any>, stale_entities: &[EntityKey]) -> Result<()> {
 	let sql = render_remove_sql(stale_entities, true)?.join("\n");
 	if !sql.trim().is_empty() {
@@ -237,8 +649,8 @@ async fn prune_managed_entities(db: &Surreal<Any>, stale_entities: &[EntityKey])
 	delete_managed_entities(db, stale_entities).await
 }
 
-async fn load_sync_hashes(db: &Surreal<Any>) -> Result<BTreeMap<String, String>> {
-	let mut resp = db.query("SELECT path, hash FROM _surrealkit_sync;").await?;
+pub async fn load_sync_hashes(db: &Surreal<Any>) -> Result<BTreeMap<String, String>> {
+	let mut resp = sql_query(db, "SELECT path, hash FROM _surrealkit_sync;").await?;
 	let rows: Vec<serde_json::Value> = resp.take(0)?;
 
 	let mut out = BTreeMap::new();
@@ -252,8 +664,192 @@ async fn load_sync_hashes(db: &Surreal<Any>) -> Result<BTreeMap<String, String>>
 	Ok(out)
 }
 
+async fn print_live_diff(db: &Surreal<Any>) -> Result<()> {
+	let files = collect_schema_files()?;
+	let local = build_catalog_snapshot(&files)?;
+	let (live, tables) = load_live_catalog(db).await?;
+	let managed = if tables.contains("_surrealkit_managed_entity") {
+		load_managed_entities(db)
+			.await?
+			.into_iter()
+			.map(|record| (record.entity.key(), record.entity.statement_hash))
+			.collect()
+	} else {
+		BTreeMap::new()
+	};
+
+	print!(
+		"{}",
+		render_live_diff(&diff_live_catalog(&local, &live, &managed))
+	);
+	Ok(())
+}
+
+/// Reads entity keys from `INFO FOR DB` and `INFO FOR TABLE`, returning them with the table names.
+async fn load_live_catalog(db: &Surreal<Any>) -> Result<(BTreeSet<EntityKey>, BTreeSet<String>)> {
+	let mut resp = sql_query(db, "INFO FOR DB;").await?;
+	let info: Option<serde_json::Value> = resp.take(0)?;
+	let info = info.unwrap_or_default();
+
+	let mut keys = BTreeSet::new();
+	collect_definition_keys(&info, &mut keys);
+	let tables: BTreeSet<String> = info
+		.get("tables")
+		.and_then(|v| v.as_object())
+		.map(|tables| tables.keys().cloned().collect())
+		.unwrap_or_default();
+
+	for table in tables.iter().filter(|t| !t.starts_with("_surrealkit")) {
+		let mut resp = sql_query(
+			db,
+			format!("INFO FOR TABLE `{}`;", table.replace('`', "\\`")),
+		)
+		.await?;
+		let info: Option<serde_json::Value> = resp.take(0)?;
+		if let Some(info) = info {
+			collect_definition_keys(&info, &mut keys);
+		}
+	}
+	Ok((keys, tables))
+}
+
+fn collect_definition_keys(info: &serde_json::Value, keys: &mut BTreeSet<EntityKey>) {
+	let Some(groups) = info.as_object() else {
+		return;
+	};
+	for definitions in groups.values().filter_map(|v| v.as_object()) {
+		keys.extend(
+			definitions
+				.values()
+				.filter_map(|v| v.as_str())
+				.filter_map(parse_definition_key),
+		);
+	}
+}
+
+fn entity_label(key: &EntityKey) -> String {
+	match &key.scope {
+		Some(scope) => format!("{} ON {}", key.name, scope),
+		None => key.name.clone(),
+	}
+}
+
+fn render_live_diff(diff: &LiveCatalogDiff) -> String {
+	if diff.added.is_empty() && diff.modified.is_empty() && diff.removed.is_empty() {
+		return "No entity differences between local schema and database.\n".to_string();
+	}
+
+	let mut by_kind: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+	for (marker, keys) in [
+		('+', &diff.added),
+		('~', &diff.modified),
+		('-', &diff.removed),
+	] {
+		for key in keys {
+			by_kind.entry(key.kind.as_str()).or_default().push(format!(
+				"  {} {}",
+				marker,
+				entity_label(key)
+			));
+		}
+	}
+
+	let mut out = format!(
+		"Schema diff: {} added, {} modified, {} removed\n",
+		diff.added.len(),
+		diff.modified.len(),
+		diff.removed.len()
+	);
+	for (kind, lines) in by_kind {
+		out.push_str(kind);
+		out.push_str(":\n");
+		for line in lines {
+			out.push_str(&line);
+			out.push('\n');
+		}
+	}
+	out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncEvent {
+	pub path: String,
+	pub hash: String,
+	pub synced_at: OffsetDateTime,
+}
+
+/// Tracks the newest `synced_at` seen so repeated polls only surface new rows.
+#[derive(Debug, Default)]
+struct SyncWatcher {
+	last_seen: Option<OffsetDateTime>,
+}
+
+impl SyncWatcher {
+	fn observe(&mut self, mut rows: Vec<SyncEvent>) -> Vec<SyncEvent> {
+		rows.retain(|row| self.last_seen.is_none_or(|seen| row.synced_at > seen));
+		rows.sort_by_key(|row| row.synced_at);
+		if let Some(last) = rows.last() {
+			self.last_seen = Some(last.synced_at);
+		}
+		rows
+	}
+}
+
+pub async fn run_sync_status_watch(db: &Surreal<Any>, interval_ms: u64) -> Result<()> {
+	run_setup(db).await?;
+	let interval = Duration::from_millis(interval_ms.max(250));
+	let mut watcher = SyncWatcher::default();
+	watcher.observe(load_sync_events(db).await?);
+
+	println!(
+		"Watching for newly synced schema files ({}ms interval)... (Ctrl+C to stop)",
+		interval.as_millis()
+	);
+	loop {
+		tokio::select! {
+			_ = tokio::signal::ctrl_c() => {
+				println!("Stopping status watch.");
+				break;
+			}
+			_ = tokio::time::sleep(interval) => {
+				for event in watcher.observe(load_sync_events(db).await?) {
+					let at = event.synced_at.format(&Rfc3339).unwrap_or_default();
+					println!("{} synced {} ({})", at, event.path, event.hash);
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+async fn load_sync_events(db: &Surreal<Any>) -> Result<Vec<SyncEvent>> {
+	let mut resp = sql_query(
+		db,
+		"SELECT path, hash, <string> synced_at AS synced_at FROM _surrealkit_sync;",
+	)
+	.await?;
+	let rows: Vec<serde_json::Value> = resp.take(0)?;
+
+	let mut out = Vec::new();
+	for row in rows {
+		let field = |key: &str| row.get(key).and_then(|v| v.as_str()).map(str::to_string);
+		let synced_at = field("synced_at")
+			.and_then(|raw| OffsetDateTime::parse(raw.trim_matches('\''), &Rfc3339).ok());
+		if let (Some(path), Some(hash), Some(synced_at)) = (field("path"), field("hash"), synced_at)
+		{
+			out.push(SyncEvent {
+				path,
+				hash,
+				synced_at,
+			});
+		}
+	}
+	Ok(out)
+}
+
 async fn store_sync_hash(db: &Surreal<Any>, path: &str, hash: &str) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"DELETE _surrealkit_sync WHERE path = $path; \
 		 CREATE _surrealkit_sync CONTENT { path: $path, hash: $hash, synced_at: time::now() };",
 	)
@@ -264,16 +860,18 @@ async fn store_sync_hash(db: &Surreal<Any>, path: &str, hash: &str) -> Result<()
 	Ok(())
 }
 
-async fn detect_shared_db(db: &Surreal<Any>) -> Result<bool> {
+pub async fn detect_shared_db(db: &Surreal<Any>) -> Result<bool> {
 	if let Ok(value) = env::var("SURREALKIT_SHARED_DB") {
 		if let Some(parsed) = parse_bool(&value) {
 			return Ok(parsed);
 		}
 	}
 
-	let mut resp = db
-		.query("SELECT value FROM _surrealkit_sync_meta WHERE key = 'shared' LIMIT 1;")
-		.await?;
+	let mut resp = sql_query(
+		db,
+		"SELECT value FROM _surrealkit_sync_meta WHERE key = 'shared' LIMIT 1;",
+	)
+	.await?;
 	let row: Option<serde_json::Value> = resp.take(0)?;
 	let shared = row
 		.as_ref()
@@ -303,7 +901,8 @@ async fn store_last_sync_meta(db: &Surreal<Any>) -> Result<()> {
 }
 
 async fn upsert_meta(db: &Surreal<Any>, key: &str, value: serde_json::Value) -> Result<()> {
-	db.query(
+	sql_query(
+		db,
 		"DELETE _surrealkit_sync_meta WHERE key = $key; \
 		 CREATE _surrealkit_sync_meta CONTENT { key: $key, value: $value, updated_at: time::now() };",
 	)
@@ -326,6 +925,252 @@ fn parse_bool(value: &str) -> Option<bool> {
 mod tests {
 	use super::*;
 
+	async fn mem_db() -> Surreal<Any> {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		run_setup(&db).await.expect("setup");
+		db
+	}
+
+	fn schema_file(name: &str, sql: &str) -> SchemaFile {
+		SchemaFile {
+			path: format!("database/schema/{name}"),
+			sql: sql.to_string(),
+			hash: crate::core::sha256_hex(sql.as_bytes()),
+		}
+	}
+
+	fn test_opts() -> SyncOpts {
+		SyncOpts {
+			watch: false,
+			debounce_ms: 0,
+			dry_run: false,
+			diff: false,
+			fail_fast: true,
+			prune: true,
+			allow_shared_prune: false,
+			confirm_prune: false,
+			yes: false,
+			transactional: true,
+			only: None,
+			lock_timeout_secs: 1,
+			file_timeout_ms: None,
+			ignore: Vec::new(),
+			dry_run_out: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn renamed_field_survives_later_syncs() {
+		let db = mem_db().await;
+		let opts = test_opts();
+		let v1 = schema_file(
+			"person.surql",
+			"DEFINE TABLE OVERWRITE person SCHEMALESS;\nDEFINE FIELD OVERWRITE nickname ON person TYPE option<string>;\n",
+		);
+		reconcile_files(&db, &opts, false, vec![v1], None)
+			.await
+			.expect("first sync");
+		db.query("CREATE person:one SET nickname = 'Al';")
+			.await
+			.expect("seed")
+			.check()
+			.expect("seed");
+
+		let v2 = schema_file(
+			"person.surql",
+			"DEFINE TABLE OVERWRITE person SCHEMALESS;\nDEFINE FIELD OVERWRITE alias ON person TYPE option<string>;\n",
+		);
+		for _ in 0..2 {
+			reconcile_files(&db, &opts, false, vec![v2.clone()], None)
+				.await
+				.expect("sync after rename");
+		}
+
+		let info = crate::core::query_json(&db, "INFO FOR TABLE person;")
+			.await
+			.expect("table info");
+		assert!(info["fields"].get("nickname").is_some(), "{info}");
+		let nickname = crate::core::query_json(&db, "RETURN person:one.nickname;")
+			.await
+			.expect("read nickname");
+		assert_eq!(nickname, serde_json::json!("Al"));
+	}
+
+	#[tokio::test]
+	async fn timed_out_schema_file_records_no_sync_row() {
+		let db = mem_db().await;
+		let files = vec![schema_file("slow.surql", "SLEEP 2s;\n")];
+		let opts = SyncOpts {
+			prune: false,
+			file_timeout_ms: Some(50),
+			..test_opts()
+		};
+
+		let err = reconcile_files(&db, &opts, false, files, None)
+			.await
+			.expect_err("slow file should time out");
+		assert_eq!(err.to_string(), "timed out after 50ms");
+		assert!(
+			load_sync_hashes(&db)
+				.await
+				.expect("load sync hashes")
+				.is_empty()
+		);
+	}
+
+	#[test]
+	fn dry_run_plan_lists_pending_files_and_marks_applied_ones() {
+		let file = |path: &str, hash: &str| SchemaFile {
+			path: path.to_string(),
+			sql: String::new(),
+			hash: hash.to_string(),
+		};
+		let files = vec![
+			file("database/schema/person.surql", "h1"),
+			file("database/schema/post.surql", "h2"),
+			file("database/schema/tag.surql", "h3"),
+		];
+		let tracked = BTreeMap::from([
+			("database/schema/person.surql".to_string(), "h1".to_string()),
+			("database/schema/post.surql".to_string(), "old".to_string()),
+			("database/schema/legacy.surql".to_string(), "h0".to_string()),
+		]);
+
+		let plan = dry_run_plan(
+			&files,
+			&tracked,
+			&["database/schema/legacy.surql".to_string()],
+		);
+		let state: Vec<_> = plan
+			.files
+			.iter()
+			.map(|f| (f.path.as_str(), f.tracked, f.applied))
+			.collect();
+		assert_eq!(
+			state,
+			vec![
+				("database/schema/person.surql", true, true),
+				("database/schema/post.surql", true, false),
+				("database/schema/tag.surql", false, false),
+			]
+		);
+		assert_eq!(plan.removed, vec!["database/schema/legacy.surql"]);
+
+		let dir = tempfile::tempdir().expect("create temp dir");
+		let out = dir.path().join("plan.json");
+		write_dry_run_plan(&out, &plan).expect("write plan");
+		let json: serde_json::Value =
+			serde_json::from_str(&fs::read_to_string(&out).expect("read plan")).expect("json");
+		assert_eq!(json["files"][2]["hash"], "h3");
+		assert_eq!(json["files"][0]["applied"], true);
+	}
+
+	#[test]
+	fn sync_watcher_reports_only_rows_newer_than_last_poll() {
+		let event = |path: &str, secs: i64| SyncEvent {
+			path: path.to_string(),
+			hash: "h".to_string(),
+			synced_at: OffsetDateTime::from_unix_timestamp(secs).expect("timestamp"),
+		};
+		let mut watcher = SyncWatcher::default();
+		watcher.observe(vec![event("a.surql", 10), event("b.surql", 20)]);
+
+		assert!(
+			watcher
+				.observe(vec![event("a.surql", 10), event("b.surql", 20)])
+				.is_empty()
+		);
+
+		let fresh = watcher.observe(vec![
+			event("a.surql", 10),
+			event("b.surql", 20),
+			event("c.surql", 30),
+		]);
+		assert_eq!(fresh, vec![event("c.surql", 30)]);
+	}
+
+	#[test]
+	fn only_surql_changes_trigger_a_sync() {
+		use notify::event::{AccessKind, ModifyKind};
+
+		let modify = |path: &str| {
+			Event::new(EventKind::Modify(ModifyKind::Any)).add_path(std::path::PathBuf::from(path))
+		};
+		assert!(is_schema_change(&modify("database/schema/person.surql")));
+		assert!(!is_schema_change(&modify(
+			"database/schema/.person.surql.swp"
+		)));
+		assert!(!is_schema_change(
+			&Event::new(EventKind::Access(AccessKind::Any))
+				.add_path(std::path::PathBuf::from("database/schema/person.surql"))
+		));
+	}
+
+	#[test]
+	fn only_filter_matches_full_or_schema_relative_paths() {
+		assert!(matches_only("person*", "database/schema/person.surql"));
+		assert!(matches_only(
+			"database/schema/billing/*",
+			"database/schema/billing/invoice.surql"
+		));
+		assert!(!matches_only("person*", "database/schema/order.surql"));
+	}
+
+	#[test]
+	fn confirm_prune_reads_the_answer() {
+		let sql = vec!["REMOVE TABLE legacy;".to_string()];
+		let answer = |input: &str, yes: bool| {
+			confirm_prune(&sql, yes, Some(&mut input.as_bytes())).expect("answer")
+		};
+		assert!(answer("", true), "--yes skips the prompt");
+		assert!(answer("y\n", false));
+		assert!(answer("YES\n", false));
+		assert!(!answer("n\n", false));
+		assert!(!answer("\n", false));
+		assert!(!answer("", false));
+	}
+
+	#[tokio::test]
+	async fn prune_leaves_unapproved_stale_entities_in_place() {
+		let db = mem_db().await;
+		let opts = test_opts();
+		let v1 = schema_file(
+			"app.surql",
+			"DEFINE TABLE OVERWRITE person SCHEMALESS;\nDEFINE TABLE OVERWRITE legacy SCHEMALESS;\n",
+		);
+		reconcile_files(&db, &opts, false, vec![v1], None)
+			.await
+			.expect("first sync");
+
+		let v2 = schema_file("app.surql", "DEFINE TABLE OVERWRITE person SCHEMALESS;\n");
+		reconcile_files(&db, &opts, false, vec![v2.clone()], Some(&BTreeSet::new()))
+			.await
+			.expect("declined sync");
+		let info = crate::core::query_json(&db, "INFO FOR DB;")
+			.await
+			.expect("db info");
+		assert!(info["tables"].get("legacy").is_some(), "{info}");
+
+		let legacy = EntityKey {
+			kind: "table".to_string(),
+			scope: None,
+			name: "legacy".to_string(),
+		};
+		reconcile_files(&db, &opts, false, vec![v2], Some(&BTreeSet::from([legacy])))
+			.await
+			.expect("approved sync");
+		let info = crate::core::query_json(&db, "INFO FOR DB;")
+			.await
+			.expect("db info");
+		assert!(info["tables"].get("legacy").is_none(), "{info}");
+	}
+
 	#[test]
 	fn parse_bool_handles_common_spellings() {
 		assert_eq!(parse_bool("true"), Some(true));
@@ -333,4 +1178,42 @@ mod tests {
 		assert_eq!(parse_bool("0"), Some(false));
 		assert_eq!(parse_bool("unknown"), None);
 	}
+
+	#[test]
+	fn baseline_plan_tracks_only_new_files_and_flags_changed_hashes() {
+		let files = vec![
+			SchemaFile {
+				path: "database/schema/a.surql".to_string(),
+				sql: String::new(),
+				hash: "a1".to_string(),
+			},
+			SchemaFile {
+				path: "database/schema/b.surql".to_string(),
+				sql: String::new(),
+				hash: "b2".to_string(),
+			},
+			SchemaFile {
+				path: "database/schema/c.surql".to_string(),
+				sql: String::new(),
+				hash: "c1".to_string(),
+			},
+		];
+		let tracked = BTreeMap::from([
+			("database/schema/a.surql".to_string(), "a1".to_string()),
+			("database/schema/b.surql".to_string(), "b1".to_string()),
+		]);
+
+		let plan = plan_baseline(&files, &tracked);
+		assert_eq!(plan.untracked.len(), 1);
+		assert_eq!(plan.untracked[0].path, "database/schema/c.surql");
+		assert_eq!(plan.already_tracked, 2);
+		assert_eq!(
+			plan.mismatched,
+			vec![(
+				"database/schema/b.surql".to_string(),
+				"b1".to_string(),
+				"b2".to_string()
+			)]
+		);
+	}
 }
@@ -1,22 +1,27 @@
 use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::sync::Mutex;
 
 use anyhow::{Context, Result, anyhow, bail};
 use serde_json::Value;
 use surrealdb::opt::auth::{Database, Namespace, Record, Root};
 use surrealdb::{Surreal, engine::any::Any};
-use surrealdb_types::SurrealValue;
 
 use crate::config::DbCfg;
-use crate::core::create_surreal_client;
+use crate::core::{HttpSqlTarget, create_surreal_client, query_json};
 
 use super::types::{ActorKind, ActorSpec};
 
+/// Header an `api_key` actor sends its key in unless `api_key_header` is set.
+const DEFAULT_API_KEY_HEADER: &str = "x-api-key";
+
 #[derive(Debug, Clone)]
 pub struct ActorSession {
 	pub db: Surreal<Any>,
 	pub headers: BTreeMap<String, String>,
 	pub auth: Option<Value>,
+	/// Where `sql_expect` cases with header assertions send their SQL.
+	pub sql_http: HttpSqlTarget,
 }
 
 pub fn merged_actor_specs(
@@ -30,20 +35,117 @@ pub fn merged_actor_specs(
 	merged
 }
 
+/// Connections kept between suites with `--reuse-connections`: one signed-in root client
+/// per host, switched to each suite's ns/db, and the latest session of each actor, reused
+/// while its spec and host stay the same (and, for scoped signins, its ns/db).
+#[derive(Default)]
+pub struct SessionCache {
+	roots: Mutex<HashMap<String, (Surreal<Any>, String)>>,
+	actors: Mutex<HashMap<String, (String, ActorSession)>>,
+}
+
+impl SessionCache {
+	/// The cached root client and its token for `host`.
+	pub fn root(&self, host: &str) -> Option<(Surreal<Any>, String)> {
+		self.roots.lock().ok()?.get(host).cloned()
+	}
+
+	pub(super) fn insert_root(&self, host: &str, db: Surreal<Any>, token: String) {
+		if let Ok(mut roots) = self.roots.lock() {
+			roots.insert(host.to_string(), (db, token));
+		}
+	}
+
+	fn actor(&self, name: &str, key: &str) -> Option<ActorSession> {
+		let actors = self.actors.lock().ok()?;
+		let (cached_key, session) = actors.get(name)?;
+		(cached_key == key).then(|| session.clone())
+	}
+
+	/// Replaces the actor's previous session, so at most one connection per actor is kept.
+	fn insert_actor(&self, name: &str, key: String, session: ActorSession) {
+		if let Ok(mut actors) = self.actors.lock() {
+			actors.insert(name.to_string(), (key, session));
+		}
+	}
+}
+
+/// Root and unauthenticated sessions are switched to each suite's ns/db on reuse.
+/// Namespace, database, record and token signins are scoped to the ns/db they were made
+/// in, so those sessions are only reused for the same target.
+fn actor_cache_key(spec: &ActorSpec, host: &str, namespace: &str, database: &str) -> String {
+	match spec.kind {
+		ActorKind::Root | ActorKind::Headers | ActorKind::Anonymous | ActorKind::ApiKey => {
+			format!("{host}|{spec:?}")
+		}
+		_ => format!("{host}|{namespace}|{database}|{spec:?}"),
+	}
+}
+
+/// The ns/db an actor works in: its own `namespace`/`database` or the suite's.
+fn actor_target(spec: &ActorSpec, namespace: &str, database: &str) -> Result<(String, String)> {
+	let actor_ns = resolve_string(
+		spec.namespace.as_deref(),
+		spec.namespace_env.as_deref(),
+		some_default(namespace),
+	)?;
+	let actor_db = resolve_string(
+		spec.database.as_deref(),
+		spec.database_env.as_deref(),
+		some_default(database),
+	)?;
+	Ok((actor_ns, actor_db))
+}
+
+/// Switches a reused session to the ns/db of the current suite.
+async fn retarget_session(
+	name: &str,
+	spec: &ActorSpec,
+	mut session: ActorSession,
+	namespace: &str,
+	database: &str,
+) -> Result<ActorSession> {
+	let (actor_ns, actor_db) = actor_target(spec, namespace, database)?;
+	if session.sql_http.namespace != actor_ns || session.sql_http.database != actor_db {
+		session
+			.db
+			.use_ns(&actor_ns)
+			.use_db(&actor_db)
+			.await
+			.with_context(|| {
+				format!("actor '{name}' use_ns/use_db failed for {actor_ns}/{actor_db}")
+			})?;
+		session.sql_http.namespace = actor_ns;
+		session.sql_http.database = actor_db;
+	}
+	Ok(session)
+}
+
 pub async fn build_actor_sessions(
 	cfg: &DbCfg,
 	host: &str,
 	namespace: &str,
 	database: &str,
 	specs: &BTreeMap<String, ActorSpec>,
+	cache: Option<&SessionCache>,
 ) -> Result<HashMap<String, ActorSession>> {
 	let mut out = HashMap::new();
 
-	let root = build_default_root_session(cfg, host, namespace, database).await?;
+	let root = build_default_root_session(cfg, host, namespace, database, cache).await?;
 	out.insert("root".to_string(), root);
 
 	for (name, spec) in specs {
-		let session = build_session(name, spec, cfg, host, namespace, database).await?;
+		let key = actor_cache_key(spec, host, namespace, database);
+		let session = match cache.and_then(|cache| cache.actor(name, &key)) {
+			Some(session) => retarget_session(name, spec, session, namespace, database).await?,
+			None => {
+				let session = build_session(name, spec, cfg, host, namespace, database).await?;
+				if let Some(cache) = cache {
+					cache.insert_actor(name, key, session.clone());
+				}
+				session
+			}
+		};
 		out.insert(name.clone(), session);
 	}
 
@@ -55,17 +157,30 @@ async fn build_default_root_session(
 	host: &str,
 	namespace: &str,
 	database: &str,
+	cache: Option<&SessionCache>,
 ) -> Result<ActorSession> {
-	let db = create_surreal_client(&host.to_string())
-		.await
-		.with_context(|| format!("connecting root actor to {host}"))?;
-	let _token = db
-		.signin(Root {
-			username: cfg.user().to_string(),
-			password: cfg.pass().to_string(),
-		})
-		.await
-		.context("root signin failed")?;
+	let (db, token) = match cache.and_then(|cache| cache.root(host)) {
+		Some(cached) => cached,
+		None => {
+			let db = create_surreal_client(host, cfg)
+				.await
+				.with_context(|| format!("connecting root actor to {host}"))?;
+			let token = db
+				.signin(Root {
+					username: cfg.user().to_string(),
+					password: cfg.pass().to_string(),
+				})
+				.await
+				.context("root signin failed")?
+				.access
+				.as_insecure_token()
+				.to_string();
+			if let Some(cache) = cache {
+				cache.insert_root(host, db.clone(), token.clone());
+			}
+			(db, token)
+		}
+	};
 	db.use_ns(namespace)
 		.use_db(database)
 		.await
@@ -75,6 +190,13 @@ async fn build_default_root_session(
 		auth: fetch_auth(&db).await?,
 		db,
 		headers: BTreeMap::new(),
+		sql_http: HttpSqlTarget {
+			host: host.to_string(),
+			namespace: namespace.to_string(),
+			database: database.to_string(),
+			headers: BTreeMap::from([("authorization".to_string(), format!("Bearer {token}"))]),
+			tls: cfg.tls_options(),
+		},
 	})
 }
 
@@ -87,18 +209,10 @@ async fn build_session(
 	database: &str,
 ) -> Result<ActorSession> {
 	let mut session_headers = spec.headers.clone();
-	let actor_ns = resolve_string(
-		spec.namespace.as_deref(),
-		spec.namespace_env.as_deref(),
-		some_default(namespace),
-	)?;
-	let actor_db = resolve_string(
-		spec.database.as_deref(),
-		spec.database_env.as_deref(),
-		some_default(database),
-	)?;
+	let (actor_ns, actor_db) = actor_target(spec, namespace, database)?;
 
-	let db = create_surreal_client(&host.to_string())
+	let host = resolve_actor_host(name, spec, host)?;
+	let db = create_surreal_client(&host, cfg)
 		.await
 		.with_context(|| format!("connecting actor '{name}' to {host}"))?;
 	let access_token = match spec.kind {
@@ -215,6 +329,16 @@ async fn build_session(
 				.with_context(|| format!("actor '{name}' default root signin failed"))?;
 			Some(token.access.as_insecure_token().to_string())
 		}
+		ActorKind::Anonymous => None,
+		ActorKind::ApiKey => {
+			let key = required_string(
+				spec.token.as_deref(),
+				spec.token_env.as_deref(),
+				format!("actor '{name}' api key"),
+			)?;
+			session_headers.entry(api_key_header(spec)).or_insert(key);
+			None
+		}
 	};
 
 	db.use_ns(&actor_ns)
@@ -233,14 +357,19 @@ async fn build_session(
 	Ok(ActorSession {
 		auth: fetch_auth(&db).await?,
 		db,
+		sql_http: HttpSqlTarget {
+			host,
+			namespace: actor_ns,
+			database: actor_db,
+			headers: session_headers.clone(),
+			tls: cfg.tls_options(),
+		},
 		headers: session_headers,
 	})
 }
 
 async fn fetch_auth(db: &Surreal<Any>) -> Result<Option<Value>> {
-	let mut response = db.query("RETURN $auth;").await?.check()?;
-	let raw: surrealdb_types::Value = response.take(0)?;
-	let json = Value::from_value(raw).unwrap_or(Value::Null);
+	let json = query_json(db, "RETURN $auth;").await?;
 	Ok((json != Value::Null).then_some(json))
 }
 
@@ -284,6 +413,27 @@ pub fn resolve_string(
 	bail!("required value missing")
 }
 
+fn api_key_header(spec: &ActorSpec) -> String {
+	spec.api_key_header
+		.as_deref()
+		.unwrap_or(DEFAULT_API_KEY_HEADER)
+		.to_ascii_lowercase()
+}
+
+/// The actor's own `host`/`host_env`, e.g. a read replica, or the run's host.
+fn resolve_actor_host(name: &str, spec: &ActorSpec, default: &str) -> Result<String> {
+	let host = resolve_string(
+		spec.host.as_deref(),
+		spec.host_env.as_deref(),
+		some_default(default),
+	)?;
+	let host = host.trim().trim_end_matches('/').to_string();
+	match host.split_once("://") {
+		Some((scheme, rest)) if !scheme.is_empty() && !rest.is_empty() => Ok(host),
+		_ => bail!("actor '{name}' host '{host}' must include a scheme, e.g. ws://localhost:8000"),
+	}
+}
+
 fn required_string(literal: Option<&str>, env_name: Option<&str>, label: String) -> Result<String> {
 	resolve_string(literal, env_name, None).with_context(|| format!("missing {label}"))
 }
@@ -291,3 +441,162 @@ fn required_string(literal: Option<&str>, env_name: Option<&str>, label: String)
 fn some_default<'a>(value: &'a str) -> Option<&'a str> {
 	Some(value)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn actors_can_target_their_own_hosts() {
+		let specs: BTreeMap<String, ActorSpec> = toml::from_str(
+			r#"
+[primary]
+kind = "root"
+
+[replica]
+kind = "root"
+host = "ws://replica.internal:8000/"
+
+[broken]
+kind = "root"
+host = "replica.internal:8000"
+"#,
+		)
+		.expect("actors should parse");
+		let default = "ws://primary.internal:8000";
+
+		assert_eq!(
+			resolve_actor_host("primary", &specs["primary"], default).expect("primary host"),
+			default
+		);
+		assert_eq!(
+			resolve_actor_host("replica", &specs["replica"], default).expect("replica host"),
+			"ws://replica.internal:8000"
+		);
+		let err = resolve_actor_host("broken", &specs["broken"], default)
+			.expect_err("host without scheme");
+		assert!(err.to_string().contains("actor 'broken'"));
+	}
+
+	#[tokio::test]
+	async fn anonymous_sessions_cannot_read_permissions_none_tables() {
+		use surrealdb::engine::any::connect;
+		use surrealdb::opt::Config;
+
+		let root = || Root {
+			username: "root".to_string(),
+			password: "root".to_string(),
+		};
+		// Configuring a root user turns on authentication for the embedded engine.
+		let db = connect(("mem://", Config::new().user(root())))
+			.await
+			.expect("in-memory engine");
+		db.signin(root()).await.expect("root signin");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		db.query("DEFINE TABLE secret PERMISSIONS NONE; CREATE secret:one SET value = 1;")
+			.await
+			.expect("define table")
+			.check()
+			.expect("define table");
+
+		// What an anonymous actor has: a connection scoped to the ns/db, without a signin.
+		db.invalidate().await.expect("drop root session");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		assert_eq!(fetch_auth(&db).await.ok().flatten(), None);
+		match query_json(&db, "SELECT * FROM secret;").await {
+			Ok(rows) => assert_eq!(rows, serde_json::json!([])),
+			Err(err) => assert!(!err.to_string().is_empty()),
+		}
+	}
+
+	#[test]
+	fn api_key_actors_default_to_the_x_api_key_header() {
+		let specs: BTreeMap<String, ActorSpec> = toml::from_str(
+			r#"
+[gateway]
+kind = "api_key"
+token = "k-123"
+
+[partner]
+kind = "api_key"
+token_env = "PARTNER_KEY"
+api_key_header = "X-Partner-Key"
+"#,
+		)
+		.expect("actors should parse");
+
+		assert_eq!(specs["gateway"].kind, ActorKind::ApiKey);
+		assert_eq!(api_key_header(&specs["gateway"]), "x-api-key");
+		assert_eq!(api_key_header(&specs["partner"]), "x-partner-key");
+	}
+
+	#[test]
+	fn cached_actor_sessions_are_keyed_by_spec_and_target() {
+		let specs: BTreeMap<String, ActorSpec> = toml::from_str(
+			r#"
+[admin]
+kind = "root"
+
+[replica]
+kind = "root"
+host = "ws://replica.internal:8000"
+
+[editor]
+kind = "database"
+username = "editor"
+password = "secret"
+"#,
+		)
+		.expect("actors should parse");
+		let host = "ws://localhost:8000";
+		let key = |spec: &str, ns: &str| actor_cache_key(&specs[spec], host, ns, "main");
+
+		assert_eq!(key("admin", "app"), key("admin", "app"));
+		assert_eq!(
+			key("admin", "app"),
+			key("admin", "app_test_run2"),
+			"root sessions are switched to the next suite's ns/db"
+		);
+		assert_ne!(key("admin", "app"), key("replica", "app"));
+		assert_ne!(key("editor", "app"), key("editor", "app_test_run2"));
+	}
+
+	#[tokio::test]
+	async fn reused_sessions_switch_to_the_next_suite_target() {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("first")
+			.use_db("main")
+			.await
+			.expect("select ns/db");
+		let session = ActorSession {
+			db,
+			headers: BTreeMap::new(),
+			auth: None,
+			sql_http: HttpSqlTarget {
+				namespace: "first".to_string(),
+				database: "main".to_string(),
+				..HttpSqlTarget::default()
+			},
+		};
+		let spec: ActorSpec = toml::from_str("kind = \"anonymous\"").expect("actor should parse");
+
+		let session = retarget_session("guest", &spec, session, "second", "main")
+			.await
+			.expect("retarget");
+		assert_eq!(session.sql_http.namespace, "second");
+		assert_eq!(
+			query_json(&session.db, "RETURN session::ns();")
+				.await
+				.expect("current ns"),
+			serde_json::json!("second")
+		);
+	}
+}
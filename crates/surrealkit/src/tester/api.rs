@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow, bail};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
@@ -8,11 +8,13 @@ use super::actors::ActorSession;
 use super::assertions::{
 	JsonAssertionContext, assert_header_value, assert_json_value_with_context,
 };
-use super::types::{ApiRequestCase, AssertionReport};
+use super::types::{ApiClientConfig, ApiRequestCase, AssertionReport, GraphQLRequestCase};
 
 #[derive(Debug, Clone)]
 pub struct ApiResult {
 	pub status: u16,
+	/// Time from sending the request until its body was read.
+	pub duration_ms: u128,
 	pub assertions: Vec<AssertionReport>,
 }
 
@@ -21,41 +23,27 @@ pub async fn execute_api_case(
 	case: &ApiRequestCase,
 	actor: &ActorSession,
 	default_timeout_ms: u64,
+	client_cfg: &ApiClientConfig,
 ) -> Result<ApiResult> {
-	let client = reqwest::Client::builder()
-		.timeout(Duration::from_millis(
-			case.timeout_ms.unwrap_or(default_timeout_ms),
-		))
-		.build()
-		.context("building API client")?;
+	let client = build_client(client_cfg, case.timeout_ms.unwrap_or(default_timeout_ms))?;
 
 	let path = case.path.trim();
 	if path.is_empty() {
 		bail!("api_request case path cannot be empty");
 	}
-	let url = format!(
-		"{}{}{}",
-		base_url.trim_end_matches('/'),
-		if path.starts_with('/') { "" } else { "/" },
-		path
-	);
+	let url = join_url(base_url, path);
 
 	let method = reqwest::Method::from_bytes(case.method.to_uppercase().as_bytes())
 		.with_context(|| format!("invalid HTTP method '{}'", case.method))?;
 
-	let mut headers = HeaderMap::new();
-	for (k, v) in &actor.headers {
-		insert_header(&mut headers, k, v)?;
-	}
-	for (k, v) in &case.headers {
-		insert_header(&mut headers, k, v)?;
-	}
+	let headers = request_headers(actor, &case.headers)?;
 
 	let mut req = client.request(method, &url).headers(headers);
 	if let Some(body) = &case.body {
 		req = req.json(body);
 	}
 
+	let started = Instant::now();
 	let resp = req
 		.send()
 		.await
@@ -63,6 +51,7 @@ pub async fn execute_api_case(
 	let status = resp.status().as_u16();
 	let headers = resp.headers().clone();
 	let body_text = resp.text().await.context("reading response body")?;
+	let duration_ms = started.elapsed().as_millis();
 
 	let mut assertions = Vec::new();
 	let status_ok = status == case.expected_status;
@@ -71,6 +60,9 @@ pub async fn execute_api_case(
 		passed: status_ok,
 		message: format!("expected status {}, got {}", case.expected_status, status),
 	});
+	if let Some(max) = case.max_duration_ms {
+		assertions.push(duration_assertion(duration_ms, max));
+	}
 
 	let body = if body_text.trim().is_empty() {
 		None
@@ -96,7 +88,155 @@ pub async fn execute_api_case(
 		}
 	}
 
-	Ok(ApiResult { status, assertions })
+	Ok(ApiResult {
+		status,
+		duration_ms,
+		assertions,
+	})
+}
+
+fn duration_assertion(duration_ms: u128, max_duration_ms: u64) -> AssertionReport {
+	AssertionReport {
+		name: "duration".to_string(),
+		passed: duration_ms <= u128::from(max_duration_ms),
+		message: format!(
+			"expected response within {}ms, took {}ms",
+			max_duration_ms, duration_ms
+		),
+	}
+}
+
+pub async fn execute_graphql_case(
+	base_url: &str,
+	case: &GraphQLRequestCase,
+	actor: &ActorSession,
+	default_timeout_ms: u64,
+	client_cfg: &ApiClientConfig,
+) -> Result<ApiResult> {
+	let client = build_client(client_cfg, case.timeout_ms.unwrap_or(default_timeout_ms))?;
+	let url = join_url(base_url, "/graphql");
+	let headers = request_headers(actor, &case.headers)?;
+
+	let started = Instant::now();
+	let resp = client
+		.post(&url)
+		.headers(headers)
+		.json(&graphql_body(case))
+		.send()
+		.await
+		.with_context(|| format!("request to {} failed", url))?;
+	let status = resp.status().as_u16();
+	let body_text = resp.text().await.context("reading response body")?;
+	let duration_ms = started.elapsed().as_millis();
+	let body = serde_json::from_str::<Value>(&body_text).ok();
+
+	let ctx = JsonAssertionContext {
+		actor_auth: actor.auth.clone(),
+	};
+	let assertions = graphql_assertions(case, status, body.as_ref(), &ctx)?;
+	Ok(ApiResult {
+		status,
+		duration_ms,
+		assertions,
+	})
+}
+
+fn graphql_body(case: &GraphQLRequestCase) -> Value {
+	let mut body = serde_json::json!({ "query": case.query });
+	if let Some(variables) = &case.variables {
+		body["variables"] = variables.clone();
+	}
+	if let Some(name) = &case.operation_name {
+		body["operationName"] = Value::String(name.clone());
+	}
+	body
+}
+
+fn graphql_assertions(
+	case: &GraphQLRequestCase,
+	status: u16,
+	body: Option<&Value>,
+	ctx: &JsonAssertionContext,
+) -> Result<Vec<AssertionReport>> {
+	let mut assertions = vec![AssertionReport {
+		name: "status".to_string(),
+		passed: status == case.expected_status,
+		message: format!("expected status {}, got {}", case.expected_status, status),
+	}];
+	if case.expect_no_errors {
+		let errors = body
+			.and_then(|body| body.get("errors"))
+			.filter(|errors| !errors.is_null() && errors.as_array().is_none_or(|e| !e.is_empty()));
+		assertions.push(AssertionReport {
+			name: "no_errors".to_string(),
+			passed: body.is_some() && errors.is_none(),
+			message: match (body, errors) {
+				(None, _) => "response body is not valid JSON".to_string(),
+				(Some(_), Some(errors)) => format!("expected no errors, got {}", errors),
+				(Some(_), None) => "response has no errors".to_string(),
+			},
+		});
+	}
+	if case.data_assertions.is_empty() && case.error_assertions.is_empty() {
+		return Ok(assertions);
+	}
+
+	let body = body.ok_or_else(|| {
+		anyhow!("graphql assertions requested but response body is not valid JSON")
+	})?;
+	let data = body.get("data").unwrap_or(&Value::Null);
+	for (idx, assertion) in case.data_assertions.iter().enumerate() {
+		let mut report = assert_json_value_with_context(data, assertion, idx, ctx)?;
+		report.name = format!("data_{}", report.name);
+		assertions.push(report);
+	}
+	let errors = body.get("errors").unwrap_or(&Value::Null);
+	for (idx, assertion) in case.error_assertions.iter().enumerate() {
+		let mut report = assert_json_value_with_context(errors, assertion, idx, ctx)?;
+		report.name = format!("errors_{}", report.name);
+		assertions.push(report);
+	}
+	Ok(assertions)
+}
+
+fn join_url(base_url: &str, path: &str) -> String {
+	format!(
+		"{}{}{}",
+		base_url.trim_end_matches('/'),
+		if path.starts_with('/') { "" } else { "/" },
+		path
+	)
+}
+
+/// Actor headers (including its bearer token) overlaid with the case's own.
+fn request_headers(
+	actor: &ActorSession,
+	extra: &std::collections::BTreeMap<String, String>,
+) -> Result<HeaderMap> {
+	let mut headers = HeaderMap::new();
+	for (k, v) in &actor.headers {
+		insert_header(&mut headers, k, v)?;
+	}
+	for (k, v) in extra {
+		insert_header(&mut headers, k, v)?;
+	}
+	Ok(headers)
+}
+
+fn build_client(cfg: &ApiClientConfig, timeout_ms: u64) -> Result<reqwest::Client> {
+	let mut builder = reqwest::Client::builder()
+		.timeout(Duration::from_millis(timeout_ms))
+		.danger_accept_invalid_certs(cfg.danger_accept_invalid_certs);
+	if let Some(ms) = cfg.connect_timeout_ms {
+		builder = builder.connect_timeout(Duration::from_millis(ms));
+	}
+	if let Some(ms) = cfg.tcp_keepalive_ms {
+		builder = builder.tcp_keepalive(Duration::from_millis(ms));
+	}
+	if let Some(agent) = &cfg.user_agent {
+		builder = builder.user_agent(agent);
+	}
+	builder.build().context("building API client")
 }
 
 fn insert_header(headers: &mut HeaderMap, key: &str, value: &str) -> Result<()> {
@@ -107,3 +247,128 @@ fn insert_header(headers: &mut HeaderMap, key: &str, value: &str) -> Result<()>
 	headers.insert(name, val);
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn client_sends_configured_user_agent() {
+		let listener = TcpListener::bind("127.0.0.1:0").expect("bind test listener");
+		let addr = listener.local_addr().expect("listener addr");
+		let server = std::thread::spawn(move || {
+			let (mut stream, _) = listener.accept().expect("accept connection");
+			let mut buf = [0u8; 4096];
+			let n = stream.read(&mut buf).expect("read request");
+			stream
+				.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+				.expect("write response");
+			String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase()
+		});
+
+		let cfg = ApiClientConfig {
+			connect_timeout_ms: Some(1_000),
+			tcp_keepalive_ms: Some(30_000),
+			user_agent: Some("surrealkit-test/1.0".to_string()),
+			danger_accept_invalid_certs: false,
+		};
+		let client = build_client(&cfg, 5_000).expect("client should build");
+		let resp = client
+			.get(format!("http://{addr}/"))
+			.send()
+			.await
+			.expect("request should succeed");
+		assert_eq!(resp.status().as_u16(), 200);
+
+		let request = server.join().expect("server thread");
+		assert!(
+			request.contains("user-agent: surrealkit-test/1.0"),
+			"{request}"
+		);
+	}
+
+	#[test]
+	fn duration_assertion_fails_past_the_threshold() {
+		let report = duration_assertion(120, 250);
+		assert!(report.passed, "{}", report.message);
+		assert_eq!(report.name, "duration");
+
+		let report = duration_assertion(300, 250);
+		assert!(!report.passed);
+		assert_eq!(report.message, "expected response within 250ms, took 300ms");
+	}
+
+	#[test]
+	fn graphql_request_body_and_assertions() {
+		let case: GraphQLRequestCase = toml::from_str(
+			r#"
+query = "query People($limit: Int) { person(limit: $limit) { name } }"
+variables = { limit = 1 }
+operation_name = "People"
+
+[[data_assertions]]
+path = "person.0.name"
+equals = "Alice"
+
+[[error_assertions]]
+path = "0"
+exists = false
+"#,
+		)
+		.expect("graphql case should parse");
+		assert_eq!(case.expected_status, 200);
+
+		let body = graphql_body(&case);
+		assert_eq!(body["operationName"], "People");
+		assert_eq!(body["variables"]["limit"], 1);
+
+		let ctx = JsonAssertionContext::default();
+		let ok = serde_json::json!({ "data": { "person": [{ "name": "Alice" }] } });
+		let reports = graphql_assertions(&case, 200, Some(&ok), &ctx).expect("assertions");
+		assert!(reports.iter().all(|r| r.passed), "{reports:?}");
+
+		let failed = serde_json::json!({
+			"data": { "person": [{ "name": "Bob" }] },
+			"errors": [{ "message": "Not enough permissions" }]
+		});
+		let reports = graphql_assertions(&case, 200, Some(&failed), &ctx).expect("assertions");
+		assert!(
+			!reports
+				.iter()
+				.find(|r| r.name == "data_json_assertion_1")
+				.unwrap()
+				.passed
+		);
+		assert!(
+			!reports
+				.iter()
+				.find(|r| r.name == "errors_json_assertion_1")
+				.unwrap()
+				.passed
+		);
+	}
+
+	#[test]
+	fn graphql_expect_no_errors_treats_empty_errors_as_success() {
+		let case: GraphQLRequestCase =
+			toml::from_str("query = \"{ person { name } }\"\nexpect_no_errors = true\n")
+				.expect("graphql case should parse");
+		let ctx = JsonAssertionContext::default();
+		let no_errors = |body: Value| {
+			graphql_assertions(&case, 200, Some(&body), &ctx)
+				.expect("assertions")
+				.into_iter()
+				.find(|r| r.name == "no_errors")
+				.expect("no_errors assertion")
+		};
+
+		assert!(no_errors(serde_json::json!({ "data": {} })).passed);
+		assert!(no_errors(serde_json::json!({ "data": {}, "errors": [] })).passed);
+		let report = no_errors(serde_json::json!({ "errors": [{ "message": "boom" }] }));
+		assert!(!report.passed);
+		assert!(report.message.contains("boom"), "{}", report.message);
+	}
+}
@@ -1,8 +1,13 @@
-use anyhow::{Result, anyhow};
+use std::cmp::Ordering;
+
+use anyhow::{Result, anyhow, bail};
 use regex::Regex;
 use serde_json::Value;
 
-use super::types::{AssertionReport, HeaderAssertionSpec, JsonAssertionSpec};
+use super::types::{
+	AssertionReport, CompareSpec, FieldCommentSpec, HeaderAssertionSpec, JsonAssertionSpec,
+	WildcardMode,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct JsonAssertionContext {
@@ -16,7 +21,85 @@ pub fn assert_json_value_with_context(
 	ctx: &JsonAssertionContext,
 ) -> Result<AssertionReport> {
 	let label = format!("json_assertion_{}", index + 1);
-	let found = lookup_path(actual, &assertion.path);
+	match lookup_wildcard(actual, &assertion.path) {
+		Some(branches) => assert_wildcard(branches, assertion, label, ctx),
+		None => {
+			let collected = collect_path(actual, &assertion.path);
+			assert_found(collected.as_ref(), assertion, label, ctx)
+		}
+	}
+}
+
+/// Applies `assertion` to every `[*]` branch, combining the results per `wildcard`.
+fn assert_wildcard(
+	branches: Vec<(String, Option<&Value>)>,
+	assertion: &JsonAssertionSpec,
+	label: String,
+	ctx: &JsonAssertionContext,
+) -> Result<AssertionReport> {
+	let total = branches.len();
+	let mut failures = Vec::new();
+	for (path, found) in branches {
+		let branch = JsonAssertionSpec {
+			path,
+			..assertion.clone()
+		};
+		let report = assert_found(found, &branch, label.clone(), ctx)?;
+		if !report.passed {
+			failures.push(report.message);
+		}
+	}
+
+	let (passed, message) = match assertion.wildcard {
+		WildcardMode::All => match failures.first() {
+			None => (
+				true,
+				format!(
+					"path '{}' held for all {} element(s)",
+					assertion.path, total
+				),
+			),
+			Some(first) => (
+				false,
+				format!(
+					"path '{}' failed for {} of {} element(s): {}",
+					assertion.path,
+					failures.len(),
+					total,
+					first
+				),
+			),
+		},
+		WildcardMode::Any if failures.len() < total => (
+			true,
+			format!(
+				"path '{}' held for {} of {} element(s)",
+				assertion.path,
+				total - failures.len(),
+				total
+			),
+		),
+		WildcardMode::Any => (
+			false,
+			format!(
+				"path '{}' held for none of {} element(s)",
+				assertion.path, total
+			),
+		),
+	};
+	Ok(AssertionReport {
+		name: label,
+		passed,
+		message,
+	})
+}
+
+fn assert_found(
+	found: Option<&Value>,
+	assertion: &JsonAssertionSpec,
+	label: String,
+	ctx: &JsonAssertionContext,
+) -> Result<AssertionReport> {
 	let exists = found.is_some();
 
 	if let Some(expected_exists) = assertion.exists {
@@ -42,19 +125,74 @@ pub fn assert_json_value_with_context(
 
 	let value = found.expect("checked above");
 
-	if let Some(expected) = &assertion.equals {
-		if value != expected {
+	if let Some(expected) = &assertion.type_equals {
+		if !JSON_TYPES.contains(&expected.as_str()) {
+			return Err(anyhow!(
+				"invalid type_equals '{}' for path '{}': expected one of {}",
+				expected,
+				assertion.path,
+				JSON_TYPES.join(", ")
+			));
+		}
+		let actual = json_type(value);
+		if actual != expected {
 			return Ok(AssertionReport {
 				name: label,
 				passed: false,
 				message: format!(
-					"path '{}' expected {:?}, got {:?}",
-					assertion.path, expected, value
+					"path '{}' expected type '{}', got '{}'",
+					assertion.path, expected, actual
 				),
 			});
 		}
 	}
 
+	if let Some(expected) = &assertion.equals
+		&& json_equals(expected, value) == assertion.negate
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"path '{}' expected {}{:?}, got {:?}",
+				assertion.path,
+				negation(assertion.negate),
+				expected,
+				value
+			),
+		});
+	}
+
+	if let Some(options) = &assertion.any_of
+		&& !options.iter().any(|option| json_equals(option, value))
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"path '{}' expected one of {}, got {:?}",
+				assertion.path,
+				list_values(options),
+				value
+			),
+		});
+	}
+
+	if let Some(excluded) = &assertion.none_of
+		&& let Some(matched) = excluded.iter().find(|option| json_equals(option, value))
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"path '{}' expected none of {}, got {:?}",
+				assertion.path,
+				list_values(excluded),
+				matched
+			),
+		});
+	}
+
 	if let Some(auth_ref) = &assertion.equals_auth {
 		let Some(auth) = ctx.actor_auth.as_ref() else {
 			return Ok(AssertionReport {
@@ -84,20 +222,25 @@ pub fn assert_json_value_with_context(
 
 	if let Some(substring) = &assertion.contains {
 		let text = value_to_text(value);
-		if !text.contains(substring) {
+		if text.contains(substring) == assertion.negate {
+			let problem = if assertion.negate {
+				"unexpected"
+			} else {
+				"missing"
+			};
 			return Ok(AssertionReport {
 				name: label,
 				passed: false,
 				message: format!(
-					"path '{}' missing substring '{}' in '{}'",
-					assertion.path, substring, text
+					"path '{}' {} substring '{}' in '{}'",
+					assertion.path, problem, substring, text
 				),
 			});
 		}
 	}
 
 	if let Some(pattern) = &assertion.regex {
-		let re = Regex::new(pattern).map_err(|e| {
+		let re = compile_regex(pattern, assertion.regex_flags.as_deref()).map_err(|e| {
 			anyhow!(
 				"invalid regex '{}' for path '{}': {}",
 				pattern,
@@ -106,18 +249,167 @@ pub fn assert_json_value_with_context(
 			)
 		})?;
 		let text = value_to_text(value);
-		if !re.is_match(&text) {
+		if re.is_match(&text) == assertion.negate {
+			let outcome = if assertion.negate {
+				"unexpectedly matched"
+			} else {
+				"did not match"
+			};
+			return Ok(AssertionReport {
+				name: label,
+				passed: false,
+				message: format!(
+					"path '{}' regex '{}' {} '{}'",
+					assertion.path, pattern, outcome, text
+				),
+			});
+		}
+	}
+
+	if let Some(approx) = &assertion.approx {
+		let (low, high) = (approx.value - approx.epsilon, approx.value + approx.epsilon);
+		match value.as_f64() {
+			Some(number) if (number - approx.value).abs() <= approx.epsilon => {}
+			Some(number) => {
+				return Ok(AssertionReport {
+					name: label,
+					passed: false,
+					message: format!(
+						"path '{}' expected {} ± {} (within [{}, {}]), got {}",
+						assertion.path, approx.value, approx.epsilon, low, high, number
+					),
+				});
+			}
+			None => {
+				return Ok(AssertionReport {
+					name: label,
+					passed: false,
+					message: format!(
+						"path '{}' expected a number within [{}, {}], got {}",
+						assertion.path, low, high, value
+					),
+				});
+			}
+		}
+	}
+
+	for (op, bound) in [
+		(">", &assertion.gt),
+		(">=", &assertion.gte),
+		("<", &assertion.lt),
+		("<=", &assertion.lte),
+	] {
+		let Some(bound) = bound else {
+			continue;
+		};
+		let Value::Number(number) = value else {
+			return Ok(AssertionReport {
+				name: label,
+				passed: false,
+				message: format!(
+					"path '{}' expected a number {} {}, got {}",
+					assertion.path, op, bound, value
+				),
+			});
+		};
+		let holds = compare_numbers(number, bound).is_some_and(|ord| match op {
+			">" => ord.is_gt(),
+			">=" => ord.is_ge(),
+			"<" => ord.is_lt(),
+			_ => ord.is_le(),
+		});
+		if !holds {
 			return Ok(AssertionReport {
 				name: label,
 				passed: false,
 				message: format!(
-					"path '{}' regex '{}' did not match '{}'",
-					assertion.path, pattern, text
+					"path '{}' expected {} {}, got {}",
+					assertion.path, op, bound, number
 				),
 			});
 		}
 	}
 
+	if let Some(expected) = assertion.length {
+		let actual_len = match value {
+			Value::String(text) => Some(text.chars().count()),
+			Value::Array(items) => Some(items.len()),
+			Value::Object(map) => Some(map.len()),
+			_ => None,
+		};
+		if actual_len != Some(expected) {
+			let got = match actual_len {
+				Some(len) => format!("{len}"),
+				None => format!("value without a length {:?}", value),
+			};
+			return Ok(AssertionReport {
+				name: label,
+				passed: false,
+				message: format!(
+					"path '{}' expected length {}, got {}",
+					assertion.path, expected, got
+				),
+			});
+		}
+	}
+
+	if let Some(expected) = &assertion.includes {
+		let found = value
+			.as_array()
+			.is_some_and(|items| items.iter().any(|item| json_equals(expected, item)));
+		if !found {
+			return Ok(AssertionReport {
+				name: label,
+				passed: false,
+				message: format!(
+					"path '{}' expected an array including {:?}, got {:?}",
+					assertion.path, expected, value
+				),
+			});
+		}
+	}
+
+	if let Some(expected) = &assertion.all_match {
+		let mismatch = match value.as_array() {
+			Some(items) => items
+				.iter()
+				.enumerate()
+				.find(|(_, item)| !json_equals(expected, item))
+				.map(|(idx, item)| format!("element {} is {:?}", idx, item)),
+			None => Some(format!("got non-array {:?}", value)),
+		};
+		if let Some(mismatch) = mismatch {
+			return Ok(AssertionReport {
+				name: label,
+				passed: false,
+				message: format!(
+					"path '{}' expected every element to equal {:?}, {}",
+					assertion.path, expected, mismatch
+				),
+			});
+		}
+	}
+
+	if let Some(compare) = &assertion.compare
+		&& let Some(message) = compare_failure(value, compare)?
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!("path '{}' {}", assertion.path, message),
+		});
+	}
+
+	if let Some(item_spec) = &assertion.contains_item
+		&& let Some(message) = contains_item_failure(value, item_spec, ctx)?
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!("path '{}' {}", assertion.path, message),
+		});
+	}
+
 	Ok(AssertionReport {
 		name: label,
 		passed: true,
@@ -125,6 +417,122 @@ pub fn assert_json_value_with_context(
 	})
 }
 
+/// `None` when `left_path <op> right_path` holds within `value`; otherwise shows both sides.
+fn compare_failure(value: &Value, spec: &CompareSpec) -> Result<Option<String>> {
+	let holds: fn(Ordering) -> bool = match spec.op.as_str() {
+		"==" => Ordering::is_eq,
+		"!=" => Ordering::is_ne,
+		"<" => Ordering::is_lt,
+		"<=" => Ordering::is_le,
+		">" => Ordering::is_gt,
+		">=" => Ordering::is_ge,
+		other => bail!(
+			"invalid compare op '{}': expected one of ==, !=, <, <=, >, >=",
+			other
+		),
+	};
+	let Some(left) = collect_path(value, &spec.left_path) else {
+		return Ok(Some(format!("compare path '{}' not found", spec.left_path)));
+	};
+	let Some(right) = collect_path(value, &spec.right_path) else {
+		return Ok(Some(format!(
+			"compare path '{}' not found",
+			spec.right_path
+		)));
+	};
+
+	let ordering = match (&left, &right) {
+		(Value::Number(a), Value::Number(b)) => compare_numbers(a, b),
+		(Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+		_ if matches!(spec.op.as_str(), "==" | "!=") => Some(if json_equals(&left, &right) {
+			Ordering::Equal
+		} else {
+			Ordering::Less
+		}),
+		_ => None,
+	};
+	Ok(match ordering {
+		Some(ordering) if holds(ordering) => None,
+		Some(_) => Some(format!(
+			"expected {} ({}) {} {} ({})",
+			spec.left_path, left, spec.op, spec.right_path, right
+		)),
+		None => Some(format!(
+			"cannot compare {} ({}) {} {} ({})",
+			spec.left_path, left, spec.op, spec.right_path, right
+		)),
+	})
+}
+
+/// Compiles `pattern` with the inline `flags` `i`, `m` and `s`.
+fn compile_regex(pattern: &str, flags: Option<&str>) -> Result<Regex> {
+	let flags = flags.unwrap_or_default();
+	if let Some(flag) = flags.chars().find(|flag| !"ims".contains(*flag)) {
+		bail!("unsupported regex flag '{}', expected i, m or s", flag);
+	}
+	if flags.is_empty() {
+		return Ok(Regex::new(pattern)?);
+	}
+	Ok(Regex::new(&format!("(?{flags}){pattern}"))?)
+}
+
+fn list_values(values: &[Value]) -> String {
+	let items: Vec<String> = values.iter().map(Value::to_string).collect();
+	format!("[{}]", items.join(", "))
+}
+
+/// Compares integers exactly and falls back to floats for mixed or fractional values.
+fn compare_numbers(a: &serde_json::Number, b: &serde_json::Number) -> Option<Ordering> {
+	if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+		return Some(a.cmp(&b));
+	}
+	if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+		return Some(a.cmp(&b));
+	}
+	a.as_f64()?.partial_cmp(&b.as_f64()?)
+}
+
+/// `None` when some array element satisfies every sub-assertion; otherwise describes the
+/// element that satisfied the most of them.
+fn contains_item_failure(
+	value: &Value,
+	item_spec: &[JsonAssertionSpec],
+	ctx: &JsonAssertionContext,
+) -> Result<Option<String>> {
+	let Some(items) = value.as_array() else {
+		return Ok(Some(format!("expected an array, got {:?}", value)));
+	};
+	let mut closest: Option<(usize, &Value, String)> = None;
+	for item in items {
+		let mut passed = 0;
+		let mut first_failure = None;
+		for (idx, sub) in item_spec.iter().enumerate() {
+			let report = assert_json_value_with_context(item, sub, idx, ctx)?;
+			if report.passed {
+				passed += 1;
+			} else {
+				first_failure.get_or_insert(report.message);
+			}
+		}
+		let Some(failure) = first_failure else {
+			return Ok(None);
+		};
+		if closest.as_ref().is_none_or(|(best, _, _)| passed > *best) {
+			closest = Some((passed, item, failure));
+		}
+	}
+	Ok(Some(match closest {
+		Some((passed, item, failure)) => format!(
+			"has no element matching all {} sub-assertions; closest {:?} matched {} ({})",
+			item_spec.len(),
+			item,
+			passed,
+			failure
+		),
+		None => "has no element matching: array is empty".to_string(),
+	}))
+}
+
 pub fn assert_header_value(
 	headers: &reqwest::header::HeaderMap,
 	assertion: &HeaderAssertionSpec,
@@ -161,34 +569,42 @@ pub fn assert_header_value(
 
 	let value = found.expect("checked above");
 
-	if let Some(expected) = &assertion.equals {
-		if &value != expected {
-			return Ok(AssertionReport {
-				name: label,
-				passed: false,
-				message: format!(
-					"header '{}' expected '{}' got '{}'",
-					assertion.name, expected, value
-				),
-			});
-		}
+	if let Some(expected) = &assertion.equals
+		&& (&value == expected) == assertion.negate
+	{
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"header '{}' expected {}'{}' got '{}'",
+				assertion.name,
+				negation(assertion.negate),
+				expected,
+				value
+			),
+		});
 	}
 
-	if let Some(part) = &assertion.contains {
-		if !value.contains(part) {
-			return Ok(AssertionReport {
-				name: label,
-				passed: false,
-				message: format!(
-					"header '{}' missing substring '{}' in '{}'",
-					assertion.name, part, value
-				),
-			});
-		}
+	if let Some(part) = &assertion.contains
+		&& value.contains(part) == assertion.negate
+	{
+		let problem = if assertion.negate {
+			"unexpected"
+		} else {
+			"missing"
+		};
+		return Ok(AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"header '{}' {} substring '{}' in '{}'",
+				assertion.name, problem, part, value
+			),
+		});
 	}
 
 	if let Some(pattern) = &assertion.regex {
-		let re = Regex::new(pattern).map_err(|e| {
+		let re = compile_regex(pattern, assertion.regex_flags.as_deref()).map_err(|e| {
 			anyhow!(
 				"invalid header regex '{}' for '{}': {}",
 				pattern,
@@ -196,13 +612,18 @@ pub fn assert_header_value(
 				e
 			)
 		})?;
-		if !re.is_match(&value) {
+		if re.is_match(&value) == assertion.negate {
+			let outcome = if assertion.negate {
+				"unexpectedly matched"
+			} else {
+				"did not match"
+			};
 			return Ok(AssertionReport {
 				name: label,
 				passed: false,
 				message: format!(
-					"header '{}' regex '{}' did not match '{}'",
-					assertion.name, pattern, value
+					"header '{}' regex '{}' {} '{}'",
+					assertion.name, pattern, outcome, value
 				),
 			});
 		}
@@ -215,6 +636,280 @@ pub fn assert_header_value(
 	})
 }
 
+fn negation(negate: bool) -> &'static str {
+	if negate { "not " } else { "" }
+}
+
+pub fn assert_field_comment(info: &Value, spec: &FieldCommentSpec) -> AssertionReport {
+	let label = "field_comment".to_string();
+	let Some(definition) = info
+		.get("fields")
+		.and_then(|fields| fields.get(&spec.name))
+		.and_then(Value::as_str)
+	else {
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!("field '{}' not found in table info", spec.name),
+		};
+	};
+	let Some(comment) = definition_comment(definition) else {
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!("field '{}' has no COMMENT", spec.name),
+		};
+	};
+
+	if let Some(expected) = &spec.equals
+		&& &comment != expected
+	{
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"field '{}' comment expected '{}', got '{}'",
+				spec.name, expected, comment
+			),
+		};
+	}
+	if let Some(part) = &spec.contains
+		&& !comment.contains(part.as_str())
+	{
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"field '{}' comment missing '{}' in '{}'",
+				spec.name, part, comment
+			),
+		};
+	}
+	AssertionReport {
+		name: label,
+		passed: true,
+		message: format!("field '{}' comment is '{}'", spec.name, comment),
+	}
+}
+
+/// Extracts the string after a `COMMENT` keyword that is not itself inside a string.
+fn definition_comment(definition: &str) -> Option<String> {
+	let mut chars = definition.char_indices();
+	let mut quote = None;
+	while let Some((idx, ch)) = chars.next() {
+		match (quote, ch) {
+			(Some(_), '\\') => {
+				chars.next();
+			}
+			(Some(open), ch) if ch == open => quote = None,
+			(Some(_), _) => {}
+			(None, '\'' | '"') => quote = Some(ch),
+			(None, _) => {
+				let rest = &definition[idx..];
+				let boundary = definition[..idx]
+					.chars()
+					.next_back()
+					.is_none_or(char::is_whitespace);
+				let keyword = rest
+					.get(.."COMMENT".len())
+					.is_some_and(|word| word.eq_ignore_ascii_case("COMMENT"));
+				if boundary
+					&& keyword && let Some(comment) =
+					read_string_literal(rest["COMMENT".len()..].trim_start())
+				{
+					return Some(comment);
+				}
+			}
+		}
+	}
+	None
+}
+
+fn read_string_literal(raw: &str) -> Option<String> {
+	let mut chars = raw.chars();
+	let open = chars.next().filter(|c| matches!(c, '\'' | '"'))?;
+	let mut out = String::new();
+	while let Some(ch) = chars.next() {
+		match ch {
+			'\\' => out.push(chars.next()?),
+			ch if ch == open => return Some(out),
+			ch => out.push(ch),
+		}
+	}
+	None
+}
+
+pub fn assert_created_in_table(actual: &Value, table: &str) -> AssertionReport {
+	let label = "created_in_table".to_string();
+	let records: Vec<&Value> = match actual {
+		Value::Array(items) => items.iter().collect(),
+		Value::Object(_) => vec![actual],
+		_ => Vec::new(),
+	};
+	if records.is_empty() {
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"expected created record(s) in table '{}', got {}",
+				table, actual
+			),
+		};
+	}
+
+	for record in records {
+		let Some(id) = record.get("id") else {
+			return AssertionReport {
+				name: label,
+				passed: false,
+				message: format!("created record has no id: {}", record),
+			};
+		};
+		match record_id_table(id) {
+			Some(found) if found == table => {}
+			Some(found) => {
+				return AssertionReport {
+					name: label,
+					passed: false,
+					message: format!(
+						"expected record id in table '{}', got '{}' ({})",
+						table,
+						found,
+						value_to_text(id)
+					),
+				};
+			}
+			None => {
+				return AssertionReport {
+					name: label,
+					passed: false,
+					message: format!("could not parse record id {}", id),
+				};
+			}
+		}
+	}
+
+	AssertionReport {
+		name: label,
+		passed: true,
+		message: format!("record(s) created in table '{}'", table),
+	}
+}
+
+/// Checks that the statement at `index` (zero-based) failed and every earlier statement succeeded.
+pub fn assert_error_at_index(statement_errors: &[Option<String>], index: usize) -> AssertionReport {
+	let label = "error_at_index".to_string();
+	if let Some(earlier) = statement_errors
+		.iter()
+		.take(index)
+		.position(Option::is_some)
+	{
+		return AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"expected statement {} to fail, but statement {} failed first: {}",
+				index,
+				earlier,
+				statement_errors[earlier].as_deref().unwrap_or_default()
+			),
+		};
+	}
+
+	match statement_errors.get(index) {
+		Some(Some(err)) => AssertionReport {
+			name: label,
+			passed: true,
+			message: format!("statement {} failed as expected: {}", index, err),
+		},
+		Some(None) => AssertionReport {
+			name: label,
+			passed: false,
+			message: format!("expected statement {} to fail, but it succeeded", index),
+		},
+		None => AssertionReport {
+			name: label,
+			passed: false,
+			message: format!(
+				"expected statement {} to fail, but the query has only {} statement(s)",
+				index,
+				statement_errors.len()
+			),
+		},
+	}
+}
+
+fn record_id_table(id: &Value) -> Option<String> {
+	parse_record_id(id).map(|(table, _)| table)
+}
+
+/// Equality that treats record ids as equal regardless of how their parts are quoted.
+fn json_equals(expected: &Value, actual: &Value) -> bool {
+	if expected == actual {
+		return true;
+	}
+	if !expected.is_string() {
+		return false;
+	}
+	match (parse_record_id(expected), parse_record_id(actual)) {
+		(Some(expected), Some(actual)) => expected == actual,
+		_ => false,
+	}
+}
+
+/// Splits `table:id` (string or `{ tb, id }` object) into unquoted parts. `None` unless
+/// the table is an identifier (or a quoted one) and the id is non-empty and not padded
+/// with whitespace, so ordinary strings such as `status: ok` are not record ids.
+fn parse_record_id(id: &Value) -> Option<(String, String)> {
+	let (table, key) = match id {
+		Value::String(text) => {
+			let (table, key) = text.split_once(':')?;
+			(table.to_string(), key.to_string())
+		}
+		Value::Object(obj) => {
+			let table = obj
+				.get("tb")
+				.or_else(|| obj.get("table"))
+				.and_then(Value::as_str)?;
+			let key = obj.get("id").map(value_to_text).unwrap_or_default();
+			(table.to_string(), key)
+		}
+		_ => return None,
+	};
+	let table = match unquote_ident(&table) {
+		Some(quoted) => quoted,
+		None if is_plain_ident(&table) => &table,
+		None => return None,
+	};
+	let key = match unquote_ident(&key) {
+		Some(quoted) => quoted,
+		None if !key.is_empty() && key.trim() == key => &key,
+		None => return None,
+	};
+	(!table.is_empty()).then(|| (table.to_string(), key.to_string()))
+}
+
+fn is_plain_ident(raw: &str) -> bool {
+	let mut chars = raw.chars();
+	chars
+		.next()
+		.is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The inside of a quoted identifier, or `None` when `raw` is not quoted.
+fn unquote_ident(raw: &str) -> Option<&str> {
+	for (open, close) in [('`', '`'), ('⟨', '⟩'), ('"', '"'), ('\'', '\'')] {
+		if let Some(inner) = raw
+			.strip_prefix(open)
+			.and_then(|rest| rest.strip_suffix(close))
+		{
+			return Some(inner);
+		}
+	}
+	None
+}
+
 fn value_to_text(value: &Value) -> String {
 	match value {
 		Value::String(v) => v.to_string(),
@@ -222,28 +917,279 @@ fn value_to_text(value: &Value) -> String {
 	}
 }
 
+/// Resolves a dot path such as `a.b.1.c`. Keys containing dots or other special
+/// characters can be written in brackets: `fields["address.city"].kind`; `[1]` indexes arrays.
+/// Paths with a `[*]` wildcard or `*` segment resolve to `None`; see [`lookup_wildcard`]
+/// and [`collect_path`]. Paths starting with `/` are JSON Pointers; see [`lookup_by_pointer`].
 pub fn lookup_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+	if path.starts_with('/') {
+		return lookup_by_pointer(value, path);
+	}
 	if path.trim().is_empty() {
 		return Some(value);
 	}
 
 	let mut cursor = value;
-	for seg in path.split('.') {
-		if seg.is_empty() {
-			continue;
+	for seg in parse_path(path)? {
+		cursor = step(cursor, &seg)?;
+	}
+
+	Some(cursor)
+}
+
+/// Resolves an RFC 6901 JSON Pointer such as `/a/b.c/0`. `~1` decodes to `/` and `~0`
+/// to `~`; segments index arrays when the current value is an array.
+pub fn lookup_by_pointer<'a>(value: &'a Value, pointer: &str) -> Option<&'a Value> {
+	if pointer.is_empty() {
+		return Some(value);
+	}
+	let mut cursor = value;
+	for raw in pointer.strip_prefix('/')?.split('/') {
+		let token = raw.replace("~1", "/").replace("~0", "~");
+		cursor = match cursor {
+			Value::Array(items) => items.get(pointer_index(&token)?)?,
+			Value::Object(map) => map.get(&token)?,
+			_ => return None,
+		};
+	}
+	Some(cursor)
+}
+
+/// Array index token: digits only, without leading zeros.
+fn pointer_index(token: &str) -> Option<usize> {
+	if token.is_empty()
+		|| !token.bytes().all(|b| b.is_ascii_digit())
+		|| (token.len() > 1 && token.starts_with('0'))
+	{
+		return None;
+	}
+	token.parse().ok()
+}
+
+/// Expands each `[*]` over the elements of its array, returning every concrete path
+/// with its value (`None` where the rest of the path is missing). `None` when the
+/// path has no wildcard or cannot be parsed.
+fn lookup_wildcard<'a>(value: &'a Value, path: &str) -> Option<Vec<(String, Option<&'a Value>)>> {
+	if path.starts_with('/') {
+		return None;
+	}
+	let segments = parse_path(path)?;
+	if !segments
+		.iter()
+		.any(|seg| matches!(seg, PathSegment::Wildcard))
+	{
+		return None;
+	}
+
+	let mut branches = vec![(String::new(), Some(value))];
+	for seg in &segments {
+		let mut next = Vec::new();
+		for (prefix, cursor) in branches {
+			match (seg, cursor.map(|cursor| (cursor, cursor.as_array()))) {
+				(PathSegment::Wildcard, Some((_, Some(items)))) => {
+					for (idx, item) in items.iter().enumerate() {
+						next.push((format!("{prefix}[{idx}]"), Some(item)));
+					}
+				}
+				_ => {
+					let path = match seg {
+						PathSegment::Key(key) if prefix.is_empty() => key.clone(),
+						PathSegment::Key(key) => format!("{prefix}.{key}"),
+						PathSegment::Literal(key) => format!("{prefix}[{key:?}]"),
+						PathSegment::Index(index) => format!("{prefix}[{index}]"),
+						PathSegment::Wildcard => format!("{prefix}[*]"),
+						PathSegment::Collect => format!("{prefix}.*"),
+					};
+					next.push((path, cursor.and_then(|cursor| step(cursor, seg))));
+				}
+			}
 		}
+		branches = next;
+	}
+	Some(branches)
+}
+
+/// Like [`lookup_path`], but a `*` segment maps the rest of the path over an array and
+/// collects the results, skipping elements where it is missing: `items.*.price` on
+/// `{"items":[{"price":1},{"price":2}]}` is `[1,2]`.
+pub fn collect_path(value: &Value, path: &str) -> Option<Value> {
+	if path.starts_with('/') {
+		return lookup_by_pointer(value, path).cloned();
+	}
+	if path.trim().is_empty() {
+		return Some(value.clone());
+	}
+	collect_segments(value, &parse_path(path)?)
+}
+
+fn collect_segments(value: &Value, segments: &[PathSegment]) -> Option<Value> {
+	match segments.split_first() {
+		None => Some(value.clone()),
+		Some((PathSegment::Collect, rest)) => Some(Value::Array(
+			value
+				.as_array()?
+				.iter()
+				.filter_map(|item| collect_segments(item, rest))
+				.collect(),
+		)),
+		Some((seg, rest)) => collect_segments(step(value, seg)?, rest),
+	}
+}
 
-		if let Ok(index) = seg.parse::<usize>() {
-			let arr = cursor.as_array()?;
-			cursor = arr.get(index)?;
+/// Placeholder written over values matched by a `redact` path.
+pub const REDACTED: &str = "<redacted>";
+
+/// Replaces every value at `paths` with [`REDACTED`]. Paths use the [`lookup_path`]
+/// syntax; `[*]` and `*` both cover every array element. Missing paths are skipped.
+pub fn redact_paths(value: &mut Value, paths: &[String]) -> Result<()> {
+	for path in paths {
+		if path.starts_with('/') {
+			if let Some(found) = value.pointer_mut(path) {
+				*found = Value::String(REDACTED.to_string());
+			}
 			continue;
 		}
+		let segments = parse_path(path).ok_or_else(|| anyhow!("invalid redact path '{}'", path))?;
+		redact_segments(value, &segments);
+	}
+	Ok(())
+}
 
-		let obj = cursor.as_object()?;
-		cursor = obj.get(seg)?;
+fn redact_segments(value: &mut Value, segments: &[PathSegment]) {
+	match segments.split_first() {
+		None => *value = Value::String(REDACTED.to_string()),
+		Some((PathSegment::Wildcard | PathSegment::Collect, rest)) => {
+			if let Some(items) = value.as_array_mut() {
+				for item in items {
+					redact_segments(item, rest);
+				}
+			}
+		}
+		Some((seg, rest)) => {
+			if let Some(next) = step_mut(value, seg) {
+				redact_segments(next, rest);
+			}
+		}
 	}
+}
 
-	Some(cursor)
+fn step_mut<'a>(cursor: &'a mut Value, seg: &PathSegment) -> Option<&'a mut Value> {
+	match seg {
+		PathSegment::Key(key) => {
+			if let Ok(index) = key.parse::<usize>() {
+				cursor.as_array_mut()?.get_mut(index)
+			} else {
+				cursor.as_object_mut()?.get_mut(key)
+			}
+		}
+		PathSegment::Literal(key) => cursor.as_object_mut()?.get_mut(key),
+		PathSegment::Index(index) => cursor.as_array_mut()?.get_mut(*index),
+		PathSegment::Wildcard | PathSegment::Collect => None,
+	}
+}
+
+fn step<'a>(cursor: &'a Value, seg: &PathSegment) -> Option<&'a Value> {
+	match seg {
+		PathSegment::Key(key) => {
+			if let Ok(index) = key.parse::<usize>() {
+				cursor.as_array()?.get(index)
+			} else {
+				cursor.as_object()?.get(key)
+			}
+		}
+		PathSegment::Literal(key) => cursor.as_object()?.get(key),
+		PathSegment::Index(index) => cursor.as_array()?.get(*index),
+		PathSegment::Wildcard | PathSegment::Collect => None,
+	}
+}
+
+enum PathSegment {
+	/// Dot-separated segment; numeric segments index arrays.
+	Key(String),
+	/// Quoted bracket segment, always an object key.
+	Literal(String),
+	/// Unquoted bracket segment such as `[1]`.
+	Index(usize),
+	/// `[*]`: every element of an array.
+	Wildcard,
+	/// `*`: the rest of the path collected from every element of an array.
+	Collect,
+}
+
+/// Splits a path into segments. `None` for unterminated brackets or quotes.
+fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
+	let mut segments = Vec::new();
+	let mut current = String::new();
+	let mut chars = path.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'.' => {
+				if !current.is_empty() {
+					segments.push(PathSegment::Key(std::mem::take(&mut current)));
+				}
+			}
+			'[' => {
+				if !current.is_empty() {
+					segments.push(PathSegment::Key(std::mem::take(&mut current)));
+				}
+				match chars.peek().copied() {
+					Some(quote @ ('"' | '\'')) => {
+						chars.next();
+						let mut key = String::new();
+						loop {
+							match chars.next()? {
+								'\\' => key.push(chars.next()?),
+								ch if ch == quote => break,
+								ch => key.push(ch),
+							}
+						}
+						if chars.next()? != ']' {
+							return None;
+						}
+						segments.push(PathSegment::Literal(key));
+					}
+					_ => {
+						let mut raw = String::new();
+						loop {
+							match chars.next()? {
+								']' => break,
+								ch => raw.push(ch),
+							}
+						}
+						let raw = raw.trim();
+						if raw == "*" {
+							segments.push(PathSegment::Wildcard);
+						} else {
+							segments.push(PathSegment::Index(raw.parse().ok()?));
+						}
+					}
+				}
+			}
+			_ => current.push(c),
+		}
+	}
+	if !current.is_empty() {
+		segments.push(PathSegment::Key(current));
+	}
+	for seg in &mut segments {
+		if matches!(seg, PathSegment::Key(key) if key == "*") {
+			*seg = PathSegment::Collect;
+		}
+	}
+	Some(segments)
+}
+
+const JSON_TYPES: &[&str] = &["string", "number", "boolean", "null", "array", "object"];
+
+fn json_type(value: &Value) -> &'static str {
+	match value {
+		Value::String(_) => "string",
+		Value::Number(_) => "number",
+		Value::Bool(_) => "boolean",
+		Value::Null => "null",
+		Value::Array(_) => "array",
+		Value::Object(_) => "object",
+	}
 }
 
 fn lookup_auth_value<'a>(auth: &'a Value, auth_ref: &str) -> Option<&'a Value> {
@@ -257,6 +1203,7 @@ fn lookup_auth_value<'a>(auth: &'a Value, auth_ref: &str) -> Option<&'a Value> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::tester::types::ApproxSpec;
 
 	#[test]
 	fn lookup_path_supports_objects_and_arrays() {
@@ -272,6 +1219,145 @@ mod tests {
 		assert_eq!(got, &serde_json::json!(2));
 	}
 
+	#[test]
+	fn lookup_path_supports_nested_bracket_indexes() {
+		let value: Value = serde_json::json!({
+			"a": [
+				{ "b": [0, 1] },
+				{ "b": [10, 11, 12] }
+			]
+		});
+		assert_eq!(
+			lookup_path(&value, "a[1].b[2]"),
+			Some(&serde_json::json!(12))
+		);
+		assert_eq!(
+			lookup_path(&value, "a.1.b.2"),
+			lookup_path(&value, "a[1].b[2]")
+		);
+		assert_eq!(lookup_path(&value, "a[1].b[3]"), None);
+		assert_eq!(lookup_path(&value, "a[x]"), None);
+	}
+
+	#[test]
+	fn lookup_path_supports_json_pointers() {
+		let value: Value = serde_json::json!({
+			"fields": { "address.city": { "kind": "string" } },
+			"a/b": { "m~n": [10, 20] },
+			"items": [{ "0": "key" }]
+		});
+		assert_eq!(
+			lookup_by_pointer(&value, "/fields/address.city/kind"),
+			Some(&Value::String("string".to_string()))
+		);
+		assert_eq!(lookup_path(&value, "/a~1b/m~0n/1"), Some(&Value::from(20)));
+		assert_eq!(
+			lookup_path(&value, "/items/0/0"),
+			Some(&Value::String("key".to_string()))
+		);
+		assert_eq!(lookup_by_pointer(&value, ""), Some(&value));
+		assert_eq!(lookup_by_pointer(&value, "/a~1b/m~0n/01"), None);
+		assert_eq!(lookup_by_pointer(&value, "/a~1b/m~0n/-"), None);
+		assert_eq!(lookup_by_pointer(&value, "fields"), None);
+
+		let assertion: JsonAssertionSpec =
+			toml::from_str("path = \"/fields/address.city/kind\"\nequals = \"string\"\n")
+				.expect("assertion should parse");
+		let report =
+			assert_json_value_with_context(&value, &assertion, 0, &JsonAssertionContext::default())
+				.expect("assertion");
+		assert!(report.passed, "{}", report.message);
+	}
+
+	#[test]
+	fn lookup_path_supports_bracketed_keys() {
+		// Shape of `INFO FOR TABLE person` with a nested field.
+		let value: Value = serde_json::json!({
+			"fields": {
+				"address.city": { "type": "string" },
+				"tags": ["a", "b"]
+			}
+		});
+		assert_eq!(
+			lookup_path(&value, r#"fields["address.city"]"#),
+			Some(&serde_json::json!({ "type": "string" }))
+		);
+		assert_eq!(
+			lookup_path(&value, r#"fields['address.city'].type"#),
+			Some(&serde_json::json!("string"))
+		);
+		assert_eq!(
+			lookup_path(&value, "fields.tags[1]"),
+			Some(&serde_json::json!("b"))
+		);
+		assert_eq!(lookup_path(&value, "fields.address.city"), None);
+		assert_eq!(lookup_path(&value, r#"fields["address.city"#), None);
+	}
+
+	#[test]
+	fn created_in_table_checks_record_id_table() {
+		// Result of `CREATE person CONTENT { name: 'Alice' };`
+		let created = serde_json::json!([{ "id": "person:8xk2", "name": "Alice" }]);
+		assert!(assert_created_in_table(&created, "person").passed);
+		assert!(!assert_created_in_table(&created, "account").passed);
+
+		let object_id = serde_json::json!({ "id": { "tb": "person", "id": "8xk2" } });
+		assert!(assert_created_in_table(&object_id, "person").passed);
+
+		assert!(!assert_created_in_table(&serde_json::json!([]), "person").passed);
+	}
+
+	#[test]
+	fn equals_matches_record_ids_regardless_of_quoting() {
+		assert!(json_equals(
+			&serde_json::json!("person:123"),
+			&serde_json::json!("person:⟨123⟩")
+		));
+		assert!(json_equals(
+			&serde_json::json!("person:alice"),
+			&serde_json::json!({ "tb": "person", "id": "alice" })
+		));
+		assert!(json_equals(
+			&serde_json::json!("`person`:123"),
+			&serde_json::json!({ "tb": "person", "id": 123 })
+		));
+		assert!(!json_equals(
+			&serde_json::json!("person:123"),
+			&serde_json::json!("person:124")
+		));
+	}
+
+	#[test]
+	fn plain_strings_with_a_colon_are_not_record_ids() {
+		for (expected, actual) in [
+			("status: ok", "status:ok"),
+			("status:ok", "status: ok"),
+			(" person:1", "person:1"),
+			("note: see docs", "note:see docs"),
+			("12:30", "12:30 "),
+			("a b:c", "a b: c"),
+		] {
+			assert!(
+				!json_equals(&serde_json::json!(expected), &serde_json::json!(actual)),
+				"{expected:?} vs {actual:?}"
+			);
+		}
+		assert_eq!(parse_record_id(&serde_json::json!("12:30")), None);
+		assert_eq!(parse_record_id(&serde_json::json!("status: ok")), None);
+
+		let spec = JsonAssertionSpec {
+			path: "status".to_string(),
+			equals: Some(serde_json::json!("status: ok")),
+			negate: true,
+			..Default::default()
+		};
+		let actual = serde_json::json!({ "status": "status:ok" });
+		let report =
+			assert_json_value_with_context(&actual, &spec, 0, &JsonAssertionContext::default())
+				.expect("assertion");
+		assert!(report.passed, "{}", report.message);
+	}
+
 	#[test]
 	fn assertion_can_compare_against_auth_reference() {
 		let actual = serde_json::json!({
@@ -279,11 +1365,8 @@ mod tests {
 		});
 		let assertion = JsonAssertionSpec {
 			path: "owner".to_string(),
-			exists: None,
-			equals: None,
 			equals_auth: Some("$auth.id".to_string()),
-			contains: None,
-			regex: None,
+			..Default::default()
 		};
 		let ctx = JsonAssertionContext {
 			actor_auth: Some(serde_json::json!({
@@ -305,11 +1388,8 @@ mod tests {
 		});
 		let assertion = JsonAssertionSpec {
 			path: "".to_string(),
-			exists: None,
-			equals: None,
 			equals_auth: Some("$auth".to_string()),
-			contains: None,
-			regex: None,
+			..Default::default()
 		};
 		let ctx = JsonAssertionContext {
 			actor_auth: Some(actual.clone()),
@@ -319,4 +1399,596 @@ mod tests {
 			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok");
 		assert!(report.passed, "{}", report.message);
 	}
+
+	#[test]
+	fn error_at_index_requires_earlier_statements_to_succeed() {
+		let errors = vec![None, Some("field email must be unique".to_string())];
+		assert!(assert_error_at_index(&errors, 1).passed);
+		assert!(!assert_error_at_index(&errors, 0).passed);
+		assert!(!assert_error_at_index(&errors, 2).passed);
+
+		let both_failed = vec![Some("boom".to_string()), Some("boom".to_string())];
+		let report = assert_error_at_index(&both_failed, 1);
+		assert!(!report.passed);
+		assert!(report.message.contains("statement 0 failed first"));
+	}
+
+	fn approx_assertion(path: &str, value: f64, epsilon: f64) -> JsonAssertionSpec {
+		JsonAssertionSpec {
+			path: path.to_string(),
+			approx: Some(ApproxSpec { value, epsilon }),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn approx_passes_within_tolerance_across_ints_and_floats() {
+		let actual = serde_json::json!({ "avg": 2.0000001, "count": 3 });
+		let ctx = JsonAssertionContext::default();
+
+		let within = approx_assertion("avg", 2.0, 0.001);
+		assert!(
+			assert_json_value_with_context(&actual, &within, 0, &ctx)
+				.expect("assertion ok")
+				.passed
+		);
+
+		let int_vs_float = approx_assertion("count", 3.0, 0.0);
+		assert!(
+			assert_json_value_with_context(&actual, &int_vs_float, 0, &ctx)
+				.expect("assertion ok")
+				.passed
+		);
+	}
+
+	#[test]
+	fn approx_reports_window_when_outside_tolerance() {
+		let actual = serde_json::json!({ "distance": 10.5 });
+		let assertion = approx_assertion("distance", 10.0, 0.25);
+		let report = assert_json_value_with_context(
+			&actual,
+			&assertion,
+			0,
+			&JsonAssertionContext::default(),
+		)
+		.expect("assertion ok");
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("[9.75, 10.25]"),
+			"{}",
+			report.message
+		);
+		assert!(report.message.contains("got 10.5"), "{}", report.message);
+	}
+
+	#[test]
+	fn contains_item_matches_any_array_element() {
+		let actual = serde_json::json!([
+			{ "id": "order:1", "status": "pending" },
+			{ "id": "order:2", "status": "active" },
+			{ "id": "order:3", "status": "active" }
+		]);
+		let sub = |path: &str, equals: Value| JsonAssertionSpec {
+			path: path.to_string(),
+			equals: Some(equals),
+			..Default::default()
+		};
+		let assertion = |id: &str| JsonAssertionSpec {
+			path: "".to_string(),
+			contains_item: Some(vec![
+				sub("id", serde_json::json!(id)),
+				sub("status", serde_json::json!("active")),
+			]),
+			..Default::default()
+		};
+		let ctx = JsonAssertionContext::default();
+
+		let report = assert_json_value_with_context(&actual, &assertion("order:2"), 0, &ctx)
+			.expect("assertion ok");
+		assert!(report.passed, "{}", report.message);
+
+		let report = assert_json_value_with_context(&actual, &assertion("order:1"), 0, &ctx)
+			.expect("assertion ok");
+		assert!(!report.passed);
+		assert!(report.message.contains("order:1"), "{}", report.message);
+		assert!(report.message.contains("matched 1"), "{}", report.message);
+	}
+
+	#[test]
+	fn numeric_comparisons_handle_integers_and_floats() {
+		let actual = serde_json::json!({ "count": 5, "ratio": 0.75, "name": "x" });
+		let ctx = JsonAssertionContext::default();
+		let number = |raw: &str| Some(serde_json::from_str(raw).expect("number"));
+		let check = |assertion: JsonAssertionSpec| {
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(
+			check(JsonAssertionSpec {
+				path: "count".into(),
+				gt: number("4"),
+				lte: number("5"),
+				..Default::default()
+			})
+			.passed
+		);
+		assert!(
+			!check(JsonAssertionSpec {
+				path: "count".into(),
+				gt: number("5"),
+				..Default::default()
+			})
+			.passed
+		);
+		assert!(
+			check(JsonAssertionSpec {
+				path: "ratio".into(),
+				gte: number("0.5"),
+				lt: number("1"),
+				..Default::default()
+			})
+			.passed
+		);
+		assert!(
+			!check(JsonAssertionSpec {
+				path: "ratio".into(),
+				lt: number("0.75"),
+				..Default::default()
+			})
+			.passed
+		);
+
+		let report = check(JsonAssertionSpec {
+			path: "name".into(),
+			gt: number("1"),
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("expected a number"),
+			"{}",
+			report.message
+		);
+	}
+
+	#[test]
+	fn compare_checks_fields_of_the_same_record() {
+		let actual = serde_json::json!([{
+			"subtotal": 30,
+			"total": 30.0,
+			"created_at": "2024-01-01T00:00:00Z",
+			"updated_at": "2024-03-01T00:00:00Z"
+		}]);
+		let ctx = JsonAssertionContext::default();
+		let check = |left: &str, op: &str, right: &str| {
+			let assertion: JsonAssertionSpec = toml::from_str(&format!(
+				"path = \"0\"\ncompare = {{ left_path = \"{left}\", op = \"{op}\", right_path = \"{right}\" }}\n"
+			))
+			.expect("assertion should parse");
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx)
+		};
+
+		assert!(
+			check("subtotal", "==", "total")
+				.expect("assertion ok")
+				.passed
+		);
+		assert!(
+			check("created_at", "<=", "updated_at")
+				.expect("assertion ok")
+				.passed
+		);
+		let report = check("updated_at", "<", "created_at").expect("assertion ok");
+		assert!(!report.passed);
+		assert_eq!(
+			report.message,
+			"path '0' expected updated_at (\"2024-03-01T00:00:00Z\") < created_at (\"2024-01-01T00:00:00Z\")"
+		);
+		let report = check("subtotal", ">", "created_at").expect("assertion ok");
+		assert!(
+			report.message.contains("cannot compare"),
+			"{}",
+			report.message
+		);
+		assert!(check("subtotal", "=>", "total").is_err());
+	}
+
+	#[test]
+	fn any_of_and_none_of_check_alternatives() {
+		let actual = serde_json::json!({ "status": "pending" });
+		let ctx = JsonAssertionContext::default();
+		let check = |toml_src: &str| {
+			let assertion: JsonAssertionSpec =
+				toml::from_str(toml_src).expect("assertion should parse");
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(check("path = \"status\"\nany_of = [\"active\", \"pending\"]").passed);
+		let report = check("path = \"status\"\nany_of = [\"active\", \"archived\"]");
+		assert!(!report.passed);
+		assert_eq!(
+			report.message,
+			"path 'status' expected one of [\"active\", \"archived\"], got String(\"pending\")"
+		);
+
+		assert!(check("path = \"status\"\nnone_of = [\"deleted\", \"banned\"]").passed);
+		assert!(!check("path = \"status\"\nnone_of = [\"deleted\", \"pending\"]").passed);
+		assert!(check("path = \"missing\"\nexists = false\nany_of = [\"active\"]").passed);
+	}
+
+	#[test]
+	fn type_equals_checks_the_json_type() {
+		let actual = serde_json::json!({ "age": "42", "tags": [], "deleted_at": null });
+		let ctx = JsonAssertionContext::default();
+		let check = |path: &str, ty: &str| {
+			let assertion = JsonAssertionSpec {
+				path: path.into(),
+				type_equals: Some(ty.into()),
+				..Default::default()
+			};
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx)
+		};
+
+		assert!(check("tags", "array").expect("assertion ok").passed);
+		assert!(check("deleted_at", "null").expect("assertion ok").passed);
+		let report = check("age", "number").expect("assertion ok");
+		assert!(!report.passed);
+		assert_eq!(
+			report.message,
+			"path 'age' expected type 'number', got 'string'"
+		);
+		assert!(check("age", "integer").is_err());
+	}
+
+	#[test]
+	fn each_check_has_a_single_name() {
+		for alias in [
+			"not_equals = \"admin\"",
+			"greater_than = 1",
+			"less_than = 10",
+			"length_equals = 3",
+		] {
+			let parsed = toml::from_str::<JsonAssertionSpec>(&format!("path = \"x\"\n{alias}"));
+			assert!(parsed.is_err(), "{alias} should be rejected");
+		}
+	}
+
+	#[test]
+	fn length_counts_string_characters_and_rejects_scalars() {
+		let actual = serde_json::json!({ "count": 5, "name": "Zoë" });
+		let ctx = JsonAssertionContext::default();
+		let check = |toml_src: &str| {
+			let assertion: JsonAssertionSpec =
+				toml::from_str(toml_src).expect("assertion should parse");
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(check("path = \"name\"\nlength = 3").passed);
+		let report = check("path = \"count\"\nlength = 1");
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("without a length"),
+			"{}",
+			report.message
+		);
+	}
+
+	#[test]
+	fn length_and_includes_check_collections() {
+		let actual = serde_json::json!({
+			"rows": [{ "id": "person:1" }, { "id": "person:2" }],
+			"tags": ["a", "b"],
+			"meta": { "x": 1 }
+		});
+		let ctx = JsonAssertionContext::default();
+		let check = |assertion: JsonAssertionSpec| {
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(
+			check(JsonAssertionSpec {
+				path: "rows".into(),
+				length: Some(2),
+				..Default::default()
+			})
+			.passed
+		);
+		assert!(
+			check(JsonAssertionSpec {
+				path: "meta".into(),
+				length: Some(1),
+				..Default::default()
+			})
+			.passed
+		);
+		let report = check(JsonAssertionSpec {
+			path: "rows".into(),
+			length: Some(3),
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(report.message.contains("got 2"), "{}", report.message);
+
+		assert!(
+			check(JsonAssertionSpec {
+				path: "tags".into(),
+				includes: Some(serde_json::json!("b")),
+				..Default::default()
+			})
+			.passed
+		);
+		let report = check(JsonAssertionSpec {
+			path: "tags".into(),
+			includes: Some(serde_json::json!("c")),
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(
+			report.message.contains(r#"String("a")"#),
+			"{}",
+			report.message
+		);
+	}
+
+	#[test]
+	fn wildcard_paths_check_every_array_element() {
+		let actual = serde_json::json!({
+			"rows": [
+				{ "id": "person:1", "meta": { "a.b": 1 } },
+				{ "id": "person:2" }
+			]
+		});
+		let ctx = JsonAssertionContext::default();
+		let check = |assertion: JsonAssertionSpec| {
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(
+			check(JsonAssertionSpec {
+				path: "rows[*].id".into(),
+				exists: Some(true),
+				..Default::default()
+			})
+			.passed
+		);
+		let report = check(JsonAssertionSpec {
+			path: r#"rows[*].meta["a.b"]"#.into(),
+			exists: Some(true),
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("1 of 2") && report.message.contains("rows[1].meta"),
+			"{}",
+			report.message
+		);
+		assert!(
+			check(JsonAssertionSpec {
+				path: r#"rows[*].meta["a.b"]"#.into(),
+				equals: Some(serde_json::json!(1)),
+				wildcard: WildcardMode::Any,
+				..Default::default()
+			})
+			.passed
+		);
+		assert!(
+			!check(JsonAssertionSpec {
+				path: "rows[*].id".into(),
+				equals: Some(serde_json::json!("person:3")),
+				wildcard: WildcardMode::Any,
+				..Default::default()
+			})
+			.passed
+		);
+		assert_eq!(lookup_path(&actual, "rows[*].id"), None);
+	}
+
+	#[test]
+	fn negate_inverts_equals_contains_and_regex() {
+		let actual = serde_json::json!({ "role": "viewer" });
+		let ctx = JsonAssertionContext::default();
+		let check = |assertion: JsonAssertionSpec| {
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+
+		assert!(
+			check(JsonAssertionSpec {
+				path: "role".into(),
+				equals: Some(serde_json::json!("admin")),
+				negate: true,
+				..Default::default()
+			})
+			.passed
+		);
+		let report = check(JsonAssertionSpec {
+			path: "role".into(),
+			equals: Some(serde_json::json!("viewer")),
+			negate: true,
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("expected not"),
+			"{}",
+			report.message
+		);
+		assert!(
+			!check(JsonAssertionSpec {
+				path: "role".into(),
+				regex: Some("^view".into()),
+				negate: true,
+				..Default::default()
+			})
+			.passed
+		);
+
+		let mut headers = reqwest::header::HeaderMap::new();
+		headers.insert("cache-control", "no-store".parse().expect("header"));
+		let header = |contains: &str| HeaderAssertionSpec {
+			name: "Cache-Control".into(),
+			contains: Some(contains.into()),
+			negate: true,
+			..Default::default()
+		};
+		assert!(
+			assert_header_value(&headers, &header("public"), 0)
+				.expect("header assertion")
+				.passed
+		);
+		let report =
+			assert_header_value(&headers, &header("no-store"), 0).expect("header assertion");
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("unexpected substring"),
+			"{}",
+			report.message
+		);
+	}
+
+	#[test]
+	fn regex_flags_enable_case_insensitive_matching() {
+		let mut headers = reqwest::header::HeaderMap::new();
+		headers.insert("content-type", "Application/JSON".parse().expect("header"));
+		let header = |flags: Option<&str>| HeaderAssertionSpec {
+			name: "content-type".into(),
+			regex: Some("^application/json$".into()),
+			regex_flags: flags.map(str::to_string),
+			..Default::default()
+		};
+
+		assert!(
+			!assert_header_value(&headers, &header(None), 0)
+				.expect("header assertion")
+				.passed
+		);
+		assert!(
+			assert_header_value(&headers, &header(Some("i")), 0)
+				.expect("header assertion")
+				.passed
+		);
+		let err = assert_header_value(&headers, &header(Some("ix")), 0)
+			.expect_err("unknown flag should error");
+		assert!(
+			err.to_string().contains("unsupported regex flag 'x'"),
+			"{err}"
+		);
+
+		let actual = serde_json::json!({ "bio": "first line\nSecond line" });
+		let assertion = JsonAssertionSpec {
+			path: "bio".into(),
+			regex: Some("^second".into()),
+			regex_flags: Some("mi".into()),
+			..Default::default()
+		};
+		let report = assert_json_value_with_context(
+			&actual,
+			&assertion,
+			0,
+			&JsonAssertionContext::default(),
+		)
+		.expect("assertion ok");
+		assert!(report.passed, "{}", report.message);
+	}
+
+	#[test]
+	fn star_segments_collect_values_across_elements() {
+		let actual = serde_json::json!({
+			"items": [
+				{ "price": 1, "currency": "EUR" },
+				{ "price": 2, "currency": "EUR" },
+				{ "currency": "USD" }
+			],
+			"meta": { "count": 3 }
+		});
+		assert_eq!(
+			collect_path(&actual, "items.*.price"),
+			Some(serde_json::json!([1, 2]))
+		);
+		assert_eq!(collect_path(&actual, "meta.*"), None);
+		assert_eq!(
+			collect_path(&actual, "meta.count"),
+			Some(serde_json::json!(3))
+		);
+		assert_eq!(lookup_path(&actual, "items.*.price"), None);
+
+		let ctx = JsonAssertionContext::default();
+		let check = |assertion: JsonAssertionSpec| {
+			assert_json_value_with_context(&actual, &assertion, 0, &ctx).expect("assertion ok")
+		};
+		assert!(
+			check(JsonAssertionSpec {
+				path: "items.*.price".into(),
+				equals: Some(serde_json::json!([1, 2])),
+				..Default::default()
+			})
+			.passed
+		);
+		let report = check(JsonAssertionSpec {
+			path: "items.*.currency".into(),
+			all_match: Some(serde_json::json!("EUR")),
+			..Default::default()
+		});
+		assert!(!report.passed);
+		assert!(report.message.contains("element 2"), "{}", report.message);
+	}
+
+	#[test]
+	fn redact_replaces_matched_values_and_skips_missing_paths() {
+		let mut value = serde_json::json!([[
+			{ "id": "order:a1", "status": "new", "meta": { "at": "2026-01-01" } },
+			{ "id": "order:b2", "status": "paid" }
+		]]);
+		redact_paths(
+			&mut value,
+			&[
+				"[0][*].id".to_string(),
+				"0.*.meta.at".to_string(),
+				"/0/1/status".to_string(),
+				"[0][5].id".to_string(),
+			],
+		)
+		.expect("redact");
+		assert_eq!(
+			value,
+			serde_json::json!([[
+				{ "id": REDACTED, "status": "new", "meta": { "at": REDACTED } },
+				{ "id": REDACTED, "status": REDACTED }
+			]])
+		);
+		assert!(redact_paths(&mut value, &["items[1".to_string()]).is_err());
+	}
+
+	#[test]
+	fn field_comment_reads_comment_from_table_info() {
+		let info = serde_json::json!({
+			"fields": {
+				"email": "DEFINE FIELD email ON person TYPE string DEFAULT 'COMMENT x' COMMENT 'Login address, it\\'s unique' PERMISSIONS FULL",
+				"age": "DEFINE FIELD age ON person TYPE int PERMISSIONS FULL"
+			}
+		});
+		let spec = |name: &str, equals: Option<&str>, contains: Option<&str>| FieldCommentSpec {
+			name: name.into(),
+			equals: equals.map(Into::into),
+			contains: contains.map(Into::into),
+		};
+
+		let report = assert_field_comment(
+			&info,
+			&spec("email", Some("Login address, it's unique"), None),
+		);
+		assert!(report.passed, "{}", report.message);
+		assert!(assert_field_comment(&info, &spec("email", None, Some("unique"))).passed);
+		let report = assert_field_comment(&info, &spec("email", Some("Login"), None));
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("got 'Login address"),
+			"{}",
+			report.message
+		);
+
+		let report = assert_field_comment(&info, &spec("age", None, Some("years")));
+		assert_eq!(report.message, "field 'age' has no COMMENT");
+		let report = assert_field_comment(&info, &spec("missing", None, None));
+		assert!(!report.passed);
+	}
 }
@@ -0,0 +1,173 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+use crate::schema_state::{SCHEMA_DIR, SchemaFile, parse_schema_statements};
+
+use super::loader::{TEST_SUITES_DIR, is_suite_file};
+use super::types::LoadedSuite;
+
+/// Files changed relative to `reference`, as paths relative to the CWD. `None` when git
+/// is unavailable or the ref cannot be resolved.
+pub fn changed_files_since(reference: &str) -> Option<Vec<String>> {
+	let output = Command::new("git")
+		.args(["diff", "--name-only", "--relative", reference])
+		.output()
+		.ok()?;
+	if !output.status.success() {
+		return None;
+	}
+	Some(
+		String::from_utf8_lossy(&output.stdout)
+			.lines()
+			.map(|line| line.trim().to_string())
+			.filter(|line| !line.is_empty())
+			.collect(),
+	)
+}
+
+/// Keeps suites whose own file changed or that mention a table defined in a changed schema
+/// file. Changes to shared test inputs (config, fixtures, seed) select every suite.
+pub fn select_changed_suites(
+	suites: Vec<LoadedSuite>,
+	changed: &[String],
+	read: &dyn Fn(&Path) -> Option<String>,
+) -> Vec<LoadedSuite> {
+	let mut changed_paths = BTreeSet::new();
+	let mut tables = BTreeSet::new();
+	for path in changed {
+		let path = path.replace('\\', "/");
+		if path.starts_with(SCHEMA_DIR) && path.ends_with(".surql") {
+			match schema_tables(&path, read) {
+				Some(found) => tables.extend(found),
+				None => return suites,
+			}
+		} else if affects_all_suites(&path) {
+			return suites;
+		}
+		changed_paths.insert(path);
+	}
+
+	suites
+		.into_iter()
+		.filter(|suite| {
+			let suite_path = suite.path.to_string_lossy().replace('\\', "/");
+			if changed_paths.contains(&suite_path) {
+				return true;
+			}
+			if tables.is_empty() {
+				return false;
+			}
+			match read(&suite.path) {
+				Some(raw) => raw
+					.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+					.any(|word| tables.contains(word)),
+				None => true,
+			}
+		})
+		.collect()
+}
+
+fn affects_all_suites(path: &str) -> bool {
+	let suite_file = path.starts_with(TEST_SUITES_DIR) && is_suite_file(Path::new(path));
+	(path.starts_with("database/tests/") && !suite_file)
+		|| path == "database/seed.surql"
+		|| path == "database/setup.surql"
+}
+
+/// Tables a schema file defines or attaches fields/events/indexes to. `None` when the
+/// file was deleted or does not parse, in which case nothing can be ruled out.
+fn schema_tables(path: &str, read: &dyn Fn(&Path) -> Option<String>) -> Option<BTreeSet<String>> {
+	let file = SchemaFile {
+		path: path.to_string(),
+		sql: read(Path::new(path))?,
+		hash: String::new(),
+	};
+	let entities = parse_schema_statements(&file).ok()?;
+	Some(
+		entities
+			.into_iter()
+			.filter_map(|entity| match entity.kind.as_str() {
+				"table" => Some(entity.name),
+				"field" | "event" | "index" => entity.scope,
+				_ => None,
+			})
+			.collect(),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use std::path::PathBuf;
+
+	use super::*;
+	use crate::tester::types::SuiteSpec;
+
+	fn suite(path: &str) -> LoadedSuite {
+		LoadedSuite {
+			path: PathBuf::from(path),
+			spec: toml::from_str::<SuiteSpec>("").expect("empty suite"),
+		}
+	}
+
+	#[test]
+	fn selects_changed_suites_and_dependents_of_changed_schema() {
+		let files: HashMap<PathBuf, String> = [
+			(
+				"database/schema/billing.surql",
+				"DEFINE TABLE invoice SCHEMAFULL;\nDEFINE FIELD total ON invoice TYPE number;",
+			),
+			(
+				"database/tests/suites/billing.toml",
+				"[[cases]]\nsql = \"SELECT * FROM invoice;\"",
+			),
+			(
+				"database/tests/suites/users.toml",
+				"[[cases]]\nsql = \"SELECT * FROM person;\"",
+			),
+			(
+				"database/tests/suites/invoices_report.toml",
+				"[[cases]]\nsql = \"SELECT * FROM invoice_report;\"",
+			),
+		]
+		.into_iter()
+		.map(|(path, raw)| (PathBuf::from(path), raw.to_string()))
+		.collect();
+		let read = |path: &Path| files.get(path).cloned();
+		let suites = || {
+			vec![
+				suite("database/tests/suites/billing.toml"),
+				suite("database/tests/suites/users.toml"),
+				suite("database/tests/suites/invoices_report.toml"),
+			]
+		};
+		let names = |selected: Vec<LoadedSuite>| {
+			selected
+				.into_iter()
+				.map(|suite| suite.path.to_string_lossy().to_string())
+				.collect::<Vec<_>>()
+		};
+
+		let changed = vec!["database/schema/billing.surql".to_string()];
+		assert_eq!(
+			names(select_changed_suites(suites(), &changed, &read)),
+			vec!["database/tests/suites/billing.toml"]
+		);
+
+		let changed = vec![
+			"database/tests/suites/users.toml".to_string(),
+			"src/main.rs".to_string(),
+		];
+		assert_eq!(
+			names(select_changed_suites(suites(), &changed, &read)),
+			vec!["database/tests/suites/users.toml"]
+		);
+
+		let changed = vec!["database/tests/config.toml".to_string()];
+		assert_eq!(select_changed_suites(suites(), &changed, &read).len(), 3);
+
+		let changed = vec!["database/schema/deleted.surql".to_string()];
+		assert_eq!(select_changed_suites(suites(), &changed, &read).len(), 3);
+	}
+}
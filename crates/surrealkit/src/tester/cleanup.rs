@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use surrealdb::{Surreal, engine::any::Any};
+use time::{Duration, OffsetDateTime};
+
+use crate::core::sql_query;
+
+use super::loader::load_global_config;
+use super::resolve_test_prefix;
+use super::runner::test_db_marker;
+
+/// Removes namespaces left behind by interrupted test runs, matched by the same prefix
+/// the runner uses.
+pub async fn clean_test_databases(
+	db: &Surreal<Any>,
+	older_than_hours: Option<u64>,
+	dry_run: bool,
+) -> Result<()> {
+	let marker = test_db_marker(&resolve_test_prefix(&load_global_config()?)?);
+	let mut resp = sql_query(db, "INFO FOR ROOT;").await?;
+	let info: Option<serde_json::Value> = resp.take(0)?;
+	let namespaces: Vec<String> = info
+		.as_ref()
+		.and_then(|info| info.get("namespaces"))
+		.and_then(|v| v.as_object())
+		.map(|namespaces| namespaces.keys().cloned().collect())
+		.unwrap_or_default();
+
+	let cutoff =
+		older_than_hours.map(|hours| OffsetDateTime::now_utc() - Duration::hours(hours as i64));
+	let stale: Vec<&String> = namespaces
+		.iter()
+		.filter(|name| is_stale_test_namespace(name, &marker, cutoff))
+		.collect();
+
+	for name in &stale {
+		if dry_run {
+			println!("would remove namespace {}", name);
+			continue;
+		}
+		sql_query(
+			db,
+			format!("REMOVE NAMESPACE IF EXISTS `{}`;", name.replace('`', "\\`")),
+		)
+		.await?
+		.check()
+		.with_context(|| format!("removing namespace {name}"))?;
+		println!("removed namespace {}", name);
+	}
+
+	if dry_run {
+		println!("{} test namespace(s) would be removed", stale.len());
+	} else {
+		println!("removed {} test namespace(s)", stale.len());
+	}
+	Ok(())
+}
+
+/// Matches `<ns>_<prefix>_<run_id>_<slug>`, where the run id has the shape the runner
+/// generates (`<nanos>` or `<nanos>x<hex>`). With a cutoff, only namespaces whose run
+/// started before it are considered stale.
+pub(super) fn is_stale_test_namespace(
+	name: &str,
+	marker: &str,
+	cutoff: Option<OffsetDateTime>,
+) -> bool {
+	let Some(nanos) = name
+		.match_indices(marker)
+		.find_map(|(idx, _)| run_id_nanos(&name[idx + marker.len()..]))
+	else {
+		return false;
+	};
+	let Some(cutoff) = cutoff else {
+		return true;
+	};
+	OffsetDateTime::from_unix_timestamp_nanos(nanos).is_ok_and(|started| started < cutoff)
+}
+
+/// The timestamp of a `<digits>[x<hex>]_<slug>` run id, or `None` for any other shape.
+fn run_id_nanos(rest: &str) -> Option<i128> {
+	let (run_id, slug) = rest.split_once('_')?;
+	let (nanos, suffix) = run_id.split_once('x').unwrap_or((run_id, "0"));
+	let valid = !slug.is_empty()
+		&& !nanos.is_empty()
+		&& nanos.bytes().all(|b| b.is_ascii_digit())
+		&& !suffix.is_empty()
+		&& suffix.bytes().all(|b| b.is_ascii_hexdigit());
+	if !valid {
+		return None;
+	}
+	nanos.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tester::runner::DEFAULT_TEST_PREFIX;
+
+	#[test]
+	fn stale_namespaces_are_filtered_by_run_timestamp() {
+		let now = OffsetDateTime::now_utc();
+		let old = (now - Duration::hours(48)).unix_timestamp_nanos();
+		let recent = (now - Duration::minutes(5)).unix_timestamp_nanos();
+		let cutoff = Some(now - Duration::hours(24));
+
+		let old_ns = format!("db_sk_test_{old}x0badf00d_users");
+		let recent_ns = format!("db_sk_test_{recent}_users");
+
+		let marker = test_db_marker(DEFAULT_TEST_PREFIX);
+
+		assert!(is_stale_test_namespace(&old_ns, &marker, cutoff));
+		assert!(!is_stale_test_namespace(&recent_ns, &marker, cutoff));
+		assert!(is_stale_test_namespace(&recent_ns, &marker, None));
+		assert!(!is_stale_test_namespace("production", &marker, None));
+	}
+
+	#[test]
+	fn namespaces_without_a_generated_run_id_are_kept() {
+		let marker = test_db_marker(DEFAULT_TEST_PREFIX);
+		for name in [
+			"db_sk_test_manual_users",
+			"db_sk_test_",
+			"db_sk_test_123",
+			"db_sk_test_123_",
+			"db_sk_test_12a_users",
+			"db_sk_test_123x_users",
+			"db_sk_test_123xzz_users",
+			"db_sk_test_x0badf00d_users",
+		] {
+			assert!(!is_stale_test_namespace(name, &marker, None), "{name}");
+		}
+		assert!(is_stale_test_namespace(
+			"db_sk_test_1700000000000000000x0badf00d_users",
+			&marker,
+			None
+		));
+	}
+}
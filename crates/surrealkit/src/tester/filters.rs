@@ -1,78 +1,402 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::core::glob_match;
+
 use super::types::{FilterInput, LoadedSuite};
 
-pub fn apply_filters(mut suites: Vec<LoadedSuite>, filters: &FilterInput) -> Vec<LoadedSuite> {
-	suites.retain(|suite| match_suite(suite, filters.suite_pattern.as_deref().unwrap_or("*")));
+pub fn apply_filters(suites: Vec<LoadedSuite>, filters: &FilterInput) -> Result<Vec<LoadedSuite>> {
+	let mut trace = Vec::new();
+	let suites = select(suites, filters, &mut trace)?;
+	if filters.explain {
+		for line in &trace {
+			eprintln!("{line}");
+		}
+	}
+	Ok(suites)
+}
+
+/// Applies the filters, recording in `trace` why each suite and case was kept or dropped.
+fn select(
+	suites: Vec<LoadedSuite>,
+	filters: &FilterInput,
+	trace: &mut Vec<String>,
+) -> Result<Vec<LoadedSuite>> {
+	let suite_glob = filters.suite_pattern.as_deref().unwrap_or("*");
+	let case_glob = filters.case_pattern.as_deref().unwrap_or("*");
+	let suite_pattern = NamePattern::parse(suite_glob)?;
+	let case_pattern = NamePattern::parse(case_glob)?;
+
+	let mut selected = Vec::new();
+	for mut suite in suites {
+		let label = suite_label(&suite);
+		if !match_suite(&suite, &suite_pattern) {
+			trace.push(format!(
+				"suite {label}: excluded, does not match --suite '{suite_glob}'"
+			));
+			continue;
+		}
+		trace.push(format!("suite {label}: matched --suite '{suite_glob}'"));
 
-	for suite in &mut suites {
+		let suite_tags = suite.spec.tags.clone();
 		suite.spec.cases.retain(|case| {
-			match_case(
-				case.name.as_str(),
-				filters.case_pattern.as_deref().unwrap_or("*"),
-			)
+			let reason = if !match_case(case.name.as_str(), &case_pattern) {
+				Some(format!("does not match --case '{case_glob}'"))
+			} else if let Some(tag) = filters
+				.tags
+				.iter()
+				.find(|tag| !suite_tags.contains(tag) && !case.tags.contains(tag))
+			{
+				Some(format!("missing tag '{tag}'"))
+			} else {
+				filters
+					.exclude_tags
+					.iter()
+					.find(|tag| suite_tags.contains(tag) || case.tags.contains(tag))
+					.map(|tag| format!("has excluded tag '{tag}'"))
+			};
+			trace.push(match &reason {
+				Some(reason) => format!("  case {}: excluded, {reason}", case.name),
+				None => format!("  case {}: included", case.name),
+			});
+			reason.is_none()
 		});
+		selected.push(suite);
+	}
+
+	apply_only(&mut selected, trace);
+	selected.retain(|suite| {
+		let keep = !suite.spec.cases.is_empty();
+		if !keep {
+			trace.push(format!(
+				"suite {}: excluded, no cases left",
+				suite_label(suite)
+			));
+		}
+		keep
+	});
+	Ok(selected)
+}
+
+/// A `--suite`/`--case` pattern: `re:<regex>`, or a glob with `*`, `?` and `{a,b}`
+/// alternatives.
+enum NamePattern {
+	Regex(Regex),
+	Globs(Vec<String>),
+}
+
+impl NamePattern {
+	fn parse(pattern: &str) -> Result<Self> {
+		match pattern.strip_prefix("re:") {
+			Some(re) => {
+				Ok(Self::Regex(Regex::new(re).with_context(|| {
+					format!("invalid filter regex '{}'", re)
+				})?))
+			}
+			None => Ok(Self::Globs(expand_braces(pattern))),
+		}
+	}
+
+	fn matches(&self, text: &str) -> bool {
+		match self {
+			Self::Regex(re) => re.is_match(text),
+			Self::Globs(globs) => globs.iter().any(|glob| glob_match(glob, text)),
+		}
+	}
+}
+
+/// Expands `{a,b}` groups into one glob per alternative; unclosed braces stay literal.
+fn expand_braces(pattern: &str) -> Vec<String> {
+	let Some(open) = pattern.find('{') else {
+		return vec![pattern.to_string()];
+	};
+	let Some(len) = pattern[open..].find('}') else {
+		return vec![pattern.to_string()];
+	};
+	let (head, group, tail) = (
+		&pattern[..open],
+		&pattern[open + 1..open + len],
+		&pattern[open + len + 1..],
+	);
+	expand_braces(tail)
+		.into_iter()
+		.flat_map(|rest| {
+			group
+				.split(',')
+				.map(move |alt| format!("{head}{alt}{rest}"))
+		})
+		.collect()
+}
 
-		if !filters.tags.is_empty() {
-			let suite_tags = suite.spec.tags.clone();
+/// Narrows the run to suites and cases marked `only`. Skipped cases are kept so they are
+/// reported.
+fn apply_only(suites: &mut Vec<LoadedSuite>, trace: &mut Vec<String>) {
+	if suites.iter().any(|suite| suite.spec.only) {
+		suites.retain(|suite| {
+			if !suite.spec.only {
+				trace.push(format!(
+					"suite {}: excluded, another suite is marked only",
+					suite_label(suite)
+				));
+			}
+			suite.spec.only
+		});
+	}
+	let any_case_only = suites
+		.iter()
+		.any(|suite| suite.spec.cases.iter().any(|case| case.only));
+	if any_case_only {
+		for suite in suites.iter_mut() {
 			suite.spec.cases.retain(|case| {
-				filters.tags.iter().all(|tag| {
-					suite_tags.iter().any(|x| x == tag) || case.tags.iter().any(|x| x == tag)
-				})
+				if !case.only {
+					trace.push(format!(
+						"  case {}: excluded, another case is marked only",
+						case.name
+					));
+				}
+				case.only
 			});
 		}
 	}
-
-	suites.retain(|suite| !suite.spec.cases.is_empty());
-	suites
 }
 
-fn match_suite(suite: &LoadedSuite, pattern: &str) -> bool {
-	let suite_name = suite
+/// The suite's `name`, or its path when it has none.
+fn suite_label(suite: &LoadedSuite) -> String {
+	suite
 		.spec
 		.name
 		.clone()
-		.unwrap_or_else(|| suite.path.to_string_lossy().to_string());
+		.unwrap_or_else(|| suite.path.to_string_lossy().to_string())
+}
+
+fn match_suite(suite: &LoadedSuite, pattern: &NamePattern) -> bool {
+	let suite_name = suite_label(suite);
 	let suite_path = suite.path.to_string_lossy().to_string();
-	glob_match(pattern, &suite_name) || glob_match(pattern, &suite_path)
+	pattern.matches(&suite_name) || pattern.matches(&suite_path)
 }
 
-fn match_case(name: &str, pattern: &str) -> bool {
-	glob_match(pattern, name)
+fn match_case(name: &str, pattern: &NamePattern) -> bool {
+	pattern.matches(name)
 }
 
-pub fn glob_match(pattern: &str, text: &str) -> bool {
-	let p: Vec<char> = pattern.chars().collect();
-	let t: Vec<char> = text.chars().collect();
-	let mut dp = vec![vec![false; t.len() + 1]; p.len() + 1];
-	dp[0][0] = true;
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use super::*;
 
-	for i in 1..=p.len() {
-		if p[i - 1] == '*' {
-			dp[i][0] = dp[i - 1][0];
+	fn suite(path: &str, raw: &str) -> LoadedSuite {
+		LoadedSuite {
+			path: PathBuf::from(path),
+			spec: toml::from_str(raw).expect("suite should parse"),
 		}
 	}
 
-	for i in 1..=p.len() {
-		for j in 1..=t.len() {
-			if p[i - 1] == '*' {
-				dp[i][j] = dp[i - 1][j] || dp[i][j - 1];
-			} else if p[i - 1] == '?' || p[i - 1] == t[j - 1] {
-				dp[i][j] = dp[i - 1][j - 1];
-			}
-		}
+	fn case_names(suites: &[LoadedSuite]) -> Vec<String> {
+		suites
+			.iter()
+			.flat_map(|suite| suite.spec.cases.iter().map(|case| case.name.clone()))
+			.collect()
 	}
 
-	dp[p.len()][t.len()]
-}
+	#[test]
+	fn only_narrows_suites_and_cases_and_skip_is_kept() {
+		let cases = r#"
+[[cases]]
+name = "a"
+kind = "sql_expect"
+sql = "RETURN 1;"
 
-#[cfg(test)]
-mod tests {
-	use super::glob_match;
+[[cases]]
+name = "b"
+kind = "sql_expect"
+sql = "RETURN 1;"
+skip = true
+"#;
+		let focused_cases = r#"
+[[cases]]
+name = "c"
+kind = "sql_expect"
+sql = "RETURN 1;"
+only = true
+
+[[cases]]
+name = "d"
+kind = "sql_expect"
+sql = "RETURN 1;"
+"#;
+		let filters = FilterInput {
+			suite_pattern: None,
+			case_pattern: None,
+			tags: Vec::new(),
+			exclude_tags: Vec::new(),
+			explain: false,
+		};
+
+		let selected =
+			apply_filters(vec![suite("one.toml", cases)], &filters).expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["a", "b"]);
+
+		let selected = apply_filters(
+			vec![suite("one.toml", cases), suite("two.toml", focused_cases)],
+			&filters,
+		)
+		.expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["c"]);
+
+		let focused_suite = format!("only = true\n{cases}");
+		let selected = apply_filters(
+			vec![
+				suite("one.toml", &focused_suite),
+				suite(
+					"two.toml",
+					focused_cases.replace("only = true", "").as_str(),
+				),
+			],
+			&filters,
+		)
+		.expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["a", "b"]);
+	}
+
+	#[test]
+	fn exclude_tags_drop_cases_by_case_or_suite_tag() {
+		let tagged = r#"
+[[cases]]
+name = "fast"
+kind = "sql_expect"
+sql = "RETURN 1;"
+tags = ["auth"]
+
+[[cases]]
+name = "slow"
+kind = "sql_expect"
+sql = "RETURN 1;"
+tags = ["auth", "slow"]
+
+[[cases]]
+name = "flaky"
+kind = "sql_expect"
+sql = "RETURN 1;"
+tags = ["flaky"]
+"#;
+		let flaky_suite = r#"
+tags = ["flaky"]
+
+[[cases]]
+name = "other"
+kind = "sql_expect"
+sql = "RETURN 1;"
+"#;
+		let suites = || vec![suite("one.toml", tagged), suite("two.toml", flaky_suite)];
+		let filters = |tags: &[&str], exclude_tags: &[&str]| FilterInput {
+			suite_pattern: None,
+			case_pattern: None,
+			tags: tags.iter().map(|tag| tag.to_string()).collect(),
+			exclude_tags: exclude_tags.iter().map(|tag| tag.to_string()).collect(),
+			explain: false,
+		};
+
+		let selected =
+			apply_filters(suites(), &filters(&[], &["slow", "flaky"])).expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["fast"]);
+		assert_eq!(selected.len(), 1, "suites left empty are pruned");
+
+		let selected =
+			apply_filters(suites(), &filters(&["auth"], &["slow"])).expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["fast"]);
+
+		let selected = apply_filters(suites(), &filters(&["auth"], &[])).expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["fast", "slow"]);
+	}
+
+	#[test]
+	fn case_patterns_support_regex_and_brace_sets() {
+		let cases = r#"
+[[cases]]
+name = "create_user"
+kind = "sql_expect"
+sql = "RETURN 1;"
+
+[[cases]]
+name = "update_user"
+kind = "sql_expect"
+sql = "RETURN 1;"
+
+[[cases]]
+name = "delete_user"
+kind = "sql_expect"
+sql = "RETURN 1;"
+"#;
+		let select = |pattern: &str| {
+			let filters = FilterInput {
+				suite_pattern: None,
+				case_pattern: Some(pattern.to_string()),
+				tags: Vec::new(),
+				exclude_tags: Vec::new(),
+				explain: false,
+			};
+			apply_filters(vec![suite("users.toml", cases)], &filters).map(|s| case_names(&s))
+		};
+
+		assert_eq!(
+			select("{create,update}_*").expect("brace glob"),
+			vec!["create_user", "update_user"]
+		);
+		assert_eq!(
+			select("re:^(create|delete)_").expect("regex"),
+			vec!["create_user", "delete_user"]
+		);
+		assert_eq!(select("*_user").expect("glob").len(), 3);
+		assert!(select("re:(").is_err());
+		assert_eq!(
+			expand_braces("{a,b}_{x,y}"),
+			vec!["a_x", "b_x", "a_y", "b_y"]
+		);
+	}
 
 	#[test]
-	fn glob_match_handles_wildcards() {
-		assert!(glob_match("*", "abc"));
-		assert!(glob_match("a*", "abc"));
-		assert!(glob_match("a?c", "abc"));
-		assert!(!glob_match("a?d", "abc"));
+	fn explain_trace_names_the_tag_that_dropped_a_case() {
+		let cases = r#"
+name = "auth"
+
+[[cases]]
+name = "login"
+kind = "sql_expect"
+sql = "RETURN 1;"
+tags = ["smoke"]
+
+[[cases]]
+name = "reset_password"
+kind = "sql_expect"
+sql = "RETURN 1;"
+tags = ["smoke", "slow"]
+
+[[cases]]
+name = "audit"
+kind = "sql_expect"
+sql = "RETURN 1;"
+"#;
+		let filters = FilterInput {
+			suite_pattern: None,
+			case_pattern: None,
+			tags: vec!["smoke".to_string()],
+			exclude_tags: vec!["slow".to_string()],
+			explain: true,
+		};
+
+		let mut trace = Vec::new();
+		let selected =
+			select(vec![suite("auth.toml", cases)], &filters, &mut trace).expect("filters apply");
+		assert_eq!(case_names(&selected), vec!["login"]);
+		assert_eq!(
+			trace,
+			vec![
+				"suite auth: matched --suite '*'",
+				"  case login: included",
+				"  case reset_password: excluded, has excluded tag 'slow'",
+				"  case audit: excluded, missing tag 'smoke'",
+			]
+		);
 	}
 }
@@ -1,17 +1,22 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow};
 use walkdir::WalkDir;
 
-use super::types::{GlobalTestConfig, LoadedSpecs, LoadedSuite, SuiteSpec};
+use super::runner::resolve_fixture_path;
+use super::types::{
+	CaseKind, CollectionConfig, GlobalTestConfig, LoadedSpecs, LoadedSuite, SuiteSpec,
+};
 
 pub const TEST_CONFIG_PATH: &str = "database/tests/config.toml";
 pub const TEST_SUITES_DIR: &str = "database/tests/suites";
+pub const COLLECTION_FILE: &str = "_collection.toml";
 
 pub fn load_specs() -> Result<LoadedSpecs> {
 	let global = load_global_config()?;
-	let suites = load_suites()?;
+	let suites = load_suites(Path::new(TEST_SUITES_DIR))?;
 
 	if suites.is_empty() {
 		return Err(anyhow!("No suite files found in {}", TEST_SUITES_DIR));
@@ -20,7 +25,7 @@ pub fn load_specs() -> Result<LoadedSpecs> {
 	Ok(LoadedSpecs { global, suites })
 }
 
-fn load_global_config() -> Result<GlobalTestConfig> {
+pub fn load_global_config() -> Result<GlobalTestConfig> {
 	let path = Path::new(TEST_CONFIG_PATH);
 	if !path.exists() {
 		return Ok(GlobalTestConfig::default());
@@ -32,22 +37,49 @@ fn load_global_config() -> Result<GlobalTestConfig> {
 	Ok(cfg)
 }
 
-fn load_suites() -> Result<Vec<LoadedSuite>> {
-	let mut suites = Vec::new();
-	for entry in WalkDir::new(TEST_SUITES_DIR)
+fn load_suites(root: &Path) -> Result<Vec<LoadedSuite>> {
+	let mut parsed = Vec::new();
+	for entry in WalkDir::new(root)
 		.follow_links(true)
 		.into_iter()
 		.filter_map(|e| e.ok())
 		.filter(|e| e.file_type().is_file())
 	{
-		let path = entry.path();
-		if path.extension().and_then(|x| x.to_str()) != Some("toml") {
+		let path = entry.into_path();
+		if !is_suite_file(&path) {
 			continue;
 		}
+		let raw =
+			fs::read_to_string(&path).with_context(|| format!("reading {}", display(&path)))?;
+		let spec = parse_suite_file(&path, &raw);
+		parsed.push((path, spec));
+	}
+
+	// Body and snapshot files next to the suites are data, not suites.
+	let referenced: HashSet<PathBuf> = parsed
+		.iter()
+		.filter_map(|(path, spec)| Some((path, spec.as_ref().ok()?)))
+		.flat_map(|(path, spec)| referenced_files(spec, path.parent().unwrap_or(root)))
+		.map(|path| file_key(&path))
+		.collect();
 
-		let raw = fs::read_to_string(path).with_context(|| format!("reading {}", display(path)))?;
-		let spec: SuiteSpec =
-			toml::from_str(&raw).with_context(|| format!("parsing {}", display(path)))?;
+	let mut suites = Vec::new();
+	let mut collections: HashMap<PathBuf, Option<CollectionConfig>> = HashMap::new();
+	for (path, spec) in parsed {
+		if referenced.contains(&file_key(&path)) {
+			continue;
+		}
+		let path = path.as_path();
+		let mut spec = spec?;
+		let dir = path.parent().unwrap_or(root).to_path_buf();
+		load_body_files(&mut spec, &dir).with_context(|| format!("loading {}", display(path)))?;
+		if !collections.contains_key(&dir) {
+			let collection = load_collection(&dir)?;
+			collections.insert(dir.clone(), collection);
+		}
+		if let Some(Some(collection)) = collections.get(&dir) {
+			apply_collection(&mut spec, collection);
+		}
 		suites.push(LoadedSuite {
 			path: relative(path),
 			spec,
@@ -58,6 +90,106 @@ fn load_suites() -> Result<Vec<LoadedSuite>> {
 	Ok(suites)
 }
 
+/// Files a suite reads as data: `body_file` payloads and `snapshot` golden files.
+fn referenced_files(spec: &SuiteSpec, dir: &Path) -> Vec<PathBuf> {
+	spec.cases
+		.iter()
+		.filter_map(|case| match &case.kind {
+			CaseKind::ApiRequest(api) => api.body_file.as_deref(),
+			CaseKind::SqlExpect(sql) => sql.snapshot.as_deref(),
+			_ => None,
+		})
+		.map(|file| resolve_fixture_path(dir, file))
+		.collect()
+}
+
+/// Compares paths regardless of `..` segments or symlinks when the file exists.
+fn file_key(path: &Path) -> PathBuf {
+	fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Suite specs are `.toml`, `.yaml`, `.yml` or `.json` files other than the collection file
+/// and `.actual.json` results left by failed snapshot checks. Files a suite references as
+/// `body_file` or `snapshot` are skipped when loading.
+pub fn is_suite_file(path: &Path) -> bool {
+	let ext = path.extension().and_then(|x| x.to_str());
+	let name = path.file_name().and_then(|x| x.to_str());
+	matches!(ext, Some("toml" | "yaml" | "yml" | "json"))
+		&& name != Some(COLLECTION_FILE)
+		&& !name.is_some_and(|name| name.ends_with(".actual.json"))
+}
+
+pub fn parse_suite_file(path: &Path, raw: &str) -> Result<SuiteSpec> {
+	match path.extension().and_then(|x| x.to_str()) {
+		Some("yaml" | "yml") => {
+			serde_yaml_ng::from_str(raw).with_context(|| format!("parsing {}", display(path)))
+		}
+		Some("json") => {
+			let spec: SuiteSpec =
+				serde_json::from_str(raw).with_context(|| format!("parsing {}", display(path)))?;
+			// JSON suites are usually generated; an empty one means the generator found nothing.
+			if spec.cases.is_empty() {
+				return Err(anyhow!("{} has no cases", display(path)));
+			}
+			Ok(spec)
+		}
+		_ => toml::from_str(raw).with_context(|| format!("parsing {}", display(path))),
+	}
+}
+
+fn load_collection(dir: &Path) -> Result<Option<CollectionConfig>> {
+	let path = dir.join(COLLECTION_FILE);
+	if !path.exists() {
+		return Ok(None);
+	}
+
+	let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", display(&path)))?;
+	let cfg: CollectionConfig =
+		toml::from_str(&raw).with_context(|| format!("parsing {}", display(&path)))?;
+	Ok(Some(cfg))
+}
+
+/// Layers collection settings under the suite's own: suite actors and base_url win,
+/// collection fixtures run before the suite's.
+fn apply_collection(spec: &mut SuiteSpec, collection: &CollectionConfig) {
+	for (name, actor) in &collection.actors {
+		spec.actors
+			.entry(name.clone())
+			.or_insert_with(|| actor.clone());
+	}
+	let mut fixtures = collection.fixtures.clone();
+	fixtures.append(&mut spec.fixtures);
+	spec.fixtures = fixtures;
+	if spec.base_url.is_none() {
+		spec.base_url = collection.base_url.clone();
+	}
+}
+
+/// Reads each `api_request` case's `body_file` into its `body`.
+fn load_body_files(spec: &mut SuiteSpec, dir: &Path) -> Result<()> {
+	for case in &mut spec.cases {
+		let CaseKind::ApiRequest(api) = &mut case.kind else {
+			continue;
+		};
+		let Some(file) = &api.body_file else {
+			continue;
+		};
+		if api.body.is_some() {
+			return Err(anyhow!(
+				"case '{}' cannot define both body and body_file",
+				case.name
+			));
+		}
+		let path = resolve_fixture_path(dir, file);
+		let raw = fs::read_to_string(&path)
+			.with_context(|| format!("reading body file {}", display(&path)))?;
+		let body = serde_json::from_str(&raw)
+			.with_context(|| format!("parsing body file {}", display(&path)))?;
+		api.body = Some(body);
+	}
+	Ok(())
+}
+
 fn relative(path: &Path) -> PathBuf {
 	let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 	path.strip_prefix(cwd).unwrap_or(path).to_path_buf()
@@ -76,4 +208,208 @@ mod tests {
 		assert!(TEST_CONFIG_PATH.starts_with("database/tests"));
 		assert!(TEST_SUITES_DIR.starts_with("database/tests"));
 	}
+
+	#[test]
+	fn yaml_suites_parse_like_toml_suites() {
+		let yaml = r#"
+name: calendar
+tags: [security]
+cases:
+  - name: guest_cannot_create
+    kind: sql_expect
+    actor: guest
+    sql: "CREATE calendar CONTENT { name: 'x' };"
+    allow: false
+    error_contains: permission
+"#;
+		let toml = r#"
+name = "calendar"
+tags = ["security"]
+
+[[cases]]
+name = "guest_cannot_create"
+kind = "sql_expect"
+actor = "guest"
+sql = "CREATE calendar CONTENT { name: 'x' };"
+allow = false
+error_contains = "permission"
+"#;
+
+		let from_yaml = parse_suite_file(Path::new("suites/calendar.yaml"), yaml).expect("yaml");
+		let from_toml = parse_suite_file(Path::new("suites/calendar.toml"), toml).expect("toml");
+		assert_eq!(format!("{from_yaml:?}"), format!("{from_toml:?}"));
+
+		let err = parse_suite_file(Path::new("suites/bad.yml"), "name: [unclosed")
+			.expect_err("invalid yaml should error");
+		assert!(format!("{err:#}").contains("suites/bad.yml"));
+		assert!(is_suite_file(Path::new("suites/calendar.yml")));
+		assert!(!is_suite_file(Path::new("suites/_collection.toml")));
+	}
+
+	#[test]
+	fn json_suites_parse_and_reject_empty_cases() {
+		let spec = parse_suite_file(Path::new("suites/person.json"), &SuiteSpec::example_json())
+			.expect("example json should parse");
+		assert_eq!(spec.name.as_deref(), Some("person"));
+		assert_eq!(spec.cases.len(), 1);
+		assert_eq!(spec.cases[0].kind.label(), "sql_expect");
+
+		let err = parse_suite_file(
+			Path::new("suites/empty.json"),
+			r#"{ "name": "empty", "cases": [] }"#,
+		)
+		.expect_err("empty cases should be rejected");
+		assert!(format!("{err:#}").contains("suites/empty.json has no cases"));
+		assert!(is_suite_file(Path::new("suites/person.json")));
+		assert!(!is_suite_file(Path::new("suites/person.actual.json")));
+	}
+
+	#[test]
+	fn live_query_accepts_trigger_sql_and_expected_notifications() {
+		let toml = r#"
+[[cases]]
+name = "create_notifies"
+kind = "live_query"
+live_sql = "LIVE SELECT * FROM person;"
+trigger_sql = "CREATE person:one;"
+expected_notifications = 1
+"#;
+		let spec = parse_suite_file(Path::new("suites/live.toml"), toml).expect("toml");
+		let CaseKind::LiveQuery(live) = &spec.cases[0].kind else {
+			panic!("expected live_query case");
+		};
+		assert!(live.statements.is_empty());
+		assert_eq!(live.trigger_sql.as_deref(), Some("CREATE person:one;"));
+		assert_eq!(live.expect_count, Some(1));
+	}
+
+	#[test]
+	fn bulk_sql_expect_steps_default_to_allow() {
+		let toml = r#"
+[[cases]]
+name = "order_lifecycle"
+kind = "bulk_sql_expect"
+actor = "user_alice"
+fail_fast_on_step = true
+
+[[cases.steps]]
+name = "create"
+sql = "CREATE order:1 SET status = 'new';"
+
+[[cases.steps]]
+name = "cannot_delete"
+sql = "DELETE order:1;"
+allow = false
+error_contains = "permission"
+"#;
+		let spec = parse_suite_file(Path::new("suites/orders.toml"), toml).expect("toml");
+		let CaseKind::BulkSqlExpect(bulk) = &spec.cases[0].kind else {
+			panic!("expected bulk_sql_expect case");
+		};
+		assert_eq!(spec.cases[0].kind.actor(), Some("user_alice"));
+		assert!(bulk.fail_fast_on_step);
+		assert_eq!(
+			bulk.steps.iter().map(|step| step.allow).collect::<Vec<_>>(),
+			[true, false]
+		);
+	}
+
+	#[test]
+	fn collection_config_is_inherited_by_suites_in_its_directory() {
+		let tmp = tempfile::tempdir().expect("create temp dir");
+		let root = tmp.path();
+		let auth = root.join("auth");
+		fs::create_dir_all(&auth).expect("create suite dirs");
+		fs::write(
+			auth.join(COLLECTION_FILE),
+			"base_url = \"http://auth.local\"\n\n[actors.reader]\nkind = \"root\"\n",
+		)
+		.expect("write collection");
+		fs::write(auth.join("login.toml"), "name = \"login\"\n").expect("write auth suite");
+		fs::write(root.join("other.toml"), "name = \"other\"\n").expect("write other suite");
+
+		let suites = load_suites(root).expect("suites should load");
+
+		assert_eq!(suites.len(), 2);
+		let login = suites
+			.iter()
+			.find(|s| s.spec.name.as_deref() == Some("login"))
+			.expect("login suite");
+		assert!(login.spec.actors.contains_key("reader"));
+		assert_eq!(login.spec.base_url.as_deref(), Some("http://auth.local"));
+		let other = suites
+			.iter()
+			.find(|s| s.spec.name.as_deref() == Some("other"))
+			.expect("other suite");
+		assert!(other.spec.actors.is_empty());
+	}
+
+	#[test]
+	fn referenced_json_files_next_to_suites_are_not_loaded_as_suites() {
+		let tmp = tempfile::tempdir().expect("create temp dir");
+		let root = tmp.path();
+		fs::write(root.join("create.json"), r#"{ "sku": "abc" }"#).expect("write body");
+		fs::write(root.join("report.json"), r#"[{ "total": 1 }]"#).expect("write snapshot");
+		fs::write(
+			root.join("orders.toml"),
+			r#"
+[[cases]]
+name = "create"
+kind = "api_request"
+method = "POST"
+path = "/orders"
+expected_status = 201
+body_file = "create.json"
+
+[[cases]]
+name = "report"
+kind = "sql_expect"
+sql = "SELECT count() AS total FROM order GROUP ALL;"
+snapshot = "./report.json"
+"#,
+		)
+		.expect("write suite");
+
+		let suites = load_suites(root).expect("suites should load");
+		assert_eq!(suites.len(), 1);
+		assert!(suites[0].path.ends_with("orders.toml"));
+	}
+
+	#[test]
+	fn api_request_body_file_is_loaded_relative_to_the_suite() {
+		let tmp = tempfile::tempdir().expect("create temp dir");
+		let tests = tmp.path();
+		let root = tests.join("suites");
+		let orders = root.join("orders");
+		fs::create_dir_all(&orders).expect("create suite dirs");
+		fs::create_dir_all(tests.join("payloads")).expect("create payload dir");
+		fs::write(
+			tests.join("payloads/create.json"),
+			r#"{ "sku": "abc", "qty": 2 }"#,
+		)
+		.expect("write body file");
+		let case = |extra: &str| {
+			format!(
+				"[[cases]]\nname = \"create\"\nkind = \"api_request\"\nmethod = \"POST\"\npath = \"/orders\"\nexpected_status = 201\nbody_file = \"../../payloads/create.json\"\n{extra}"
+			)
+		};
+		fs::write(orders.join("create.toml"), case("")).expect("write suite");
+
+		let suites = load_suites(&root).expect("suites should load");
+		let CaseKind::ApiRequest(api) = &suites[0].spec.cases[0].kind else {
+			panic!("expected api_request case");
+		};
+		assert_eq!(
+			api.body,
+			Some(serde_json::json!({ "sku": "abc", "qty": 2 }))
+		);
+
+		fs::write(orders.join("create.toml"), case("body = { sku = \"x\" }\n"))
+			.expect("write suite");
+		let err = load_suites(&root).expect_err("body and body_file conflict");
+		assert!(
+			format!("{err:#}").contains("cannot define both body and body_file"),
+			"{err:#}"
+		);
+	}
 }
@@ -1,45 +1,105 @@
 mod actors;
 mod api;
 mod assertions;
+mod changed;
+mod cleanup;
 mod filters;
 mod loader;
 mod report;
+mod retry;
 mod runner;
+mod snapshot;
 mod types;
 
 use std::env;
+use std::fs;
 
 use anyhow::{Result, bail};
 
 use crate::config::DbCfg;
 
-pub use types::TestOpts;
+pub use cleanup::clean_test_databases;
+pub use types::{OutputFormat, TestOpts};
 
-pub async fn run_test(opts: TestOpts) -> Result<()> {
-	let cfg = DbCfg::from_env(&rust_dotenv::dotenv::DotEnv::new(""))?;
+pub async fn run_test(cfg: DbCfg, opts: TestOpts) -> Result<()> {
 	let loaded = loader::load_specs()?;
 	let filter_input = types::FilterInput {
 		suite_pattern: opts.suite.clone(),
 		case_pattern: opts.case.clone(),
 		tags: opts.tags.clone(),
+		exclude_tags: opts.exclude_tags.clone(),
+		explain: opts.explain,
 	};
-	let suites = filters::apply_filters(loaded.suites, &filter_input);
+	let mut suites = filters::apply_filters(loaded.suites, &filter_input)?;
 	if suites.is_empty() {
 		bail!("No suites matched the selected filters");
 	}
+	if let Some(reference) = &opts.changed_since {
+		match changed::changed_files_since(reference) {
+			Some(files) => {
+				suites = changed::select_changed_suites(suites, &files, &|path| {
+					fs::read_to_string(path).ok()
+				});
+				if suites.is_empty() {
+					println!("No suites affected by changes since {reference}");
+					return Ok(());
+				}
+			}
+			None => eprintln!(
+				"warning: could not list changes since '{reference}' with git; running all suites"
+			),
+		}
+	}
 
 	let base_url = resolve_base_url(&opts, &loaded.global);
 	let timeout_ms = resolve_timeout_ms(&opts, &loaded.global);
-	let ctx = runner::RunnerContext::new(cfg, opts.clone(), loaded.global, base_url, timeout_ms);
+	let test_prefix = resolve_test_prefix(&loaded.global)?;
+	let ctx = runner::RunnerContext::new(
+		cfg,
+		opts.clone(),
+		loaded.global,
+		base_url,
+		timeout_ms,
+		test_prefix,
+	);
+	if opts.list {
+		ctx.write_list(&mut std::io::stdout(), &suites)?;
+		return Ok(());
+	}
 	let report = ctx.run(suites).await?;
 
-	report::print_human_report(&report);
+	match (&opts.tap_out, opts.output) {
+		(Some(path), _) => report::write_tap_file(path, &report)?,
+		(None, OutputFormat::Tap) => report::write_tap_report(&mut std::io::stdout(), &report)?,
+		(None, OutputFormat::Human | OutputFormat::Github) => {}
+	}
+	if opts.output != OutputFormat::Tap {
+		report::print_human_report(&report);
+	}
+	let in_github_actions = env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true");
+	if opts.output == OutputFormat::Github
+		|| (opts.output == OutputFormat::Human && in_github_actions)
+	{
+		report::emit_github_annotations(&report);
+	}
 	if let Some(path) = &opts.json_out {
-		report::write_json_report(path, &report)?;
+		report::write_json_report(path, &report, opts.json_compact)?;
+	}
+	if let Some(path) = &opts.junit_out {
+		report::write_junit_report(path, &report)?;
+	}
+	if let Some(path) = &opts.metrics_out {
+		report::write_metrics_report(path, &report)?;
 	}
 	if report.cases_failed > 0 {
 		bail!("{} test cases failed", report.cases_failed);
 	}
+	if report.suites_failed > 0 {
+		bail!(
+			"{} suite(s) exceeded their max_total_ms budget",
+			report.suites_failed
+		);
+	}
 	Ok(())
 }
 
@@ -63,6 +123,33 @@ fn resolve_timeout_ms(opts: &TestOpts, global: &types::GlobalTestConfig) -> u64
 		.unwrap_or(10_000)
 }
 
+fn resolve_test_prefix(global: &types::GlobalTestConfig) -> Result<String> {
+	let prefix = global
+		.defaults
+		.db_prefix
+		.clone()
+		.or_else(|| env::var("SURREALKIT_TEST_PREFIX").ok())
+		.unwrap_or_else(|| runner::DEFAULT_TEST_PREFIX.to_string());
+	validate_test_prefix(&prefix)?;
+	Ok(prefix)
+}
+
+/// The prefix is embedded in unquoted namespace/database names, so it must be a plain
+/// identifier fragment.
+fn validate_test_prefix(prefix: &str) -> Result<()> {
+	if prefix.is_empty()
+		|| !prefix
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_')
+	{
+		bail!(
+			"invalid test database prefix '{}': use only ASCII letters, digits and underscores",
+			prefix
+		);
+	}
+	Ok(())
+}
+
 fn normalize_base_url(raw: String) -> String {
 	if let Some(rest) = raw.strip_prefix("ws://") {
 		return format!("http://{rest}");
@@ -72,3 +159,23 @@ fn normalize_base_url(raw: String) -> String {
 	}
 	raw
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_prefix_comes_from_config_and_is_validated() {
+		let mut global = types::GlobalTestConfig::default();
+		global.defaults.db_prefix = Some("ci_tmp".to_string());
+		assert_eq!(
+			resolve_test_prefix(&global).expect("valid prefix"),
+			"ci_tmp"
+		);
+
+		global.defaults.db_prefix = Some("ci-tmp".to_string());
+		assert!(resolve_test_prefix(&global).is_err());
+		global.defaults.db_prefix = Some(String::new());
+		assert!(resolve_test_prefix(&global).is_err());
+	}
+}
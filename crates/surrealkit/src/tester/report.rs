@@ -1,9 +1,11 @@
+use std::fmt::Write as _;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
 
 use anyhow::{Context, Result};
 
-use super::types::RunReport;
+use super::types::{RunReport, SuiteReport};
 
 pub fn print_human_report(report: &RunReport) {
 	println!("Test run summary:");
@@ -12,10 +14,13 @@ pub fn print_human_report(report: &RunReport) {
 		report.suites_total, report.suites_failed
 	);
 	println!(
-		"  cases: {} total, {} passed, {} failed",
-		report.cases_total, report.cases_passed, report.cases_failed
+		"  cases: {} total, {} passed, {} failed, {} skipped",
+		report.cases_total, report.cases_passed, report.cases_failed, report.cases_skipped
 	);
 	println!("  duration_ms: {}", report.duration_ms);
+	if report.bailed {
+		println!("  stopped early: --bail limit reached");
+	}
 
 	for suite in &report.suites {
 		println!(
@@ -26,6 +31,10 @@ pub fn print_human_report(report: &RunReport) {
 			suite.cases_passed,
 			suite.cases_failed
 		);
+		if let Some(budget) = &suite.budget {
+			let status = if budget.passed { "ok" } else { "FAIL" };
+			println!("  {} max_total_ms: {}", status, budget.message());
+		}
 		for case in &suite.cases {
 			if case.passed {
 				continue;
@@ -46,20 +55,356 @@ pub fn print_human_report(report: &RunReport) {
 	}
 }
 
-pub fn write_json_report(path: &Path, report: &RunReport) -> Result<()> {
+pub fn write_json_report(path: &Path, report: &RunReport, compact: bool) -> Result<()> {
 	if let Some(parent) = path.parent() {
 		fs::create_dir_all(parent)
 			.with_context(|| format!("creating report directory {}", parent.display()))?;
 	}
-	let raw = serde_json::to_string_pretty(report).context("serializing report json")?;
+	let raw = render_json(report, compact)?;
 	fs::write(path, format!("{raw}\n"))
 		.with_context(|| format!("writing report file {}", path.display()))?;
 	Ok(())
 }
 
+fn render_json(report: &RunReport, compact: bool) -> Result<String> {
+	if compact {
+		serde_json::to_string(report).context("serializing report json")
+	} else {
+		serde_json::to_string_pretty(report).context("serializing report json")
+	}
+}
+
+/// Prints GitHub Actions workflow commands so failures show up as PR annotations.
+pub fn emit_github_annotations(report: &RunReport) {
+	for line in github_annotations(report) {
+		println!("{line}");
+	}
+}
+
+fn github_annotations(report: &RunReport) -> Vec<String> {
+	let mut lines = Vec::new();
+	for suite in &report.suites {
+		let file = escape_property(&suite.suite_file);
+		for case in suite.cases.iter().filter(|case| !case.passed) {
+			lines.push(format!(
+				"::error file={},title={}::{}",
+				file,
+				escape_property(&format!("FAIL {}", case.name)),
+				escape_data(case.message.as_deref().unwrap_or("unknown failure"))
+			));
+			for assertion in case.assertions.iter().filter(|a| !a.passed) {
+				lines.push(format!(
+					"::warning file={},title={}::{}",
+					file,
+					escape_property(&format!("{} / {}", case.name, assertion.name)),
+					escape_data(&assertion.message)
+				));
+			}
+		}
+		if let Some(budget) = suite.budget.as_ref().filter(|budget| !budget.passed) {
+			lines.push(format!(
+				"::error file={},title={}::{}",
+				file,
+				escape_property(&format!("{} max_total_ms", suite.suite_name)),
+				escape_data(&budget.message())
+			));
+		}
+	}
+	lines
+}
+
+fn escape_data(raw: &str) -> String {
+	raw.replace('%', "%25")
+		.replace('\r', "%0D")
+		.replace('\n', "%0A")
+}
+
+fn escape_property(raw: &str) -> String {
+	escape_data(raw).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Emits TAP version 14, one test point per case, with a YAML block for failures.
+/// Suite budgets are not test points and are written as comments.
+pub fn write_tap_report(out: &mut dyn Write, report: &RunReport) -> Result<()> {
+	writeln!(out, "TAP version 14")?;
+	writeln!(out, "1..{}", report.cases_total)?;
+	for suite in &report.suites {
+		if let Some(budget) = &suite.budget {
+			let status = if budget.passed { "ok" } else { "exceeded" };
+			writeln!(
+				out,
+				"# {} max_total_ms {}: {}",
+				suite.suite_name,
+				status,
+				budget.message()
+			)?;
+		}
+	}
+	let cases = report
+		.suites
+		.iter()
+		.flat_map(|suite| suite.cases.iter().map(move |case| (suite, case)));
+	for (idx, (suite, case)) in cases.enumerate() {
+		let status = if case.passed { "ok" } else { "not ok" };
+		let directive = if case.is_skipped() { " # SKIP" } else { "" };
+		writeln!(
+			out,
+			"{} {} - {} / {}{}",
+			status,
+			idx + 1,
+			suite.suite_name,
+			case.name,
+			directive
+		)?;
+		if case.passed {
+			continue;
+		}
+		writeln!(out, "  ---")?;
+		writeln!(
+			out,
+			"  message: {}",
+			yaml_str(case.message.as_deref().unwrap_or("unknown failure"))
+		)?;
+		writeln!(out, "  suite_file: {}", yaml_str(&suite.suite_file))?;
+		writeln!(out, "  assertions:")?;
+		for assertion in case.assertions.iter().filter(|a| !a.passed) {
+			writeln!(out, "    - name: {}", yaml_str(&assertion.name))?;
+			writeln!(out, "      message: {}", yaml_str(&assertion.message))?;
+		}
+		writeln!(out, "  ...")?;
+	}
+	Ok(())
+}
+
+pub fn write_tap_file(path: &Path, report: &RunReport) -> Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)
+			.with_context(|| format!("creating report directory {}", parent.display()))?;
+	}
+	let mut raw = Vec::new();
+	write_tap_report(&mut raw, report)?;
+	fs::write(path, raw).with_context(|| format!("writing report file {}", path.display()))?;
+	Ok(())
+}
+
+/// JSON string literals are valid YAML scalars and escape everything we need.
+fn yaml_str(raw: &str) -> String {
+	serde_json::Value::from(raw).to_string()
+}
+
+/// Writes a JUnit XML report, via a temp file and rename so CI never reads a partial file.
+pub fn write_junit_report(path: &Path, report: &RunReport) -> Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)
+			.with_context(|| format!("creating report directory {}", parent.display()))?;
+	}
+	let tmp = path.with_extension("xml.tmp");
+	fs::write(&tmp, render_junit(report))
+		.with_context(|| format!("writing report file {}", tmp.display()))?;
+	fs::rename(&tmp, path).with_context(|| format!("writing report file {}", path.display()))?;
+	Ok(())
+}
+
+fn render_junit(report: &RunReport) -> String {
+	let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	let _ = writeln!(
+		out,
+		"<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">",
+		report.cases_total,
+		report.cases_failed,
+		report.suites.iter().map(budget_errors).sum::<usize>(),
+		report.cases_skipped,
+		seconds(report.duration_ms)
+	);
+	for suite in &report.suites {
+		let _ = writeln!(
+			out,
+			"  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">",
+			xml_escape(&suite.suite_name),
+			suite.cases_total,
+			suite.cases_failed,
+			budget_errors(suite),
+			suite.cases_skipped,
+			seconds(suite.duration_ms)
+		);
+		if let Some(budget) = &suite.budget {
+			let _ = writeln!(
+				out,
+				"    <properties>\n      <property name=\"max_total_ms\" value=\"{}\"/>\n      <property name=\"api_total_ms\" value=\"{}\"/>\n    </properties>",
+				budget.max_total_ms, budget.total_ms
+			);
+			if !budget.passed {
+				let _ = writeln!(
+					out,
+					"    <system-err>{}</system-err>",
+					xml_escape(&budget.message())
+				);
+			}
+		}
+		for case in &suite.cases {
+			let _ = write!(
+				out,
+				"    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+				xml_escape(&case.name),
+				xml_escape(&suite.suite_name),
+				seconds(case.duration_ms)
+			);
+			if case.is_skipped() {
+				out.push_str(">\n      <skipped/>\n    </testcase>\n");
+				continue;
+			}
+			if case.passed {
+				out.push_str("/>\n");
+				continue;
+			}
+			let details = case
+				.assertions
+				.iter()
+				.filter(|assertion| !assertion.passed)
+				.map(|assertion| format!("{}: {}", assertion.name, assertion.message))
+				.collect::<Vec<_>>()
+				.join("\n");
+			let _ = writeln!(
+				out,
+				">\n      <failure message=\"{}\" type=\"{}\">{}</failure>\n    </testcase>",
+				xml_escape(case.message.as_deref().unwrap_or("unknown failure")),
+				xml_escape(&case.kind),
+				xml_escape(&details)
+			);
+		}
+		out.push_str("  </testsuite>\n");
+	}
+	out.push_str("</testsuites>\n");
+	out
+}
+
+/// An exceeded `max_total_ms` counts as a suite-level JUnit error.
+fn budget_errors(suite: &SuiteReport) -> usize {
+	usize::from(suite.budget.as_ref().is_some_and(|budget| !budget.passed))
+}
+
+/// Writes Prometheus textfile metrics, via a temp file and rename as the textfile
+/// collector requires.
+pub fn write_metrics_report(path: &Path, report: &RunReport) -> Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)
+			.with_context(|| format!("creating metrics directory {}", parent.display()))?;
+	}
+	let tmp = path.with_extension("prom.tmp");
+	fs::write(&tmp, render_metrics(report))
+		.with_context(|| format!("writing metrics file {}", tmp.display()))?;
+	fs::rename(&tmp, path).with_context(|| format!("writing metrics file {}", path.display()))?;
+	Ok(())
+}
+
+/// Metric name, help text, run-wide value and per-suite accessor.
+type CaseCountMetric = (&'static str, &'static str, usize, fn(&SuiteReport) -> usize);
+
+fn render_metrics(report: &RunReport) -> String {
+	let mut out = String::new();
+	let counts: [CaseCountMetric; 4] = [
+		("cases_total", "Test cases run", report.cases_total, |s| {
+			s.cases_total
+		}),
+		(
+			"cases_passed",
+			"Test cases that passed",
+			report.cases_passed,
+			|s| s.cases_passed,
+		),
+		(
+			"cases_failed",
+			"Test cases that failed",
+			report.cases_failed,
+			|s| s.cases_failed,
+		),
+		(
+			"cases_skipped",
+			"Test cases that were skipped",
+			report.cases_skipped,
+			|s| s.cases_skipped,
+		),
+	];
+	for (name, help, total, per_suite) in counts {
+		let _ = writeln!(out, "# HELP surrealkit_{name} {help}.");
+		let _ = writeln!(out, "# TYPE surrealkit_{name} gauge");
+		let _ = writeln!(out, "surrealkit_{name} {total}");
+		for suite in &report.suites {
+			let _ = writeln!(
+				out,
+				"surrealkit_{name}{{{}}} {}",
+				suite_labels(suite),
+				per_suite(suite)
+			);
+		}
+	}
+	out.push_str("# HELP surrealkit_run_duration_ms Wall time of the test run in milliseconds.\n");
+	out.push_str("# TYPE surrealkit_run_duration_ms gauge\n");
+	let _ = writeln!(out, "surrealkit_run_duration_ms {}", report.duration_ms);
+	out.push_str(
+		"# HELP surrealkit_suite_api_total_ms Summed API response times of suites with a max_total_ms budget.\n",
+	);
+	out.push_str("# TYPE surrealkit_suite_api_total_ms gauge\n");
+	for suite in &report.suites {
+		if let Some(budget) = &suite.budget {
+			let _ = writeln!(
+				out,
+				"surrealkit_suite_api_total_ms{{{}}} {}",
+				suite_labels(suite),
+				budget.total_ms
+			);
+		}
+	}
+	out.push_str("# HELP surrealkit_suite_duration_ms Wall time of each suite in milliseconds.\n");
+	out.push_str("# TYPE surrealkit_suite_duration_ms gauge\n");
+	for suite in &report.suites {
+		let _ = writeln!(
+			out,
+			"surrealkit_suite_duration_ms{{{}}} {}",
+			suite_labels(suite),
+			suite.duration_ms
+		);
+	}
+	out
+}
+
+fn suite_labels(suite: &SuiteReport) -> String {
+	format!(
+		"suite=\"{}\",file=\"{}\"",
+		label_escape(&suite.suite_name),
+		label_escape(&suite.suite_file)
+	)
+}
+
+fn label_escape(raw: &str) -> String {
+	raw.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+fn seconds(ms: u128) -> String {
+	format!("{:.3}", ms as f64 / 1000.0)
+}
+
+fn xml_escape(raw: &str) -> String {
+	let mut out = String::with_capacity(raw.len());
+	for ch in raw.chars() {
+		match ch {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' => out.push_str("&quot;"),
+			'\'' => out.push_str("&apos;"),
+			_ => out.push(ch),
+		}
+	}
+	out
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::tester::types::RunReport;
+	use super::{github_annotations, render_json, render_junit, render_metrics, write_tap_report};
+	use crate::tester::types::{AssertionReport, BudgetReport, CaseReport, RunReport, SuiteReport};
 
 	#[test]
 	fn json_report_is_serializable() {
@@ -72,9 +417,164 @@ mod tests {
 			cases_total: 1,
 			cases_passed: 1,
 			cases_failed: 0,
+			cases_skipped: 0,
+			bailed: false,
 			suites: Vec::new(),
 		};
 		let encoded = serde_json::to_string(&report).expect("serialization should work");
 		assert!(encoded.contains("\"cases_total\":1"));
+
+		let compact = render_json(&report, true).expect("compact json");
+		let pretty = render_json(&report, false).expect("pretty json");
+		assert!(!compact.contains('\n'));
+		assert!(pretty.contains("\n  \"cases_total\": 1"));
+	}
+
+	#[test]
+	fn tap_report_marks_failed_cases_with_diagnostics() {
+		let mut out = Vec::new();
+		write_tap_report(&mut out, &sample_report()).expect("tap output");
+		let tap = String::from_utf8(out).expect("utf8");
+
+		assert!(tap.starts_with("TAP version 14\n1..2\n"));
+		assert!(tap.contains("ok 1 - smoke / ok\n"));
+		assert!(tap.contains("not ok 2 - smoke / broken\n  ---\n"));
+		assert!(tap.contains("  suite_file: \"database/tests/suites/smoke.toml\"\n"));
+		assert!(tap.contains("    - name: \"json_path\"\n"));
+		assert!(tap.trim_end().ends_with("  ..."));
+	}
+
+	#[test]
+	fn github_annotations_cover_failed_cases_and_assertions() {
+		let lines = github_annotations(&sample_report());
+		assert_eq!(
+			lines,
+			vec![
+				"::error file=database/tests/suites/smoke.toml,title=FAIL broken::one or more assertions failed",
+				"::warning file=database/tests/suites/smoke.toml,title=broken / json_path::expected <1> & got \"2\"",
+			]
+		);
+	}
+
+	#[test]
+	fn junit_report_maps_suites_cases_and_failures() {
+		let xml = render_junit(&sample_report());
+		assert!(xml.contains(
+			"<testsuites tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\"1.500\">"
+		));
+		assert!(xml.contains(
+			"<testsuite name=\"smoke\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\"0.500\">"
+		));
+		assert!(xml.contains("<testcase name=\"ok\" classname=\"smoke\" time=\"0.250\"/>"));
+		assert!(xml.contains(
+			"<failure message=\"one or more assertions failed\" type=\"sql_expect\">json_path: expected &lt;1&gt; &amp; got &quot;2&quot;</failure>"
+		));
+	}
+
+	#[test]
+	fn exceeded_budget_is_reported_at_suite_level() {
+		let mut report = sample_report();
+		report.suites[0].budget = Some(BudgetReport {
+			total_ms: 160,
+			max_total_ms: 100,
+			passed: false,
+		});
+		assert!(report.suites[0].failed());
+
+		let xml = render_junit(&report);
+		assert!(xml.contains("failures=\"1\" errors=\"1\""), "{xml}");
+		assert!(xml.contains("<property name=\"max_total_ms\" value=\"100\"/>"));
+		assert!(
+			xml.contains(
+				"<system-err>API responses took 160ms in total, budget 100ms</system-err>"
+			)
+		);
+		assert_eq!(xml.matches("<testcase ").count(), 2);
+
+		let lines = github_annotations(&report);
+		assert_eq!(
+			lines.last().map(String::as_str),
+			Some(
+				"::error file=database/tests/suites/smoke.toml,title=smoke max_total_ms::API responses took 160ms in total, budget 100ms"
+			)
+		);
+
+		let mut out = Vec::new();
+		write_tap_report(&mut out, &report).expect("tap output");
+		let tap = String::from_utf8(out).expect("utf8");
+		assert!(tap.starts_with(
+			"TAP version 14\n1..2\n# smoke max_total_ms exceeded: API responses took 160ms in total, budget 100ms\n"
+		));
+
+		let metrics = render_metrics(&report);
+		assert!(metrics.contains(
+			"surrealkit_suite_api_total_ms{suite=\"smoke\",file=\"database/tests/suites/smoke.toml\"} 160"
+		));
+	}
+
+	#[test]
+	fn metrics_report_has_run_and_per_suite_series() {
+		let metrics = render_metrics(&sample_report());
+		let labels = r#"{suite="smoke",file="database/tests/suites/smoke.toml"}"#;
+		for line in [
+			"# TYPE surrealkit_cases_total gauge".to_string(),
+			"surrealkit_cases_total 2".to_string(),
+			format!("surrealkit_cases_total{labels} 2"),
+			"surrealkit_cases_failed 1".to_string(),
+			format!("surrealkit_cases_failed{labels} 1"),
+			"surrealkit_cases_skipped 0".to_string(),
+			"surrealkit_run_duration_ms 1500".to_string(),
+			format!("surrealkit_suite_duration_ms{labels} 500"),
+		] {
+			assert!(
+				metrics.lines().any(|l| l == line),
+				"missing {line}:\n{metrics}"
+			);
+		}
+	}
+
+	fn sample_report() -> RunReport {
+		let case = |name: &str, passed: bool| CaseReport {
+			name: name.into(),
+			kind: "sql_expect".into(),
+			duration_ms: 250,
+			passed,
+			message: (!passed).then(|| "one or more assertions failed".into()),
+			assertions: vec![AssertionReport {
+				name: "json_path".into(),
+				passed,
+				message: "expected <1> & got \"2\"".into(),
+			}],
+			attempt: 1,
+			retry_count: 0,
+			latency_ms: None,
+		};
+		RunReport {
+			started_at: "2020-01-01T00:00:00Z".into(),
+			finished_at: "2020-01-01T00:00:01Z".into(),
+			duration_ms: 1500,
+			suites_total: 1,
+			suites_failed: 1,
+			cases_total: 2,
+			cases_passed: 1,
+			cases_failed: 1,
+			cases_skipped: 0,
+			bailed: false,
+			suites: vec![SuiteReport {
+				suite_file: "database/tests/suites/smoke.toml".into(),
+				suite_name: "smoke".into(),
+				namespace: "ns".into(),
+				database: "db".into(),
+				duration_ms: 500,
+				setup_duration_ms: 0,
+				teardown_duration_ms: 0,
+				cases_total: 2,
+				cases_passed: 1,
+				cases_failed: 1,
+				cases_skipped: 0,
+				cases: vec![case("ok", true), case("broken", false)],
+				budget: None,
+			}],
+		}
 	}
 }
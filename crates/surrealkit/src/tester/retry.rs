@@ -0,0 +1,246 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::types::RetryOn;
+
+pub const DEFAULT_TRANSIENT_ERRORS: &[&str] = &["resource busy", "transaction conflict"];
+
+const MAX_ATTEMPTS: u32 = 3;
+const BASE_BACKOFF_MS: u64 = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct TransientRetry {
+	patterns: Vec<String>,
+}
+
+impl TransientRetry {
+	pub fn new(enabled: bool, configured: &[String]) -> Self {
+		if !enabled {
+			return Self::default();
+		}
+		let patterns = if configured.is_empty() {
+			DEFAULT_TRANSIENT_ERRORS
+				.iter()
+				.map(|p| p.to_string())
+				.collect()
+		} else {
+			configured.to_vec()
+		};
+		Self {
+			patterns: patterns
+				.into_iter()
+				.map(|p| p.to_ascii_lowercase())
+				.collect(),
+		}
+	}
+
+	fn is_transient(&self, err: &anyhow::Error) -> bool {
+		let text = format!("{err:#}").to_ascii_lowercase();
+		self.patterns.iter().any(|p| text.contains(p.as_str()))
+	}
+
+	pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T>>,
+	{
+		let mut attempt = 1;
+		loop {
+			match op().await {
+				Ok(value) => return Ok(value),
+				Err(err) if attempt < MAX_ATTEMPTS && self.is_transient(&err) => {
+					let backoff = BASE_BACKOFF_MS << (attempt - 1);
+					tokio::time::sleep(Duration::from_millis(backoff)).await;
+					attempt += 1;
+				}
+				Err(err) => return Err(err),
+			}
+		}
+	}
+}
+
+/// Re-runs a whole case up to `retries` times, with a backoff doubling from `delay_ms`.
+/// Errors are always retried; failed results only with [`RetryOn::Failure`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseRetry {
+	pub retries: u32,
+	pub delay_ms: u64,
+	pub retry_on: RetryOn,
+}
+
+impl CaseRetry {
+	/// Returns the last result together with the number of attempts made. `failed`
+	/// tells whether a successful result still counts as a failure.
+	pub async fn run<T, F, Fut>(&self, failed: impl Fn(&T) -> bool, mut op: F) -> (Result<T>, u32)
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T>>,
+	{
+		let mut attempt = 1;
+		loop {
+			let result = op().await;
+			let retry = match &result {
+				Err(_) => true,
+				Ok(value) => self.retry_on == RetryOn::Failure && failed(value),
+			};
+			if !retry || attempt > self.retries {
+				return (result, attempt);
+			}
+			let delay_ms = self.delay_ms.saturating_mul(1u64 << (attempt - 1).min(16));
+			tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+			attempt += 1;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+
+	use anyhow::anyhow;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn retries_transient_error_until_success() {
+		let retry = TransientRetry::new(true, &[]);
+		let calls = Cell::new(0);
+		let result = retry
+			.run(|| {
+				calls.set(calls.get() + 1);
+				let n = calls.get();
+				async move {
+					if n == 1 {
+						Err(anyhow!("Resource busy, try again"))
+					} else {
+						Ok(n)
+					}
+				}
+			})
+			.await
+			.expect("query should eventually succeed");
+		assert_eq!(result, 2);
+		assert_eq!(calls.get(), 2);
+	}
+
+	#[tokio::test]
+	async fn does_not_retry_when_disabled_or_not_transient() {
+		let disabled = TransientRetry::new(false, &[]);
+		let calls = Cell::new(0);
+		let result: Result<()> = disabled
+			.run(|| {
+				calls.set(calls.get() + 1);
+				async { Err(anyhow!("resource busy")) }
+			})
+			.await;
+		assert!(result.is_err());
+		assert_eq!(calls.get(), 1);
+
+		let enabled = TransientRetry::new(true, &["lock held".to_string()]);
+		calls.set(0);
+		let result: Result<()> = enabled
+			.run(|| {
+				calls.set(calls.get() + 1);
+				async { Err(anyhow!("resource busy")) }
+			})
+			.await;
+		assert!(result.is_err());
+		assert_eq!(calls.get(), 1);
+	}
+
+	#[tokio::test]
+	async fn case_retry_stops_at_first_success_or_after_all_attempts() {
+		let retry = CaseRetry {
+			retries: 2,
+			delay_ms: 1,
+			retry_on: RetryOn::Error,
+		};
+		let calls = Cell::new(0);
+		let (result, attempts) = retry
+			.run(
+				|_| false,
+				|| {
+					calls.set(calls.get() + 1);
+					let n = calls.get();
+					async move {
+						if n < 2 {
+							Err(anyhow!("connection reset"))
+						} else {
+							Ok(n)
+						}
+					}
+				},
+			)
+			.await;
+		assert_eq!(result.expect("second attempt succeeds"), 2);
+		assert_eq!(attempts, 2);
+
+		calls.set(0);
+		let (result, attempts): (Result<()>, u32) = retry
+			.run(
+				|_| false,
+				|| {
+					calls.set(calls.get() + 1);
+					async { Err(anyhow!("connection reset")) }
+				},
+			)
+			.await;
+		assert!(result.is_err());
+		assert_eq!(attempts, 3);
+		assert_eq!(calls.get(), 3);
+
+		calls.set(0);
+		let (result, attempts) = retry
+			.run(
+				|passed: &bool| !passed,
+				|| {
+					calls.set(calls.get() + 1);
+					async { Ok(false) }
+				},
+			)
+			.await;
+		assert!(!result.expect("failed result is not an error"));
+		assert_eq!(
+			attempts, 1,
+			"`retry_on = \"error\"` does not re-run failed results"
+		);
+	}
+
+	#[tokio::test]
+	async fn retry_on_failure_reruns_failed_results() {
+		let retry = CaseRetry {
+			retries: 2,
+			delay_ms: 1,
+			retry_on: RetryOn::Failure,
+		};
+		let calls = Cell::new(0);
+		let (result, attempts) = retry
+			.run(
+				|passed: &bool| !passed,
+				|| {
+					calls.set(calls.get() + 1);
+					let passed = calls.get() == 3;
+					async move { Ok(passed) }
+				},
+			)
+			.await;
+		assert!(result.expect("third attempt passes"));
+		assert_eq!(attempts, 3);
+
+		calls.set(0);
+		let (result, attempts) = retry
+			.run(
+				|passed: &bool| !passed,
+				|| {
+					calls.set(calls.get() + 1);
+					async { Ok(false) }
+				},
+			)
+			.await;
+		assert!(!result.expect("last result is kept"));
+		assert_eq!(attempts, 3);
+		assert_eq!(calls.get(), 3);
+	}
+}
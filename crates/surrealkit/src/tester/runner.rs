@@ -1,39 +1,68 @@
+use std::collections::hash_map::RandomState;
 use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::hash::BuildHasher;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow, bail};
+use futures::{Stream, StreamExt};
 use serde_json::Value;
-use surrealdb::{Surreal, engine::any::Any};
+use surrealdb::{Notification, Surreal, engine::any::Any};
 use surrealdb_types::SurrealValue;
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use tokio::sync::Semaphore;
 
 use crate::config::DbCfg;
-use crate::core::create_surreal_client;
+use crate::core::{
+	create_surreal_client, exec_surql_with_headers, query_json, sql_query, wrap_in_transaction,
+};
+use crate::rollout::DEFAULT_LOCK_TIMEOUT_SECS;
 use crate::seed;
 use crate::setup::run_setup;
 use crate::sync::{self, SyncOpts};
 
 use super::actors::{
-	ActorSession, actor_name_or_default, build_actor_sessions, merged_actor_specs, require_actor,
+	ActorSession, SessionCache, actor_name_or_default, build_actor_sessions, merged_actor_specs,
+	require_actor,
+};
+use super::api::{execute_api_case, execute_graphql_case};
+use super::assertions::{
+	JsonAssertionContext, assert_created_in_table, assert_error_at_index, assert_field_comment,
+	assert_header_value, assert_json_value_with_context, redact_paths,
 };
-use super::api::execute_api_case;
-use super::assertions::{JsonAssertionContext, assert_json_value_with_context};
+use super::retry::{CaseRetry, TransientRetry};
+use super::snapshot::{SnapshotContext, check_snapshot};
 use super::types::{
-	AssertionReport, CaseKind, CaseReport, FilterInput, GlobalTestConfig, JsonAssertionSpec,
-	LoadedSuite, PermissionAction, RunReport, SuiteReport, TestOpts,
+	ALL_ACTORS, ApiClientConfig, AssertionReport, BudgetReport, CaseKind, CaseReport, CaseSpec,
+	FilterInput, GlobalTestConfig, JsonAssertionSpec, LoadedSuite, PermissionAction,
+	PermissionsMatrixCase, RetryOn, RunReport, SuiteReport, TestOpts,
 };
 
+/// Marks namespaces and databases the runner creates, so `clean --test-dbs` can find them.
+pub(super) const DEFAULT_TEST_PREFIX: &str = "sk_test";
+
+pub(super) fn test_db_marker(prefix: &str) -> String {
+	format!("_{prefix}_")
+}
+
 pub struct RunnerContext {
 	pub cfg: DbCfg,
 	pub opts: TestOpts,
 	pub global: GlobalTestConfig,
 	pub base_url: Option<String>,
 	pub timeout_ms: u64,
+	retry: TransientRetry,
 	run_id: String,
+	test_prefix: String,
+	/// Failed cases so far, shared with parallel suite tasks for `--bail`.
+	failed_cases: Arc<AtomicUsize>,
+	/// Set with `--reuse-connections`. Parallel suite tasks each get their own, since a
+	/// shared client cannot be switched between namespaces concurrently.
+	sessions: Option<SessionCache>,
 }
 
 impl RunnerContext {
@@ -43,14 +72,21 @@ impl RunnerContext {
 		global: GlobalTestConfig,
 		base_url: Option<String>,
 		timeout_ms: u64,
+		test_prefix: String,
 	) -> Self {
+		let retry = TransientRetry::new(opts.retry_transient, &global.defaults.transient_errors);
+		let sessions = opts.reuse_connections.then(SessionCache::default);
 		Self {
 			cfg,
 			opts,
 			global,
 			base_url,
 			timeout_ms,
+			retry,
 			run_id: unique_run_id(),
+			test_prefix,
+			failed_cases: Arc::new(AtomicUsize::new(0)),
+			sessions,
 		}
 	}
 
@@ -65,11 +101,19 @@ impl RunnerContext {
 		};
 
 		let suites_total = suite_reports.len();
-		let suites_failed = suite_reports.iter().filter(|s| s.cases_failed > 0).count();
+		let suites_failed = suite_reports.iter().filter(|s| s.failed()).count();
 		let cases_total: usize = suite_reports.iter().map(|s| s.cases_total).sum();
 		let cases_passed: usize = suite_reports.iter().map(|s| s.cases_passed).sum();
 		let cases_failed: usize = suite_reports.iter().map(|s| s.cases_failed).sum();
+		let cases_skipped: usize = suite_reports.iter().map(|s| s.cases_skipped).sum();
 		let finished_at = OffsetDateTime::now_utc();
+		let bailed = self.bailed();
+		if bailed {
+			eprintln!(
+				"Stopped after {} failed case(s) (--bail)",
+				self.failed_cases.load(Ordering::SeqCst)
+			);
+		}
 
 		Ok(RunReport {
 			started_at: started_at.format(&Rfc3339)?,
@@ -80,17 +124,31 @@ impl RunnerContext {
 			cases_total,
 			cases_passed,
 			cases_failed,
+			cases_skipped,
+			bailed,
 			suites: suite_reports,
 		})
 	}
 
+	/// Counts a failed case; true once `--bail` is reached.
+	fn record_failure(&self) -> bool {
+		self.failed_cases.fetch_add(1, Ordering::SeqCst);
+		self.bailed()
+	}
+
+	fn bailed(&self) -> bool {
+		self.opts
+			.bail
+			.is_some_and(|limit| self.failed_cases.load(Ordering::SeqCst) >= limit.max(1))
+	}
+
 	async fn run_sequential(&self, suites: Vec<LoadedSuite>) -> Result<Vec<SuiteReport>> {
 		let mut reports = Vec::new();
 		for suite in suites {
 			let report = self.run_suite(suite).await?;
-			let failed = report.cases_failed > 0;
+			let failed = report.failed();
 			reports.push(report);
-			if self.opts.fail_fast && failed {
+			if (self.opts.fail_fast && failed) || self.bailed() {
 				break;
 			}
 		}
@@ -115,9 +173,9 @@ impl RunnerContext {
 		while let Some(joined) = joinset.join_next().await {
 			match joined {
 				Ok(Ok(report)) => {
-					let failed = report.cases_failed > 0;
+					let failed = report.failed();
 					reports.push(report);
-					if self.opts.fail_fast && failed {
+					if (self.opts.fail_fast && failed) || self.bailed() {
 						joinset.abort_all();
 						break;
 					}
@@ -145,10 +203,54 @@ impl RunnerContext {
 			global: self.global.clone(),
 			base_url: self.base_url.clone(),
 			timeout_ms: self.timeout_ms,
+			retry: self.retry.clone(),
 			run_id: self.run_id.clone(),
+			test_prefix: self.test_prefix.clone(),
+			failed_cases: self.failed_cases.clone(),
+			sessions: self.opts.reuse_connections.then(SessionCache::default),
 		}
 	}
 
+	/// Writes each suite's file, target ns/db and cases without running anything.
+	pub fn write_list(&self, out: &mut impl Write, suites: &[LoadedSuite]) -> io::Result<()> {
+		for suite in suites {
+			let suite_name = suite
+				.spec
+				.name
+				.clone()
+				.unwrap_or_else(|| suite.path.to_string_lossy().to_string());
+			let target = suite_target(
+				&self.cfg,
+				&self.run_id,
+				&self.test_prefix,
+				self.opts.no_ephemeral,
+				suite,
+				&suite_name,
+			);
+			writeln!(out, "{} ({})", suite_name, suite.path.display())?;
+			writeln!(
+				out,
+				"  target: {}/{}{}",
+				target.namespace,
+				target.database,
+				if target.ephemeral {
+					" (created for the run)"
+				} else {
+					" (existing)"
+				}
+			)?;
+			for case in &suite.spec.cases {
+				let skipped = if suite.spec.skip || case.skip {
+					" [skip]"
+				} else {
+					""
+				};
+				writeln!(out, "  - {} ({}){skipped}", case.name, case.kind.label())?;
+			}
+		}
+		Ok(())
+	}
+
 	async fn run_suite(&self, suite: LoadedSuite) -> Result<SuiteReport> {
 		let started = Instant::now();
 		let suite_name = suite
@@ -156,22 +258,107 @@ impl RunnerContext {
 			.name
 			.clone()
 			.unwrap_or_else(|| suite.path.to_string_lossy().to_string());
-		let slug = slugify(&format!("{}-{}", suite_name, suite.path.display()));
-		let namespace = format!("{}_sk_test_{}_{}", self.cfg.ns(), self.run_id, slug);
-		let database = format!("{}_sk_test_{}_{}", self.cfg.db(), self.run_id, slug);
+		let SuiteTarget {
+			namespace,
+			database,
+			ephemeral,
+		} = suite_target(
+			&self.cfg,
+			&self.run_id,
+			&self.test_prefix,
+			self.opts.no_ephemeral,
+			&suite,
+			&suite_name,
+		);
 		let host = self.cfg.host().to_string();
+		let is_skipped = |case: &CaseSpec| suite.spec.skip || case.skip;
+		let runnable = suite.spec.cases.iter().any(|case| !is_skipped(case));
+		if runnable && !ephemeral {
+			eprintln!(
+				"warning: suite '{}' runs against existing database {}/{}; any write case will modify it",
+				suite_name, namespace, database
+			);
+		}
 
-		let actors = self
-			.prepare_suite(&suite, &host, &namespace, &database)
-			.await?;
+		let actors = if runnable {
+			self.prepare_suite(&suite, &host, &namespace, &database, ephemeral)
+				.await?
+		} else {
+			HashMap::new()
+		};
+		let base_url = self.suite_base_url(&suite);
+		let hooks = &suite.spec;
+		let setup_start = Instant::now();
+		let before_all_err = if runnable {
+			run_suite_hook(&actors, &hooks.before_all, "before_all", &self.retry)
+				.await
+				.err()
+				.map(|err| format!("suite setup failed: {err:#}"))
+		} else {
+			None
+		};
+		let setup_duration_ms = setup_start.elapsed().as_millis();
+		let snapshots = SnapshotContext::for_suite(&suite.path, self.opts.update_snapshots);
 		let mut cases = Vec::new();
 
 		for case in &suite.spec.cases {
+			if self.bailed() {
+				break;
+			}
+			if is_skipped(case) {
+				cases.push(CaseReport::skipped(case));
+				continue;
+			}
 			let case_start = Instant::now();
-			let case_result =
-				run_case(case, &actors, self.base_url.as_deref(), self.timeout_ms).await;
+			let case_retry = self.case_retry(case);
+			let case_timeout = case.case_timeout_ms.unwrap_or(self.timeout_ms);
+			let (case_result, attempts) = match &before_all_err {
+				Some(err) => (Err(anyhow!("{err}")), 1),
+				None => {
+					case_retry
+						.run(
+							|report: &CaseReport| !report.passed,
+							|| async {
+								let result = with_case_timeout(case_timeout, async {
+									run_suite_hook(
+										&actors,
+										&hooks.before_each,
+										"before_each",
+										&self.retry,
+									)
+									.await?;
+									run_case_with_before_sql(
+										case,
+										&actors,
+										base_url.as_deref(),
+										self.timeout_ms,
+										&self.retry,
+										&self.global.api,
+										&snapshots,
+									)
+									.await
+								})
+								.await;
+								let result =
+									run_after_sql(case, &actors, &self.retry, result).await;
+								if let Err(err) = run_suite_hook(
+									&actors,
+									&hooks.after_each,
+									"after_each",
+									&self.retry,
+								)
+								.await
+								{
+									eprintln!("warning: suite '{}': {:#}", suite_name, err);
+								}
+								result
+							},
+						)
+						.await
+				}
+			};
 
-			let report = match case_result {
+			let mut report = match case_result {
 				Ok(mut report) => {
 					report.duration_ms = case_start.elapsed().as_millis();
 					report
@@ -183,22 +370,62 @@ impl RunnerContext {
 					passed: false,
 					message: Some(format!("{err:#}")),
 					assertions: Vec::new(),
+					attempt: 1,
+					retry_count: 0,
+					latency_ms: None,
 				},
 			};
+			report.attempt = attempts;
+			report.retry_count = case_retry.retries;
+			if attempts > 1 {
+				let note = format!("attempt {} of {}", attempts, case_retry.retries + 1);
+				report.message = Some(match report.message.take() {
+					Some(message) => format!("{message} ({note})"),
+					None => format!("passed on {note}"),
+				});
+			}
 
 			let failed = !report.passed;
 			cases.push(report);
-			if self.opts.fail_fast && failed {
+			if failed && (self.record_failure() || self.opts.fail_fast) {
 				break;
 			}
 		}
+		let budget = match suite.spec.max_total_ms {
+			Some(max_total_ms) if runnable => {
+				let budget = suite_budget_report(&cases, max_total_ms);
+				if !budget.passed {
+					self.record_failure();
+				}
+				Some(budget)
+			}
+			_ => None,
+		};
+
+		let teardown_start = Instant::now();
+		if runnable
+			&& let Err(err) =
+				run_suite_hook(&actors, &hooks.after_all, "after_all", &self.retry).await
+		{
+			eprintln!("warning: suite '{}': {:#}", suite_name, err);
+		}
+		let teardown_duration_ms = teardown_start.elapsed().as_millis();
 
 		let cases_total = cases.len();
 		let cases_failed = cases.iter().filter(|c| !c.passed).count();
-		let cases_passed = cases_total.saturating_sub(cases_failed);
+		let cases_skipped = cases.iter().filter(|c| c.is_skipped()).count();
+		let cases_passed = cases_total.saturating_sub(cases_failed + cases_skipped);
 
-		if !self.opts.keep_db {
-			if let Err(err) = cleanup_suite_db(&self.cfg, &host, &namespace, &database).await {
+		if runnable && ephemeral && !self.opts.keep_db {
+			if let Err(err) = cleanup_suite_db(
+				&self.cfg,
+				&host,
+				&namespace,
+				&database,
+				self.sessions.as_ref(),
+			)
+			.await
+			{
 				eprintln!(
 					"warning: failed to clean up test db {}/{}: {:#}",
 					namespace, database, err
@@ -212,44 +439,112 @@ impl RunnerContext {
 			namespace,
 			database,
 			duration_ms: started.elapsed().as_millis(),
+			setup_duration_ms,
+			teardown_duration_ms,
 			cases_total,
 			cases_passed,
 			cases_failed,
+			cases_skipped,
 			cases,
+			budget,
 		})
 	}
 
+	fn case_retry(&self, case: &CaseSpec) -> CaseRetry {
+		let defaults = &self.global.defaults;
+		let retries = case.retries.or(defaults.retries).unwrap_or(0);
+		let retry_on = case.retry_on.or(defaults.retry_on).unwrap_or_default();
+		let delay_ms = case
+			.retry_delay_ms
+			.or(defaults.retry_delay_ms)
+			.unwrap_or(200);
+		if self.opts.global_retry == 0 {
+			return CaseRetry {
+				retries,
+				delay_ms,
+				retry_on,
+			};
+		}
+		CaseRetry {
+			retries: retries.max(self.opts.global_retry),
+			delay_ms,
+			retry_on: RetryOn::Failure,
+		}
+	}
+
+	fn suite_base_url(&self, suite: &LoadedSuite) -> Option<String> {
+		if self.opts.base_url.is_some() {
+			return self.base_url.clone();
+		}
+		suite
+			.spec
+			.base_url
+			.clone()
+			.map(super::normalize_base_url)
+			.or_else(|| self.base_url.clone())
+	}
+
 	async fn prepare_suite(
 		&self,
 		suite: &LoadedSuite,
 		host: &str,
 		namespace: &str,
 		database: &str,
+		ephemeral: bool,
 	) -> Result<HashMap<String, ActorSession>> {
 		let merged = merged_actor_specs(&self.global.actors, &suite.spec.actors);
-		let bootstrap_actors =
-			build_actor_sessions(&self.cfg, host, namespace, database, &BTreeMap::new()).await?;
+		if !ephemeral {
+			return build_actor_sessions(
+				&self.cfg,
+				host,
+				namespace,
+				database,
+				&merged,
+				self.sessions.as_ref(),
+			)
+			.await;
+		}
+
+		let bootstrap_actors = build_actor_sessions(
+			&self.cfg,
+			host,
+			namespace,
+			database,
+			&BTreeMap::new(),
+			self.sessions.as_ref(),
+		)
+		.await?;
 		let root = require_actor(&bootstrap_actors, "root")?;
 
-		if !self.opts.no_setup {
+		let steps = self.opts.prep_steps();
+		if steps.setup {
 			run_setup(&root.db).await?;
 		}
-		if !self.opts.no_sync {
+		if steps.sync {
 			sync::run_sync(
 				&root.db,
 				SyncOpts {
 					watch: false,
 					debounce_ms: 250,
 					dry_run: false,
+					diff: false,
 					fail_fast: true,
 					prune: true,
 					allow_shared_prune: true,
+					confirm_prune: false,
+					yes: false,
+					transactional: true,
+					only: None,
+					lock_timeout_secs: DEFAULT_LOCK_TIMEOUT_SECS,
+					file_timeout_ms: None,
+					ignore: self.cfg.sync_ignore().to_vec(),
+					dry_run_out: None,
 				},
 			)
 			.await?;
 		}
-		if !self.opts.no_seed {
-			seed::seed(&root.db).await?;
+		if steps.seed {
+			seed::seed(&root.db, false).await?;
 		}
 
 		for fixture in self
@@ -258,7 +553,13 @@ impl RunnerContext {
 			.iter()
 			.filter(|f| fixture_targets_root(f))
 		{
-			apply_fixture(fixture, &bootstrap_actors, Path::new("database/tests")).await?;
+			apply_fixture(
+				fixture,
+				&bootstrap_actors,
+				Path::new("database/tests"),
+				&self.retry,
+			)
+			.await?;
 		}
 		for fixture in suite
 			.spec
@@ -270,10 +571,18 @@ impl RunnerContext {
 				.path
 				.parent()
 				.unwrap_or_else(|| Path::new("database/tests/suites"));
-			apply_fixture(fixture, &bootstrap_actors, suite_base).await?;
+			apply_fixture(fixture, &bootstrap_actors, suite_base, &self.retry).await?;
 		}
 
-		let actors = build_actor_sessions(&self.cfg, host, namespace, database, &merged).await?;
+		let actors = build_actor_sessions(
+			&self.cfg,
+			host,
+			namespace,
+			database,
+			&merged,
+			self.sessions.as_ref(),
+		)
+		.await?;
 
 		for fixture in self
 			.global
@@ -281,7 +590,7 @@ impl RunnerContext {
 			.iter()
 			.filter(|f| !fixture_targets_root(f))
 		{
-			apply_fixture(fixture, &actors, Path::new("database/tests")).await?;
+			apply_fixture(fixture, &actors, Path::new("database/tests"), &self.retry).await?;
 		}
 		for fixture in suite
 			.spec
@@ -293,25 +602,197 @@ impl RunnerContext {
 				.path
 				.parent()
 				.unwrap_or_else(|| Path::new("database/tests/suites"));
-			apply_fixture(fixture, &actors, suite_base).await?;
+			apply_fixture(fixture, &actors, suite_base, &self.retry).await?;
 		}
 
 		Ok(actors)
 	}
 }
 
+struct SuiteTarget {
+	namespace: String,
+	database: String,
+	/// Whether the runner created this ns/db and should provision and remove it.
+	ephemeral: bool,
+}
+
+fn suite_target(
+	cfg: &DbCfg,
+	run_id: &str,
+	prefix: &str,
+	no_ephemeral: bool,
+	suite: &LoadedSuite,
+	suite_name: &str,
+) -> SuiteTarget {
+	if no_ephemeral || suite.spec.use_existing_db {
+		return SuiteTarget {
+			namespace: cfg.ns().to_string(),
+			database: cfg.db().to_string(),
+			ephemeral: false,
+		};
+	}
+	let slug = slugify(&format!("{}-{}", suite_name, suite.path.display()));
+	let marker = test_db_marker(prefix);
+	SuiteTarget {
+		namespace: format!("{}{marker}{}_{}", cfg.ns(), run_id, slug),
+		database: format!("{}{marker}{}_{}", cfg.db(), run_id, slug),
+		ephemeral: true,
+	}
+}
+
+/// Runs suite-level hook SQL as root.
+async fn run_suite_hook(
+	actors: &HashMap<String, ActorSession>,
+	statements: &[String],
+	hook: &str,
+	retry: &TransientRetry,
+) -> Result<()> {
+	if statements.is_empty() {
+		return Ok(());
+	}
+	let root = require_actor(actors, "root")?;
+	for sql in statements {
+		execute_sql_value(&root.db, sql, retry)
+			.await
+			.with_context(|| format!("{hook} failed: {sql}"))?;
+	}
+	Ok(())
+}
+
+async fn with_case_timeout(
+	timeout_ms: u64,
+	case: impl Future<Output = Result<CaseReport>>,
+) -> Result<CaseReport> {
+	match tokio::time::timeout(Duration::from_millis(timeout_ms), case).await {
+		Ok(result) => result,
+		Err(_) => Err(anyhow!("timed out after {timeout_ms}ms")),
+	}
+}
+
+/// Runs the case's `before_sql`, then the case. `after_sql` is left to
+/// [`run_after_sql`] so teardown still runs when this is cut short by a timeout.
+async fn run_case_with_before_sql(
+	case: &CaseSpec,
+	actors: &HashMap<String, ActorSession>,
+	base_url: Option<&str>,
+	timeout_ms: u64,
+	retry: &TransientRetry,
+	api_cfg: &ApiClientConfig,
+	snapshots: &SnapshotContext,
+) -> Result<CaseReport> {
+	let actor = require_actor(actors, actor_name_or_default(case.kind.actor()))?;
+	for sql in &case.before_sql {
+		execute_sql_value(&actor.db, sql, retry)
+			.await
+			.with_context(|| format!("before_sql failed: {sql}"))?;
+	}
+	run_case(
+		case, actors, base_url, timeout_ms, retry, api_cfg, snapshots,
+	)
+	.await
+}
+
+/// Runs the case's `after_sql` whatever `result` is, adding teardown failures to the
+/// report message, or to the error, without failing a passed case.
+async fn run_after_sql(
+	case: &CaseSpec,
+	actors: &HashMap<String, ActorSession>,
+	retry: &TransientRetry,
+	result: Result<CaseReport>,
+) -> Result<CaseReport> {
+	if case.after_sql.is_empty() {
+		return result;
+	}
+	let actor = require_actor(actors, actor_name_or_default(case.kind.actor()))?;
+	let mut after_errs = Vec::new();
+	for sql in &case.after_sql {
+		if let Err(err) = execute_sql_value(&actor.db, sql, retry).await {
+			after_errs.push(format!("after_sql failed: {sql}: {err:#}"));
+		}
+	}
+	match result {
+		Ok(mut report) => {
+			for warning in after_errs {
+				append_warning(&mut report, &warning);
+			}
+			Ok(report)
+		}
+		Err(err) if after_errs.is_empty() => Err(err),
+		Err(err) => Err(anyhow!(
+			"{err:#}; warning: {}",
+			after_errs.join("; warning: ")
+		)),
+	}
+}
+
+/// Teardown problems are surfaced in the message without failing the case.
+fn append_warning(report: &mut CaseReport, warning: &str) {
+	report.message = Some(match report.message.take() {
+		Some(message) => format!("{message}; warning: {warning}"),
+		None => format!("warning: {warning}"),
+	});
+}
+
 async fn run_case(
-	case: &crate::tester::types::CaseSpec,
+	case: &CaseSpec,
 	actors: &HashMap<String, ActorSession>,
 	base_url: Option<&str>,
 	timeout_ms: u64,
+	retry: &TransientRetry,
+	api_cfg: &ApiClientConfig,
+	snapshots: &SnapshotContext,
 ) -> Result<CaseReport> {
 	match &case.kind {
 		CaseKind::SqlExpect(spec) => {
 			let actor_name = actor_name_or_default(spec.actor.as_deref());
 			let actor = require_actor(actors, actor_name)?;
-			let result = execute_sql_value(&actor.db, &spec.sql).await;
-			report_sql_expect(
+			let mut headers = None;
+			let (result, failed_at) = match spec.error_at_index {
+				Some(_) if spec.allow => {
+					bail!("case '{}' sets error_at_index but allow = true", case.name)
+				}
+				Some(index) => {
+					let errors = retry
+						.run(|| query_statement_errors(&actor.db, &spec.sql))
+						.await?;
+					let result = match errors.iter().flatten().next() {
+						Some(err) => Err(anyhow!("{err}")),
+						None => Ok(Value::Null),
+					};
+					(result, Some(assert_error_at_index(&errors, index)))
+				}
+				None if !spec.header_assertions.is_empty() => {
+					let result = retry
+						.run(|| exec_surql_with_headers(&actor.db, &actor.sql_http, &spec.sql))
+						.await
+						.map(|(value, response_headers)| {
+							headers = Some(response_headers);
+							value
+						});
+					(result, None)
+				}
+				None => (execute_sql_value(&actor.db, &spec.sql, retry).await, None),
+			};
+			let created = match (&spec.created_in_table, &result) {
+				(Some(table), Ok(value)) if spec.allow => {
+					Some(assert_created_in_table(value, table))
+				}
+				_ => None,
+			};
+			let result = match result {
+				Ok(mut value) => {
+					redact_paths(&mut value, &spec.redact)?;
+					Ok(value)
+				}
+				Err(err) => Err(err),
+			};
+			let snapshot = match (&spec.snapshot, &result) {
+				(Some(file), Ok(value)) if spec.allow => {
+					Some(check_snapshot(snapshots, file, value)?)
+				}
+				_ => None,
+			};
+			let mut report = report_sql_expect(
 				case.name.clone(),
 				case.kind.label().to_string(),
 				result,
@@ -320,62 +801,145 @@ async fn run_case(
 				spec.error_code.as_deref(),
 				&spec.assertions,
 				actor,
-			)
+			)?;
+			let header_checks = match &headers {
+				Some(headers) => spec
+					.header_assertions
+					.iter()
+					.enumerate()
+					.map(|(idx, a)| assert_header_value(headers, a, idx))
+					.collect::<Result<Vec<_>>>()?,
+				None => Vec::new(),
+			};
+			for assertion in created
+				.into_iter()
+				.chain(failed_at)
+				.chain(header_checks)
+				.chain(snapshot)
+			{
+				if !assertion.passed && report.passed {
+					report.passed = false;
+					report.message = Some("one or more assertions failed".to_string());
+				}
+				report.assertions.push(assertion);
+			}
+			Ok(report)
+		}
+		CaseKind::BulkSqlExpect(spec) => {
+			if spec.steps.is_empty() {
+				bail!("bulk_sql_expect case '{}' has no steps", case.name);
+			}
+			let actor_name = actor_name_or_default(spec.actor.as_deref());
+			let actor = require_actor(actors, actor_name)?;
+
+			let mut assertions = Vec::new();
+			let mut failed_steps = Vec::new();
+			for step in &spec.steps {
+				let result = execute_sql_value(&actor.db, &step.sql, retry).await;
+				let step_report = report_sql_expect(
+					step.name.clone(),
+					case.kind.label().to_string(),
+					result,
+					step.allow,
+					step.error_contains.as_deref(),
+					step.error_code.as_deref(),
+					&step.assertions,
+					actor,
+				)?;
+				assertions.extend(step_report.assertions.into_iter().map(|assertion| {
+					AssertionReport {
+						name: format!("{}.{}", step.name, assertion.name),
+						..assertion
+					}
+				}));
+				if !step_report.passed {
+					failed_steps.push(step.name.clone());
+					if spec.fail_fast_on_step {
+						break;
+					}
+				}
+			}
+
+			let passed = failed_steps.is_empty();
+			Ok(CaseReport {
+				name: case.name.clone(),
+				kind: case.kind.label().to_string(),
+				duration_ms: 0,
+				passed,
+				message: (!passed).then(|| format!("failed steps: {}", failed_steps.join(", "))),
+				assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
+			})
 		}
 		CaseKind::PermissionsMatrix(spec) => {
 			if spec.rules.is_empty() {
 				bail!("permissions_matrix case '{}' has no rules", case.name);
 			}
-			let actor_name = actor_name_or_default(spec.actor.as_deref());
-			let actor = require_actor(actors, actor_name)?;
+			let targets = permission_matrix_actors(&case.name, spec, actors)?;
 			let root = require_actor(actors, "root")?;
 			let record_id = spec
 				.record_id
 				.clone()
 				.unwrap_or_else(|| "perm_record".to_string());
+			let multi_actor = targets.len() > 1 || spec.actor.as_deref() == Some(ALL_ACTORS);
 
 			let mut assertions = Vec::new();
-			for (idx, rule) in spec.rules.iter().enumerate() {
-				let seed_sql = format!(
-					"UPSERT {}:{} MERGE {{ __surrealkit_perm_seed: true }};",
-					spec.table, record_id
-				);
-				let _ = execute_sql_value(&root.db, &seed_sql).await;
-				let sql = match rule.action {
-					PermissionAction::Create => format!(
-						"CREATE {}:{}_create_{} CONTENT {{ marker: 'perm' }};",
-						spec.table, record_id, idx
-					),
-					PermissionAction::Select => {
-						format!("SELECT * FROM {}:{};", spec.table, record_id)
-					}
-					PermissionAction::Update => format!(
-						"UPDATE {}:{} SET marker = 'updated_{}';",
-						spec.table, record_id, idx
-					),
-					PermissionAction::Delete => {
-						format!("DELETE {}:{};", spec.table, record_id)
-					}
-					PermissionAction::Query => rule.sql.clone().ok_or_else(|| {
-						anyhow!(
-							"permissions_matrix action=query in '{}' requires sql",
-							case.name
-						)
-					})?,
+			for (actor_idx, (actor_name, actor)) in targets.into_iter().enumerate() {
+				// Created records get per-actor ids so a later actor's CREATE does not collide.
+				let create_prefix = if multi_actor {
+					format!("{record_id}_create_{actor_idx}")
+				} else {
+					format!("{record_id}_create")
 				};
+				for (idx, rule) in spec.rules.iter().enumerate() {
+					let seed_sql = format!(
+						"UPSERT {}:{} MERGE {{ __surrealkit_perm_seed: true }};",
+						spec.table, record_id
+					);
+					let _ = execute_sql_value(&root.db, &seed_sql, retry).await;
+					let sql = match rule.action {
+						PermissionAction::Create => format!(
+							"CREATE {}:{}_{} CONTENT {{ marker: 'perm' }};",
+							spec.table, create_prefix, idx
+						),
+						PermissionAction::Select => {
+							format!("SELECT * FROM {}:{};", spec.table, record_id)
+						}
+						PermissionAction::Update => format!(
+							"UPDATE {}:{} SET marker = 'updated_{}';",
+							spec.table, record_id, idx
+						),
+						PermissionAction::Delete => {
+							format!("DELETE {}:{};", spec.table, record_id)
+						}
+						PermissionAction::Query => rule.sql.clone().ok_or_else(|| {
+							anyhow!(
+								"permissions_matrix action=query in '{}' requires sql",
+								case.name
+							)
+						})?,
+					};
 
-				let result = execute_sql_value(&actor.db, &sql).await;
-				let mut report = evaluate_outcome(
-					format!("rule_{}", idx + 1),
-					result,
-					rule.allow,
-					rule.error_contains.as_deref(),
-					None,
-				)?;
-				if !report.passed {
-					report.message = format!("{}; sql={}", report.message, sql);
+					let result = execute_sql_value(&actor.db, &sql, retry).await;
+					let name = if multi_actor {
+						format!("{}.rule_{}", actor_name, idx + 1)
+					} else {
+						format!("rule_{}", idx + 1)
+					};
+					let mut report = evaluate_outcome(
+						name,
+						result,
+						rule.allow,
+						rule.error_contains.as_deref(),
+						None,
+					)?;
+					if !report.passed {
+						report.message = format!("{}; sql={}", report.message, sql);
+					}
+					assertions.push(report);
 				}
-				assertions.push(report);
 			}
 
 			let passed = assertions.iter().all(|x| x.passed);
@@ -390,6 +954,9 @@ async fn run_case(
 					Some("one or more permission rules failed".to_string())
 				},
 				assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
 			})
 		}
 		CaseKind::SchemaMetadata(spec) => {
@@ -404,7 +971,7 @@ async fn run_case(
 					.ok_or_else(|| anyhow!("schema_metadata requires either table or sql"))?;
 				format!("INFO FOR TABLE {};", table)
 			};
-			let value = execute_sql_value(&actor.db, &sql).await?;
+			let value = execute_sql_value(&actor.db, &sql, retry).await?;
 			let text = value.to_string();
 			let mut assertions = Vec::new();
 			for (idx, needle) in spec.contains.iter().enumerate() {
@@ -414,6 +981,9 @@ async fn run_case(
 					message: format!("expected metadata to contain '{}'", needle),
 				});
 			}
+			if let Some(field_comment) = &spec.field_comment {
+				assertions.push(assert_field_comment(&value, field_comment));
+			}
 			for (idx, assertion) in spec.assertions.iter().enumerate() {
 				assertions.push(assert_json_value_with_context(
 					&value,
@@ -434,18 +1004,23 @@ async fn run_case(
 					Some("schema metadata assertions failed".to_string())
 				},
 				assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
 			})
 		}
 		CaseKind::SchemaBehavior(spec) => {
 			let actor_name = actor_name_or_default(spec.actor.as_deref());
 			let actor = require_actor(actors, actor_name)?;
 			for sql in &spec.setup_sql {
-				execute_sql_value(&actor.db, sql).await.with_context(|| {
-					format!("schema_behavior setup failed in case '{}'", case.name)
-				})?;
+				execute_sql_value(&actor.db, sql, retry)
+					.await
+					.with_context(|| {
+						format!("schema_behavior setup failed in case '{}'", case.name)
+					})?;
 			}
 
-			let action_result = execute_sql_value(&actor.db, &spec.action_sql).await;
+			let action_result = execute_sql_value(&actor.db, &spec.action_sql, retry).await;
 			let mut report = report_sql_expect(
 				case.name.clone(),
 				case.kind.label().to_string(),
@@ -462,7 +1037,7 @@ async fn run_case(
 					.verify_sql
 					.clone()
 					.unwrap_or_else(|| spec.action_sql.clone());
-				let value = execute_sql_value(&actor.db, &verify_sql).await?;
+				let value = execute_sql_value(&actor.db, &verify_sql, retry).await?;
 				for (idx, assertion) in spec.assertions.iter().enumerate() {
 					report.assertions.push(assert_json_value_with_context(
 						&value,
@@ -479,6 +1054,157 @@ async fn run_case(
 
 			Ok(report)
 		}
+		CaseKind::Transaction(spec) => {
+			if spec.statements.is_empty() {
+				bail!("transaction case '{}' has no statements", case.name);
+			}
+			if !spec.assertions.is_empty() && spec.verify_sql.is_none() {
+				bail!(
+					"transaction case '{}' has assertions but no verify_sql",
+					case.name
+				);
+			}
+			let actor_name = actor_name_or_default(spec.actor.as_deref());
+			let actor = require_actor(actors, actor_name)?;
+
+			let sql = wrap_in_transaction(&transaction_body(&spec.statements));
+			let result = execute_sql_value(&actor.db, &sql, retry).await;
+			let mut assertions = vec![evaluate_outcome(
+				"commit".to_string(),
+				result,
+				spec.expect_commit,
+				spec.error_contains.as_deref(),
+				None,
+			)?];
+
+			if let Some(verify_sql) = &spec.verify_sql {
+				let value = execute_sql_value(&actor.db, verify_sql, retry).await?;
+				for (idx, assertion) in spec.assertions.iter().enumerate() {
+					assertions.push(assert_json_value_with_context(
+						&value,
+						assertion,
+						idx,
+						&actor_assertion_context(actor),
+					)?);
+				}
+			}
+
+			let passed = assertions.iter().all(|x| x.passed);
+			Ok(CaseReport {
+				name: case.name.clone(),
+				kind: case.kind.label().to_string(),
+				duration_ms: 0,
+				passed,
+				message: if passed {
+					None
+				} else {
+					Some("transaction assertions failed".to_string())
+				},
+				assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
+			})
+		}
+		CaseKind::LiveQuery(spec) => {
+			let actor_name = actor_name_or_default(spec.actor.as_deref());
+			let actor = require_actor(actors, actor_name)?;
+			let wanted = spec
+				.expect_count
+				.or_else(|| (!spec.expect_actions.is_empty()).then_some(spec.expect_actions.len()));
+
+			let mut response = sql_query(&actor.db, spec.live_sql.as_str())
+				.await
+				.with_context(|| format!("starting live query in case '{}'", case.name))?;
+			let stream = response
+				.stream::<surrealdb_types::Value>(0)
+				.with_context(|| format!("live query stream in case '{}'", case.name))?;
+			let mut events_stream = stream.map(|item| item.map(live_event).map_err(Into::into));
+
+			for sql in spec.statements.iter().chain(spec.trigger_sql.as_ref()) {
+				execute_sql_value(&actor.db, sql, retry)
+					.await
+					.with_context(|| {
+						format!("live_query statement failed in case '{}'", case.name)
+					})?;
+			}
+			let timeout = Duration::from_millis(spec.timeout_ms.unwrap_or(timeout_ms));
+			let grace = spec
+				.expect_count
+				.map(|_| Duration::from_millis(EXTRA_NOTIFICATION_GRACE_MS));
+			let events = collect_live_events(&mut events_stream, wanted, grace, timeout).await?;
+			// Dropping the stream kills the live query on the server.
+			drop(events_stream);
+
+			let mut assertions = Vec::new();
+			if let Some(expected) = spec.expect_count {
+				let passed = events.len() == expected;
+				assertions.push(AssertionReport {
+					name: "notifications".to_string(),
+					passed,
+					message: if passed {
+						format!("received {} notification(s)", expected)
+					} else {
+						format!(
+							"expected {} notification(s) within the timeout, got {}",
+							expected,
+							events.len()
+						)
+					},
+				});
+			}
+			if !spec.expect_actions.is_empty() {
+				let actual: Vec<&str> = events
+					.iter()
+					.filter_map(|event| event.get("action").and_then(Value::as_str))
+					.collect();
+				let expected: Vec<String> = spec
+					.expect_actions
+					.iter()
+					.map(|action| action.to_ascii_uppercase())
+					.collect();
+				let passed = actual == expected;
+				assertions.push(AssertionReport {
+					name: "actions".to_string(),
+					passed,
+					message: if passed {
+						format!("received {:?}", actual)
+					} else {
+						format!("expected actions {:?}, got {:?}", expected, actual)
+					},
+				});
+			}
+			let first = events.first().cloned().unwrap_or(Value::Null);
+			let all = Value::Array(events);
+			let ctx = actor_assertion_context(actor);
+			for (idx, assertion) in spec.assertions.iter().enumerate() {
+				assertions.push(assert_json_value_with_context(
+					&first, assertion, idx, &ctx,
+				)?);
+			}
+			for (idx, assertion) in spec.events_assertions.iter().enumerate() {
+				let mut report = assert_json_value_with_context(&all, assertion, idx, &ctx)?;
+				report.name = format!("events_assertion_{}", idx + 1);
+				assertions.push(report);
+			}
+
+			let passed = assertions.iter().all(|x| x.passed);
+			Ok(CaseReport {
+				name: case.name.clone(),
+				kind: case.kind.label().to_string(),
+				duration_ms: 0,
+				passed,
+				message: if passed {
+					None
+				} else {
+					Some("live query assertions failed".to_string())
+				},
+				assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
+			})
+		}
 		CaseKind::ApiRequest(spec) => {
 			let actor_name = actor_name_or_default(spec.actor.as_deref());
 			let actor = require_actor(actors, actor_name)?;
@@ -488,7 +1214,7 @@ async fn run_case(
 					case.name
 				)
 			})?;
-			let api_result = execute_api_case(base_url, spec, actor, timeout_ms).await?;
+			let api_result = execute_api_case(base_url, spec, actor, timeout_ms, api_cfg).await?;
 			let passed = api_result.assertions.iter().all(|x| x.passed);
 			Ok(CaseReport {
 				name: case.name.clone(),
@@ -499,11 +1225,44 @@ async fn run_case(
 					None
 				} else {
 					Some(format!(
-						"api assertions failed (status={})",
-						api_result.status
+						"api assertions failed (status={}, duration={}ms)",
+						api_result.status, api_result.duration_ms
 					))
 				},
 				assertions: api_result.assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: Some(api_result.duration_ms),
+			})
+		}
+		CaseKind::GraphQLRequest(spec) => {
+			let actor_name = actor_name_or_default(spec.actor.as_deref());
+			let actor = require_actor(actors, actor_name)?;
+			let base_url = base_url.ok_or_else(|| {
+				anyhow!(
+					"graphql_request case '{}' requires base URL (--base-url, config default, or env)",
+					case.name
+				)
+			})?;
+			let result = execute_graphql_case(base_url, spec, actor, timeout_ms, api_cfg).await?;
+			let passed = result.assertions.iter().all(|x| x.passed);
+			Ok(CaseReport {
+				name: case.name.clone(),
+				kind: case.kind.label().to_string(),
+				duration_ms: 0,
+				passed,
+				message: if passed {
+					None
+				} else {
+					Some(format!(
+						"graphql assertions failed (status={})",
+						result.status
+					))
+				},
+				assertions: result.assertions,
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: Some(result.duration_ms),
 			})
 		}
 	}
@@ -561,17 +1320,8 @@ fn report_sql_expect(
 			});
 		}
 		(false, Err(err)) => {
-			let text = format!("{err:#}");
-			let contains_ok = error_contains
-				.map(|needle| text.contains(needle))
-				.unwrap_or(true);
-			let code_ok = error_code.map(|code| text.contains(code)).unwrap_or(true);
-			passed = contains_ok && code_ok;
-			message = if passed {
-				None
-			} else {
-				Some(format!("error mismatch, got '{}'", text))
-			};
+			message = error_mismatch(&err, error_contains, error_code);
+			passed = message.is_none();
 			assertions.push(AssertionReport {
 				name: "outcome".to_string(),
 				passed,
@@ -591,9 +1341,60 @@ fn report_sql_expect(
 		passed,
 		message,
 		assertions,
+		attempt: 1,
+		retry_count: 0,
+		latency_ms: None,
 	})
 }
 
+/// Checks the summed response times of the suite's API and GraphQL cases against its
+/// `max_total_ms` budget.
+fn suite_budget_report(cases: &[CaseReport], max_total_ms: u64) -> BudgetReport {
+	let total_ms: u128 = cases.iter().filter_map(|case| case.latency_ms).sum();
+	BudgetReport {
+		total_ms,
+		max_total_ms,
+		passed: total_ms <= u128::from(max_total_ms),
+	}
+}
+
+/// The actors a `permissions_matrix` case runs as: `actors`, every actor for
+/// `actor = "all"` (sorted by name), or the single `actor`.
+fn permission_matrix_actors<'a>(
+	case_name: &str,
+	spec: &PermissionsMatrixCase,
+	actors: &'a HashMap<String, ActorSession>,
+) -> Result<Vec<(&'a str, &'a ActorSession)>> {
+	if !spec.actors.is_empty() {
+		if spec.actor.is_some() {
+			bail!("permissions_matrix case '{case_name}' sets both actor and actors");
+		}
+		return spec
+			.actors
+			.iter()
+			.map(|name| {
+				let (name, actor) = actors
+					.get_key_value(name.as_str())
+					.ok_or_else(|| anyhow!("actor '{}' not configured", name))?;
+				Ok((name.as_str(), actor))
+			})
+			.collect();
+	}
+	if spec.actor.as_deref() == Some(ALL_ACTORS) {
+		let mut all: Vec<_> = actors
+			.iter()
+			.map(|(name, actor)| (name.as_str(), actor))
+			.collect();
+		all.sort_by_key(|(name, _)| *name);
+		return Ok(all);
+	}
+	let name = actor_name_or_default(spec.actor.as_deref());
+	let (name, actor) = actors
+		.get_key_value(name)
+		.ok_or_else(|| anyhow!("actor '{}' not configured", name))?;
+	Ok(vec![(name.as_str(), actor)])
+}
+
 fn actor_assertion_context(actor: &ActorSession) -> JsonAssertionContext {
 	JsonAssertionContext {
 		actor_auth: actor.auth.clone(),
@@ -622,19 +1423,11 @@ fn evaluate_outcome(
 			})
 		}
 		(false, Err(err)) => {
-			let text = format!("{err:#}");
-			let contains_ok = error_contains
-				.map(|needle| text.contains(needle))
-				.unwrap_or(true);
-			let code_ok = error_code.map(|code| text.contains(code)).unwrap_or(true);
+			let mismatch = error_mismatch(&err, error_contains, error_code);
 			Ok(AssertionReport {
 				name: label,
-				passed: contains_ok && code_ok,
-				message: if contains_ok && code_ok {
-					"query failed as expected".to_string()
-				} else {
-					format!("error mismatch, got '{}'", text)
-				},
+				passed: mismatch.is_none(),
+				message: mismatch.unwrap_or_else(|| "query failed as expected".to_string()),
 			})
 		}
 		(false, Ok(_)) => Ok(AssertionReport {
@@ -645,27 +1438,152 @@ fn evaluate_outcome(
 	}
 }
 
-async fn execute_sql_value(db: &Surreal<Any>, sql: &str) -> Result<Value> {
-	let mut response = db.query(sql).await?.check()?;
-	let raw: surrealdb_types::Value = response.take(0)?;
-	let json = Value::from_value(raw).unwrap_or(Value::Null);
-	Ok(json)
+/// Checks an expected failure against `error_contains` (message substring) and
+/// `error_code` (structured code, see [`surreal_error_code`]).
+fn error_mismatch(
+	err: &anyhow::Error,
+	error_contains: Option<&str>,
+	error_code: Option<&str>,
+) -> Option<String> {
+	let text = format!("{err:#}");
+	let code = surreal_error_code(err);
+	let contains_ok = error_contains.is_none_or(|needle| text.contains(needle));
+	let code_ok =
+		error_code.is_none_or(|expected| error_code_matches(code.as_deref(), &text, expected));
+	if contains_ok && code_ok {
+		return None;
+	}
+	Some(match code {
+		Some(code) => format!("error mismatch, got code {code}: '{text}'"),
+		None => format!("error mismatch, got '{text}'"),
+	})
+}
+
+/// `expected` matches the structured code or a leading part of it (`NotAllowed` matches
+/// `NotAllowed.Auth`), ignoring case. A message substring is still accepted so suites
+/// written against older servers keep passing.
+fn error_code_matches(code: Option<&str>, text: &str, expected: &str) -> bool {
+	let structured = code.is_some_and(|code| {
+		let (code, expected) = (code.to_ascii_lowercase(), expected.to_ascii_lowercase());
+		code == expected || code.starts_with(&format!("{expected}."))
+	});
+	structured || text.contains(expected)
+}
+
+/// Dotted error kind from the first SurrealDB error in the chain, e.g. `NotAllowed.Auth`
+/// or `AlreadyExists.Record`.
+fn surreal_error_code(err: &anyhow::Error) -> Option<String> {
+	let err = err
+		.chain()
+		.find_map(|cause| cause.downcast_ref::<surrealdb::Error>())?;
+	let details = Value::from_value(err.details().clone().into_value()).ok();
+	Some(
+		details
+			.as_ref()
+			.and_then(error_code_from_details)
+			.unwrap_or_else(|| err.kind_str().to_string()),
+	)
+}
+
+fn error_code_from_details(details: &Value) -> Option<String> {
+	let mut kinds = Vec::new();
+	let mut cursor = details;
+	while let Some(kind) = cursor.get("kind").and_then(Value::as_str) {
+		kinds.push(kind);
+		match cursor.get("details") {
+			Some(next) => cursor = next,
+			None => break,
+		}
+	}
+	(!kinds.is_empty()).then(|| kinds.join("."))
+}
+
+fn live_event(notification: Notification<surrealdb_types::Value>) -> Value {
+	serde_json::json!({
+		"action": notification.action.to_string(),
+		"data": Value::from_value(notification.data).unwrap_or(Value::Null),
+	})
+}
+
+/// How long a `live_query` case with `expect_count` keeps listening once the expected
+/// events have arrived, so that extra notifications fail the count.
+const EXTRA_NOTIFICATION_GRACE_MS: u64 = 250;
+
+/// Collects events until `wanted` have arrived or `timeout` elapses. With `grace`, keeps
+/// collecting that much longer (still within `timeout`) after `wanted` is reached.
+async fn collect_live_events<S>(
+	stream: &mut S,
+	wanted: Option<usize>,
+	grace: Option<Duration>,
+	timeout: Duration,
+) -> Result<Vec<Value>>
+where
+	S: Stream<Item = Result<Value>> + Unpin,
+{
+	let mut deadline = tokio::time::Instant::now() + timeout;
+	let mut events = Vec::new();
+	while wanted.is_none_or(|wanted| events.len() < wanted) {
+		match tokio::time::timeout_at(deadline, stream.next()).await {
+			Ok(Some(event)) => events.push(event?),
+			Ok(None) | Err(_) => return Ok(events),
+		}
+	}
+	if let Some(grace) = grace {
+		deadline = deadline.min(tokio::time::Instant::now() + grace);
+		while let Ok(Some(event)) = tokio::time::timeout_at(deadline, stream.next()).await {
+			events.push(event?);
+		}
+	}
+	Ok(events)
+}
+
+fn transaction_body(statements: &[String]) -> String {
+	statements
+		.iter()
+		.map(|stmt| {
+			let stmt = stmt.trim_end();
+			if stmt.ends_with(';') {
+				stmt.to_string()
+			} else {
+				format!("{stmt};")
+			}
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+async fn execute_sql_value(db: &Surreal<Any>, sql: &str, retry: &TransientRetry) -> Result<Value> {
+	retry.run(|| query_json(db, sql)).await
+}
+
+/// Runs every statement without short-circuiting and returns each one's error, if any.
+async fn query_statement_errors(db: &Surreal<Any>, sql: &str) -> Result<Vec<Option<String>>> {
+	let mut response = sql_query(db, sql).await?;
+	// Count before `take_errors`, which removes failed results from the response.
+	let statements = response.num_statements();
+	let mut errors = response.take_errors();
+	Ok((0..statements)
+		.map(|idx| errors.remove(&idx).map(|err| err.to_string()))
+		.collect())
 }
 
 async fn apply_fixture(
 	fixture: &crate::tester::types::FixtureSpec,
 	actors: &HashMap<String, ActorSession>,
 	base_dir: &Path,
+	retry: &TransientRetry,
 ) -> Result<()> {
 	let actor_name = actor_name_or_default(fixture.actor.as_deref());
 	let actor = require_actor(actors, actor_name)?;
 	let sql = fixture_sql(fixture, base_dir)?;
-	execute_sql_value(&actor.db, &sql).await.with_context(|| {
-		format!(
-			"fixture '{}' failed",
-			fixture.name.as_deref().unwrap_or("unnamed")
-		)
-	})?;
+	execute_sql_value(&actor.db, &sql, retry)
+		.await
+		.with_context(|| {
+			format!(
+				"fixture '{}' failed",
+				fixture.name.as_deref().unwrap_or("unnamed")
+			)
+		})?;
 	Ok(())
 }
 
@@ -692,7 +1610,7 @@ fn fixture_sql(fixture: &crate::tester::types::FixtureSpec, base_dir: &Path) ->
 	}
 }
 
-fn resolve_fixture_path(base_dir: &Path, file: &str) -> PathBuf {
+pub(super) fn resolve_fixture_path(base_dir: &Path, file: &str) -> PathBuf {
 	let candidate = Path::new(file);
 	if candidate.is_absolute() {
 		candidate.to_path_buf()
@@ -705,26 +1623,46 @@ fn fixture_targets_root(fixture: &crate::tester::types::FixtureSpec) -> bool {
 	matches!(fixture.actor.as_deref(), None | Some("root"))
 }
 
-async fn cleanup_suite_db(cfg: &DbCfg, host: &str, namespace: &str, database: &str) -> Result<()> {
-	let db = create_surreal_client(&host.to_string())
-		.await
-		.with_context(|| format!("connecting for cleanup {host}"))?;
-	db.signin(surrealdb::opt::auth::Root {
-		username: cfg.user().to_string(),
-		password: cfg.pass().to_string(),
-	})
-	.await
-	.context("cleanup root signin failed")?;
+async fn cleanup_suite_db(
+	cfg: &DbCfg,
+	host: &str,
+	namespace: &str,
+	database: &str,
+	sessions: Option<&SessionCache>,
+) -> Result<()> {
+	let db = match sessions.and_then(|sessions| sessions.root(host)) {
+		Some((db, _)) => db,
+		None => {
+			let db = create_surreal_client(host, cfg)
+				.await
+				.with_context(|| format!("connecting for cleanup {host}"))?;
+			db.signin(surrealdb::opt::auth::Root {
+				username: cfg.user().to_string(),
+				password: cfg.pass().to_string(),
+			})
+			.await
+			.context("cleanup root signin failed")?;
+			db
+		}
+	};
 	db.use_ns(namespace).await?;
 	let drop_db = format!("REMOVE DATABASE {};", database);
-	let resp = db.query(drop_db).await?;
+	let resp = sql_query(&db, drop_db).await?;
 	let _ = resp.check();
 	Ok(())
 }
 
+/// `<unix nanos>x<8 hex>`; the suffix mixes the process id, a per-process counter and a
+/// randomly seeded hash so concurrent runs against one server do not collide.
 fn unique_run_id() -> String {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
 	let ts = OffsetDateTime::now_utc().unix_timestamp_nanos();
-	format!("{}", ts)
+	let suffix = RandomState::new().hash_one((
+		ts,
+		std::process::id(),
+		COUNTER.fetch_add(1, Ordering::Relaxed),
+	));
+	format!("{}x{:08x}", ts, suffix as u32)
 }
 
 fn slugify(input: &str) -> String {
@@ -754,16 +1692,585 @@ pub fn build_filter_input(opts: &TestOpts) -> FilterInput {
 		suite_pattern: opts.suite.clone(),
 		case_pattern: opts.case.clone(),
 		tags: opts.tags.clone(),
+		exclude_tags: opts.exclude_tags.clone(),
+		explain: opts.explain,
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::slugify;
+	use super::*;
+	use crate::config::EnvFile;
+	use crate::core::HttpSqlTarget;
+	use crate::tester::cleanup::is_stale_test_namespace;
+
+	async fn mem_root() -> HashMap<String, ActorSession> {
+		let db = surrealdb::engine::any::connect("mem://")
+			.await
+			.expect("in-memory engine");
+		db.use_ns("test")
+			.use_db("test")
+			.await
+			.expect("select ns/db");
+		let root = ActorSession {
+			db,
+			headers: BTreeMap::new(),
+			auth: None,
+			sql_http: HttpSqlTarget::default(),
+		};
+		HashMap::from([("root".to_string(), root)])
+	}
+
+	fn parse_cases(raw: &str) -> Vec<CaseSpec> {
+		toml::from_str::<crate::tester::types::SuiteSpec>(raw)
+			.expect("suite should parse")
+			.cases
+	}
+
+	#[tokio::test]
+	async fn case_hooks_set_up_and_tear_down_around_the_case() {
+		let actors = mem_root().await;
+		let retry = TransientRetry::default();
+		let snapshots = SnapshotContext {
+			dir: std::env::temp_dir(),
+			update: false,
+		};
+		let cases = parse_cases(
+			r#"
+[[cases]]
+name = "reads_temp_record"
+kind = "sql_expect"
+sql = "SELECT value FROM temp:one;"
+before_sql = ["CREATE temp:one SET value = 42;"]
+after_sql = ["DELETE temp:one;"]
+
+[[cases.assertions]]
+path = "0.value"
+equals = 42
+
+[[cases]]
+name = "temp_record_is_gone"
+kind = "sql_expect"
+sql = "SELECT * FROM temp;"
+
+[[cases.assertions]]
+path = "0"
+exists = false
+"#,
+		);
+
+		for case in &cases {
+			let result = run_case_with_before_sql(
+				case,
+				&actors,
+				None,
+				1_000,
+				&retry,
+				&ApiClientConfig::default(),
+				&snapshots,
+			)
+			.await;
+			let report = run_after_sql(case, &actors, &retry, result)
+				.await
+				.expect("case runs");
+			assert!(
+				report.passed,
+				"{}: {:?} {:?}",
+				case.name, report.message, report.assertions
+			);
+			assert_eq!(report.message, None, "{}", case.name);
+		}
+	}
+
+	#[tokio::test]
+	async fn after_sql_runs_when_the_case_times_out_or_errors() {
+		let actors = mem_root().await;
+		let retry = TransientRetry::default();
+		let snapshots = SnapshotContext {
+			dir: std::env::temp_dir(),
+			update: false,
+		};
+		let cases = parse_cases(
+			r#"
+[[cases]]
+name = "slow"
+kind = "sql_expect"
+sql = "SLEEP 2s;"
+before_sql = ["CREATE temp:one;"]
+after_sql = ["DELETE temp:one;"]
+
+[[cases]]
+name = "broken"
+kind = "sql_expect"
+sql = "RETURN 1;"
+before_sql = ["THROW 'setup broke';"]
+after_sql = ["THROW 'teardown broke';"]
+"#,
+		);
+
+		let result = with_case_timeout(
+			50,
+			run_case_with_before_sql(
+				&cases[0],
+				&actors,
+				None,
+				1_000,
+				&retry,
+				&ApiClientConfig::default(),
+				&snapshots,
+			),
+		)
+		.await;
+		let err = run_after_sql(&cases[0], &actors, &retry, result)
+			.await
+			.expect_err("slow case times out");
+		assert_eq!(err.to_string(), "timed out after 50ms");
+		let left = query_json(&actors["root"].db, "SELECT * FROM temp;")
+			.await
+			.expect("select temp");
+		assert_eq!(left, serde_json::json!([]));
+
+		let result = run_case_with_before_sql(
+			&cases[1],
+			&actors,
+			None,
+			1_000,
+			&retry,
+			&ApiClientConfig::default(),
+			&snapshots,
+		)
+		.await;
+		let err = run_after_sql(&cases[1], &actors, &retry, result)
+			.await
+			.expect_err("before_sql errors");
+		let message = format!("{err:#}");
+		assert!(
+			message.contains("setup broke") && message.contains("warning: after_sql failed"),
+			"{message}"
+		);
+	}
+
+	#[test]
+	fn error_codes_match_structured_kinds_by_prefix() {
+		let details = serde_json::json!({
+			"kind": "NotAllowed",
+			"details": { "kind": "Auth", "details": { "kind": "InvalidAuth" } }
+		});
+		let code = error_code_from_details(&details).expect("code");
+		assert_eq!(code, "NotAllowed.Auth.InvalidAuth");
+		assert_eq!(
+			error_code_from_details(&serde_json::json!({ "kind": "Thrown" })).as_deref(),
+			Some("Thrown")
+		);
+
+		assert!(error_code_matches(Some(&code), "", "NotAllowed"));
+		assert!(error_code_matches(Some(&code), "", "notallowed.auth"));
+		assert!(!error_code_matches(Some(&code), "", "NotAll"));
+		assert!(!error_code_matches(Some(&code), "", "AlreadyExists"));
+		assert!(error_code_matches(
+			None,
+			"IAM error: Not enough permissions",
+			"IAM"
+		));
+
+		let err = anyhow!("Not enough permissions");
+		assert_eq!(
+			error_mismatch(&err, Some("permissions"), Some("NotAllowed")),
+			Some("error mismatch, got 'Not enough permissions'".to_string())
+		);
+		assert_eq!(error_mismatch(&err, Some("permissions"), None), None);
+	}
+
+	#[test]
+	fn run_ids_differ_when_generated_back_to_back() {
+		let ids: Vec<String> = (0..64).map(|_| unique_run_id()).collect();
+		let unique: std::collections::BTreeSet<&String> = ids.iter().collect();
+		assert_eq!(unique.len(), ids.len());
+		assert!(ids[0].chars().all(|c| c.is_ascii_alphanumeric()));
+	}
 
 	#[test]
 	fn slugify_is_safe() {
 		assert_eq!(slugify("Hello World"), "hello_world");
 		assert_eq!(slugify("***"), "suite");
 	}
+
+	#[tokio::test]
+	async fn live_events_grace_catches_more_than_the_expected_count() {
+		let event = |action: &str| Ok(serde_json::json!({ "action": action, "data": {} }));
+		let mut stream =
+			futures::stream::iter(vec![event("CREATE"), event("UPDATE"), event("DELETE")])
+				.chain(futures::stream::pending());
+
+		let events = collect_live_events(
+			&mut stream,
+			Some(2),
+			Some(Duration::from_millis(20)),
+			Duration::from_secs(5),
+		)
+		.await
+		.expect("events");
+		assert_eq!(events.len(), 3);
+	}
+
+	#[tokio::test]
+	async fn live_events_stop_at_wanted_count_or_timeout() {
+		let event = |action: &str| Ok(serde_json::json!({ "action": action, "data": {} }));
+		let mut stream = futures::stream::iter(vec![event("CREATE"), event("UPDATE")])
+			.chain(futures::stream::pending());
+
+		let events = collect_live_events(&mut stream, Some(1), None, Duration::from_secs(5))
+			.await
+			.expect("events");
+		assert_eq!(events.len(), 1);
+
+		let events = collect_live_events(&mut stream, None, None, Duration::from_millis(20))
+			.await
+			.expect("events");
+		assert_eq!(
+			events,
+			vec![serde_json::json!({ "action": "UPDATE", "data": {} })]
+		);
+	}
+
+	#[test]
+	fn transaction_statements_are_wrapped_in_one_transaction() {
+		let statements = vec![
+			"CREATE account:a SET balance = 10".to_string(),
+			"CREATE account:a SET balance = 20;".to_string(),
+		];
+		assert_eq!(
+			wrap_in_transaction(&transaction_body(&statements)),
+			"BEGIN TRANSACTION;\nCREATE account:a SET balance = 10;\nCREATE account:a SET balance = 20;\nCOMMIT TRANSACTION;"
+		);
+	}
+
+	#[tokio::test]
+	async fn read_only_suite_runs_against_the_configured_db_without_creating_one() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let root = mem_root().await.remove("root").expect("root actor").db;
+		root.use_ns(cfg.ns())
+			.use_db(cfg.db())
+			.await
+			.expect("select configured ns/db");
+		query_json(&root, "CREATE person:one SET name = 'Ada';")
+			.await
+			.expect("seed");
+
+		let opts = TestOpts {
+			reuse_connections: true,
+			..TestOpts::default()
+		};
+		let ctx = RunnerContext::new(
+			cfg.clone(),
+			opts,
+			GlobalTestConfig::default(),
+			None,
+			1_000,
+			DEFAULT_TEST_PREFIX.to_string(),
+		);
+		ctx.sessions.as_ref().expect("session cache").insert_root(
+			cfg.host(),
+			root.clone(),
+			String::new(),
+		);
+		let suite = LoadedSuite {
+			path: PathBuf::from("database/tests/suites/smoke.toml"),
+			spec: toml::from_str(
+				r#"
+name = "smoke"
+use_existing_db = true
+
+[[cases]]
+name = "people_are_seeded"
+kind = "sql_expect"
+sql = "SELECT name FROM person;"
+
+[[cases.assertions]]
+path = "0.name"
+equals = "Ada"
+"#,
+			)
+			.expect("suite should parse"),
+		};
+
+		let report = ctx.run(vec![suite]).await.expect("run");
+		assert_eq!(report.cases_passed, 1, "{:?}", report.suites[0].cases);
+		assert_eq!(report.suites[0].namespace, cfg.ns());
+		assert_eq!(report.suites[0].database, cfg.db());
+
+		let marker = test_db_marker(DEFAULT_TEST_PREFIX);
+		let root_info = query_json(&root, "INFO FOR ROOT;")
+			.await
+			.expect("root info");
+		let namespaces = root_info["namespaces"].as_object().expect("namespaces");
+		assert!(
+			namespaces.keys().all(|ns| !ns.contains(&marker)),
+			"{namespaces:?}"
+		);
+		let ns_info = query_json(&root, "INFO FOR NS;").await.expect("ns info");
+		let databases: Vec<&String> = ns_info["databases"]
+			.as_object()
+			.expect("databases")
+			.keys()
+			.collect();
+		assert_eq!(databases, vec![cfg.db()]);
+	}
+
+	#[test]
+	fn configured_prefix_names_ephemeral_dbs_and_is_matched_by_cleanup() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let suite = LoadedSuite {
+			path: PathBuf::from("database/tests/suites/smoke.toml"),
+			spec: toml::from_str("name = \"smoke\"").expect("suite should parse"),
+		};
+
+		let target = suite_target(&cfg, "123", "ci_tmp", false, &suite, "smoke");
+		assert!(target.namespace.contains("_ci_tmp_123_"));
+		assert!(target.database.contains("_ci_tmp_123_"));
+		assert!(is_stale_test_namespace(
+			&target.namespace,
+			&test_db_marker("ci_tmp"),
+			None
+		));
+		assert!(!is_stale_test_namespace(
+			&target.namespace,
+			&test_db_marker(DEFAULT_TEST_PREFIX),
+			None
+		));
+	}
+
+	#[tokio::test]
+	async fn case_timeout_fails_slow_cases() {
+		let err = with_case_timeout(10, async {
+			tokio::time::sleep(Duration::from_secs(5)).await;
+			bail!("case should not finish")
+		})
+		.await
+		.expect_err("slow case should time out");
+		assert_eq!(err.to_string(), "timed out after 10ms");
+
+		let fast = with_case_timeout(1_000, async {
+			Ok(CaseReport {
+				name: "fast".into(),
+				kind: "sql_expect".into(),
+				duration_ms: 0,
+				passed: true,
+				message: None,
+				assertions: Vec::new(),
+				attempt: 1,
+				retry_count: 0,
+				latency_ms: None,
+			})
+		})
+		.await
+		.expect("fast case should finish");
+		assert!(fast.passed);
+	}
+
+	#[test]
+	fn after_sql_warnings_do_not_fail_the_case() {
+		let mut report = CaseReport {
+			name: "cleanup".into(),
+			kind: "sql_expect".into(),
+			duration_ms: 0,
+			passed: true,
+			message: None,
+			assertions: Vec::new(),
+			attempt: 1,
+			retry_count: 0,
+			latency_ms: None,
+		};
+		append_warning(&mut report, "after_sql failed: DELETE temp;");
+		assert!(report.passed);
+		assert_eq!(
+			report.message.as_deref(),
+			Some("warning: after_sql failed: DELETE temp;")
+		);
+
+		report.message = Some("expected error".into());
+		append_warning(&mut report, "after_sql failed: DELETE temp;");
+		assert_eq!(
+			report.message.as_deref(),
+			Some("expected error; warning: after_sql failed: DELETE temp;")
+		);
+	}
+
+	#[test]
+	fn bail_trips_once_the_shared_failure_count_is_reached() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let opts = TestOpts {
+			bail: Some(2),
+			..TestOpts::default()
+		};
+		let ctx = RunnerContext::new(
+			cfg,
+			opts,
+			GlobalTestConfig::default(),
+			None,
+			1_000,
+			DEFAULT_TEST_PREFIX.to_string(),
+		);
+		let task = ctx.clone_for_task();
+
+		assert!(!ctx.bailed());
+		assert!(!task.record_failure());
+		assert!(ctx.record_failure());
+		assert!(task.bailed());
+	}
+
+	#[test]
+	fn list_prints_targets_and_cases_without_connecting() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let ctx = RunnerContext::new(
+			cfg.clone(),
+			TestOpts::default(),
+			GlobalTestConfig::default(),
+			None,
+			1_000,
+			DEFAULT_TEST_PREFIX.to_string(),
+		);
+		let suite = LoadedSuite {
+			path: PathBuf::from("database/tests/suites/smoke.toml"),
+			spec: toml::from_str(
+				r#"
+name = "smoke"
+use_existing_db = true
+
+[[cases]]
+name = "people_are_seeded"
+kind = "sql_expect"
+sql = "SELECT * FROM person LIMIT 1;"
+
+[[cases]]
+name = "later"
+kind = "sql_expect"
+sql = "RETURN 1;"
+skip = true
+"#,
+			)
+			.expect("suite should parse"),
+		};
+
+		let mut out = Vec::new();
+		ctx.write_list(&mut out, &[suite]).expect("write list");
+		assert_eq!(
+			String::from_utf8(out).expect("utf8"),
+			format!(
+				"smoke (database/tests/suites/smoke.toml)\n  target: {}/{} (existing)\n  - people_are_seeded (sql_expect)\n  - later (sql_expect) [skip]\n",
+				cfg.ns(),
+				cfg.db()
+			)
+		);
+	}
+
+	#[test]
+	fn suite_budget_sums_api_latencies() {
+		let api_case = |name: &str, latency_ms: u128| CaseReport {
+			name: name.to_string(),
+			kind: "api_request".to_string(),
+			duration_ms: latency_ms + 5,
+			passed: true,
+			message: None,
+			assertions: Vec::new(),
+			attempt: 1,
+			retry_count: 0,
+			latency_ms: Some(latency_ms),
+		};
+		let mut cases = vec![api_case("list", 40), api_case("get", 30)];
+
+		let report = suite_budget_report(&cases, 100);
+		assert!(report.passed, "{}", report.message());
+		assert_eq!(report.total_ms, 70);
+
+		cases.push(api_case("search", 90));
+		let report = suite_budget_report(&cases, 100);
+		assert!(!report.passed);
+		assert_eq!(
+			report.message(),
+			"API responses took 160ms in total, budget 100ms"
+		);
+	}
+
+	#[test]
+	fn global_retry_sets_a_floor_and_retries_failures() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let opts = TestOpts {
+			global_retry: 2,
+			..TestOpts::default()
+		};
+		let ctx = RunnerContext::new(
+			cfg,
+			opts,
+			GlobalTestConfig::default(),
+			None,
+			1_000,
+			DEFAULT_TEST_PREFIX.to_string(),
+		);
+		let suite: crate::tester::types::SuiteSpec = toml::from_str(
+			r#"
+[[cases]]
+name = "default"
+kind = "sql_expect"
+sql = "RETURN 1;"
+
+[[cases]]
+name = "flaky"
+kind = "sql_expect"
+sql = "RETURN 1;"
+retries = 4
+"#,
+		)
+		.expect("suite should parse");
+
+		let default = ctx.case_retry(&suite.cases[0]);
+		assert_eq!(default.retries, 2);
+		assert_eq!(default.retry_on, RetryOn::Failure);
+		assert_eq!(ctx.case_retry(&suite.cases[1]).retries, 4);
+	}
+
+	#[test]
+	fn retry_on_defaults_to_errors_and_inherits_from_defaults() {
+		let cfg = DbCfg::from_env(&EnvFile::default()).expect("default config");
+		let global: GlobalTestConfig = toml::from_str(
+			r#"
+[defaults]
+retries = 1
+retry_on = "failure"
+"#,
+		)
+		.expect("global config should parse");
+		let ctx = RunnerContext::new(
+			cfg,
+			TestOpts::default(),
+			global,
+			None,
+			1_000,
+			DEFAULT_TEST_PREFIX.to_string(),
+		);
+		let suite: crate::tester::types::SuiteSpec = toml::from_str(
+			r#"
+[[cases]]
+name = "inherits"
+kind = "sql_expect"
+sql = "RETURN 1;"
+
+[[cases]]
+name = "errors_only"
+kind = "sql_expect"
+sql = "RETURN 1;"
+retries = 3
+retry_on = "error"
+"#,
+		)
+		.expect("suite should parse");
+
+		let inherited = ctx.case_retry(&suite.cases[0]);
+		assert_eq!(inherited.retries, 1);
+		assert_eq!(inherited.retry_on, RetryOn::Failure);
+		let errors_only = ctx.case_retry(&suite.cases[1]);
+		assert_eq!(errors_only.retries, 3);
+		assert_eq!(errors_only.retry_on, RetryOn::Error);
+		assert_eq!(CaseRetry::default().retry_on, RetryOn::Error);
+	}
 }
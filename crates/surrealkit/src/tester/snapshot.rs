@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+
+use super::types::AssertionReport;
+
+/// Differences listed in a snapshot mismatch message before the rest are counted.
+const MAX_LISTED_DIFFS: usize = 5;
+
+/// Where `snapshot` files of a suite live, and whether to rewrite them.
+#[derive(Debug, Clone)]
+pub struct SnapshotContext {
+	pub dir: PathBuf,
+	pub update: bool,
+}
+
+impl SnapshotContext {
+	pub fn for_suite(suite_path: &Path, update: bool) -> Self {
+		Self {
+			dir: suite_path
+				.parent()
+				.unwrap_or_else(|| Path::new("database/tests/suites"))
+				.to_path_buf(),
+			update,
+		}
+	}
+}
+
+/// Compares `actual` with the golden file `file`. A mismatch writes the actual value to
+/// `<name>.actual.json` next to it; with `update`, the golden file is rewritten instead.
+pub fn check_snapshot(
+	ctx: &SnapshotContext,
+	file: &str,
+	actual: &Value,
+) -> Result<AssertionReport> {
+	let path = ctx.dir.join(file);
+	let actual_path = path.with_extension("actual.json");
+	let actual = normalize(actual);
+	let rendered = format!("{}\n", serde_json::to_string_pretty(&actual)?);
+	let report = |passed: bool, message: String| AssertionReport {
+		name: "snapshot".to_string(),
+		passed,
+		message,
+	};
+
+	if ctx.update {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent).with_context(|| format!("creating {:?}", parent))?;
+		}
+		fs::write(&path, rendered).with_context(|| format!("writing {:?}", path))?;
+		let _ = fs::remove_file(&actual_path);
+		return Ok(report(true, format!("snapshot {} updated", path.display())));
+	}
+
+	let expected = match fs::read_to_string(&path) {
+		Ok(raw) => serde_json::from_str::<Value>(&raw)
+			.with_context(|| format!("parsing snapshot {:?}", path))?,
+		Err(_) => {
+			fs::write(&actual_path, rendered)
+				.with_context(|| format!("writing {:?}", actual_path))?;
+			return Ok(report(
+				false,
+				format!(
+					"snapshot {} does not exist; actual result written to {} (run with --update-snapshots to accept it)",
+					path.display(),
+					actual_path.display()
+				),
+			));
+		}
+	};
+
+	if expected == actual {
+		let _ = fs::remove_file(&actual_path);
+		return Ok(report(true, format!("matches {}", path.display())));
+	}
+	fs::write(&actual_path, rendered).with_context(|| format!("writing {:?}", actual_path))?;
+	let mut diffs = Vec::new();
+	diff_values(&expected, &actual, "", &mut diffs);
+	let mut summary = diffs
+		.iter()
+		.take(MAX_LISTED_DIFFS)
+		.cloned()
+		.collect::<Vec<_>>()
+		.join("; ");
+	if diffs.len() > MAX_LISTED_DIFFS {
+		summary.push_str(&format!("; and {} more", diffs.len() - MAX_LISTED_DIFFS));
+	}
+	Ok(report(
+		false,
+		format!(
+			"result differs from {} ({}); actual result written to {}",
+			path.display(),
+			summary,
+			actual_path.display()
+		),
+	))
+}
+
+/// Sorts object keys so snapshots are stable regardless of the server's field order.
+fn normalize(value: &Value) -> Value {
+	match value {
+		Value::Object(map) => {
+			let mut keys: Vec<_> = map.keys().collect();
+			keys.sort();
+			Value::Object(
+				keys.into_iter()
+					.map(|key| (key.clone(), normalize(&map[key])))
+					.collect::<Map<_, _>>(),
+			)
+		}
+		Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+		other => other.clone(),
+	}
+}
+
+/// Collects `path: expected X, got Y` lines for every leaf where the values differ.
+fn diff_values(expected: &Value, actual: &Value, path: &str, out: &mut Vec<String>) {
+	let label = if path.is_empty() { "(root)" } else { path };
+	let child = |key: &str| {
+		if path.is_empty() {
+			key.to_string()
+		} else {
+			format!("{path}.{key}")
+		}
+	};
+	match (expected, actual) {
+		(Value::Object(left), Value::Object(right)) => {
+			for (key, value) in left {
+				match right.get(key) {
+					Some(other) => diff_values(value, other, &child(key), out),
+					None => out.push(format!("{}: missing", child(key))),
+				}
+			}
+			for key in right.keys().filter(|key| !left.contains_key(*key)) {
+				out.push(format!("{}: unexpected", child(key)));
+			}
+		}
+		(Value::Array(left), Value::Array(right)) => {
+			for (idx, (a, b)) in left.iter().zip(right).enumerate() {
+				diff_values(a, b, &child(&idx.to_string()), out);
+			}
+			if left.len() != right.len() {
+				out.push(format!(
+					"{label}: expected {} items, got {}",
+					left.len(),
+					right.len()
+				));
+			}
+		}
+		_ if expected != actual => out.push(format!("{label}: expected {expected}, got {actual}")),
+		_ => {}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mismatches_write_actual_file_and_update_rewrites_golden() {
+		let tmp = tempfile::tempdir().expect("create temp dir");
+		let dir = tmp.path();
+		let golden = dir.join("people.json");
+		fs::write(&golden, r#"[{ "name": "Ada", "age": 36 }]"#).expect("write golden");
+		let ctx = SnapshotContext {
+			dir: dir.to_path_buf(),
+			update: false,
+		};
+
+		let same = serde_json::json!([{ "age": 36, "name": "Ada" }]);
+		assert!(
+			check_snapshot(&ctx, "people.json", &same)
+				.expect("check")
+				.passed
+		);
+
+		let changed = serde_json::json!([{ "name": "Ada", "age": 37 }, { "name": "Bob" }]);
+		let report = check_snapshot(&ctx, "people.json", &changed).expect("check");
+		assert!(!report.passed);
+		assert!(
+			report.message.contains("0.age: expected 36, got 37")
+				&& report.message.contains("(root): expected 1 items, got 2"),
+			"{}",
+			report.message
+		);
+		let actual: Value = serde_json::from_str(
+			&fs::read_to_string(dir.join("people.actual.json")).expect("actual file"),
+		)
+		.expect("actual json");
+		assert_eq!(actual, changed);
+
+		let update = SnapshotContext {
+			update: true,
+			..ctx
+		};
+		assert!(
+			check_snapshot(&update, "people.json", &changed)
+				.expect("update")
+				.passed
+		);
+		assert!(!dir.join("people.actual.json").exists());
+		let check = SnapshotContext {
+			update: false,
+			..update
+		};
+		assert!(
+			check_snapshot(&check, "people.json", &changed)
+				.expect("check")
+				.passed
+		);
+	}
+}
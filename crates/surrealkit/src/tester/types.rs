@@ -3,20 +3,81 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TestOpts {
 	pub suite: Option<String>,
 	pub case: Option<String>,
 	pub tags: Vec<String>,
+	pub exclude_tags: Vec<String>,
+	/// Prints why each suite and case was selected or filtered out.
+	pub explain: bool,
+	/// Prints the selected suites and cases instead of running them.
+	pub list: bool,
+	pub changed_since: Option<String>,
 	pub fail_fast: bool,
+	/// Stops the whole run once this many cases have failed.
+	pub bail: Option<usize>,
 	pub parallel: usize,
 	pub json_out: Option<PathBuf>,
+	pub json_compact: bool,
+	pub junit_out: Option<PathBuf>,
+	/// Prometheus textfile written after the run.
+	pub metrics_out: Option<PathBuf>,
+	pub output: OutputFormat,
+	pub tap_out: Option<PathBuf>,
 	pub no_setup: bool,
 	pub no_sync: bool,
 	pub no_seed: bool,
+	pub only_setup: bool,
+	pub only_sync: bool,
+	pub only_seed: bool,
 	pub base_url: Option<String>,
 	pub timeout_ms: Option<u64>,
 	pub keep_db: bool,
+	pub no_ephemeral: bool,
+	pub retry_transient: bool,
+	/// Minimum `retries` for every case, which then also re-runs failed results.
+	pub global_retry: u32,
+	/// Keeps the root connection, and actor sessions for identical specs, across suites.
+	pub reuse_connections: bool,
+	/// Rewrites `snapshot` files with the actual results.
+	pub update_snapshots: bool,
+}
+
+impl TestOpts {
+	/// Preparation steps to run: exactly the `--only-*` ones when any is set, otherwise
+	/// everything not turned off with `--no-*`.
+	pub fn prep_steps(&self) -> PrepSteps {
+		if self.only_setup || self.only_sync || self.only_seed {
+			return PrepSteps {
+				setup: self.only_setup,
+				sync: self.only_sync,
+				seed: self.only_seed,
+			};
+		}
+		PrepSteps {
+			setup: !self.no_setup,
+			sync: !self.no_sync,
+			seed: !self.no_seed,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrepSteps {
+	pub setup: bool,
+	pub sync: bool,
+	pub seed: bool,
+}
+
+/// Format of the report printed to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+	#[default]
+	Human,
+	Tap,
+	/// Human report plus GitHub Actions `::error` annotations.
+	Github,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -25,6 +86,8 @@ pub struct GlobalTestConfig {
 	#[serde(default)]
 	pub defaults: GlobalDefaults,
 	#[serde(default)]
+	pub api: ApiClientConfig,
+	#[serde(default)]
 	pub actors: BTreeMap<String, ActorSpec>,
 	#[serde(default)]
 	pub fixtures: Vec<FixtureSpec>,
@@ -35,22 +98,96 @@ pub struct GlobalTestConfig {
 pub struct GlobalDefaults {
 	pub base_url: Option<String>,
 	pub timeout_ms: Option<u64>,
+	#[serde(default)]
+	pub transient_errors: Vec<String>,
+	/// Prefix for ephemeral namespaces/databases; defaults to `sk_test`.
+	pub db_prefix: Option<String>,
+	/// Default for cases that do not set `retries`.
+	pub retries: Option<u32>,
+	pub retry_delay_ms: Option<u64>,
+	pub retry_on: Option<RetryOn>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ApiClientConfig {
+	pub connect_timeout_ms: Option<u64>,
+	pub tcp_keepalive_ms: Option<u64>,
+	pub user_agent: Option<String>,
+	/// Skips TLS certificate validation. Only for self-signed staging servers.
+	#[serde(default)]
+	pub danger_accept_invalid_certs: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SuiteSpec {
 	pub name: Option<String>,
+	pub base_url: Option<String>,
+	/// Runs against the configured ns/db instead of a fresh one; no setup, sync, seed or cleanup.
+	#[serde(default)]
+	pub use_existing_db: bool,
+	/// Reports every case as skipped without provisioning a database.
+	#[serde(default)]
+	pub skip: bool,
+	/// When any suite sets `only`, the other suites are left out.
+	#[serde(default)]
+	pub only: bool,
 	#[serde(default)]
 	pub tags: Vec<String>,
 	#[serde(default)]
 	pub actors: BTreeMap<String, ActorSpec>,
 	#[serde(default)]
 	pub fixtures: Vec<FixtureSpec>,
+	/// Hook SQL run as root. A failing `before_all` fails every case, a failing
+	/// `before_each` fails its case; `after_*` failures are only logged.
+	#[serde(default, alias = "setup_sql")]
+	pub before_all: Vec<String>,
+	#[serde(default)]
+	pub before_each: Vec<String>,
+	#[serde(default)]
+	pub after_each: Vec<String>,
+	#[serde(default, alias = "teardown_sql")]
+	pub after_all: Vec<String>,
+	/// Fails the suite when its API and GraphQL response times add up to more than this.
+	pub max_total_ms: Option<u64>,
 	#[serde(default)]
 	pub cases: Vec<CaseSpec>,
 }
 
+impl SuiteSpec {
+	/// A minimal JSON suite, for documentation and generators.
+	#[allow(dead_code)]
+	pub fn example_json() -> String {
+		r#"{
+  "name": "person",
+  "tags": ["smoke"],
+  "cases": [
+    {
+      "name": "root_can_select_person",
+      "kind": "sql_expect",
+      "actor": "root",
+      "sql": "SELECT * FROM person;",
+      "allow": true
+    }
+  ]
+}
+"#
+		.to_string()
+	}
+}
+
+/// Shared settings from a suite directory's `_collection.toml`.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CollectionConfig {
+	pub base_url: Option<String>,
+	#[serde(default)]
+	pub actors: BTreeMap<String, ActorSpec>,
+	#[serde(default)]
+	pub fixtures: Vec<FixtureSpec>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct FixtureSpec {
@@ -64,6 +201,9 @@ pub struct FixtureSpec {
 #[serde(deny_unknown_fields)]
 pub struct ActorSpec {
 	pub kind: ActorKind,
+	/// Connects this actor to another node instead of the configured host.
+	pub host: Option<String>,
+	pub host_env: Option<String>,
 	pub username: Option<String>,
 	pub username_env: Option<String>,
 	pub password: Option<String>,
@@ -79,6 +219,8 @@ pub struct ActorSpec {
 	pub params: Option<serde_json::Value>,
 	pub token: Option<String>,
 	pub token_env: Option<String>,
+	/// Header that carries an `api_key` actor's key; defaults to `x-api-key`.
+	pub api_key_header: Option<String>,
 	#[serde(default)]
 	pub headers: BTreeMap<String, String>,
 }
@@ -92,6 +234,10 @@ pub enum ActorKind {
 	Record,
 	Token,
 	Headers,
+	/// Connects without signing in, for public endpoints and guest permissions.
+	Anonymous,
+	/// Sends `token`/`token_env` as a static key in `api_key_header` instead of signing in.
+	ApiKey,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -99,28 +245,86 @@ pub struct CaseSpec {
 	pub name: String,
 	#[serde(default)]
 	pub tags: Vec<String>,
+	/// Run by the case's actor before the case.
+	#[serde(default)]
+	pub before_sql: Vec<String>,
+	/// Run by the case's actor after the case, even when it fails. Failures here are
+	/// reported as a warning in the case message and do not fail the case.
+	#[serde(default)]
+	pub after_sql: Vec<String>,
+	/// Reported as skipped instead of run.
+	#[serde(default)]
+	pub skip: bool,
+	/// When any case sets `only`, the other cases are left out.
+	#[serde(default)]
+	pub only: bool,
+	/// Deadline for the whole case, hooks included; defaults to the run's `timeout_ms`.
+	/// Named apart from the `timeout_ms` of `api_request`/`live_query`, which it wraps.
+	pub case_timeout_ms: Option<u64>,
+	/// Extra attempts when the case errors, or also when it fails with `retry_on = "failure"`.
+	pub retries: Option<u32>,
+	pub retry_delay_ms: Option<u64>,
+	pub retry_on: Option<RetryOn>,
 	#[serde(flatten)]
 	pub kind: CaseKind,
 }
 
+/// What makes `retries` re-run a case.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+	/// Errors only, such as a dropped connection.
+	#[default]
+	Error,
+	/// Errors and failed results, such as a flaky assertion.
+	Failure,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum CaseKind {
 	SqlExpect(SqlExpectCase),
+	BulkSqlExpect(BulkSqlExpectCase),
 	PermissionsMatrix(PermissionsMatrixCase),
 	SchemaMetadata(SchemaMetadataCase),
 	SchemaBehavior(SchemaBehaviorCase),
 	ApiRequest(ApiRequestCase),
+	Transaction(TransactionCase),
+	LiveQuery(LiveQueryCase),
+	#[serde(rename = "graphql_request")]
+	GraphQLRequest(GraphQLRequestCase),
 }
 
 impl CaseKind {
 	pub fn label(&self) -> &'static str {
 		match self {
 			Self::SqlExpect(_) => "sql_expect",
+			Self::BulkSqlExpect(_) => "bulk_sql_expect",
 			Self::PermissionsMatrix(_) => "permissions_matrix",
 			Self::SchemaMetadata(_) => "schema_metadata",
 			Self::SchemaBehavior(_) => "schema_behavior",
 			Self::ApiRequest(_) => "api_request",
+			Self::Transaction(_) => "transaction",
+			Self::LiveQuery(_) => "live_query",
+			Self::GraphQLRequest(_) => "graphql_request",
+		}
+	}
+
+	pub fn actor(&self) -> Option<&str> {
+		match self {
+			Self::SqlExpect(spec) => spec.actor.as_deref(),
+			Self::BulkSqlExpect(spec) => spec.actor.as_deref(),
+			// Multi-actor matrices run their before/after SQL as root.
+			Self::PermissionsMatrix(spec) => spec
+				.actor
+				.as_deref()
+				.filter(|name| *name != ALL_ACTORS && spec.actors.is_empty()),
+			Self::SchemaMetadata(spec) => spec.actor.as_deref(),
+			Self::SchemaBehavior(spec) => spec.actor.as_deref(),
+			Self::ApiRequest(spec) => spec.actor.as_deref(),
+			Self::Transaction(spec) => spec.actor.as_deref(),
+			Self::LiveQuery(spec) => spec.actor.as_deref(),
+			Self::GraphQLRequest(spec) => spec.actor.as_deref(),
 		}
 	}
 }
@@ -133,15 +337,60 @@ pub struct SqlExpectCase {
 	#[serde(default = "default_true")]
 	pub allow: bool,
 	pub error_contains: Option<String>,
+	/// Structured SurrealDB error kind such as `NotAllowed` or `AlreadyExists.Record`.
+	pub error_code: Option<String>,
+	/// Zero-based statement expected to fail; requires `allow = false`.
+	pub error_at_index: Option<usize>,
+	/// Table every returned record's `id` must belong to. The ids are not kept for
+	/// later cases.
+	pub created_in_table: Option<String>,
+	#[serde(default)]
+	pub assertions: Vec<JsonAssertionSpec>,
+	/// Checked against the response headers of an `http(s)://` host.
+	#[serde(default)]
+	pub header_assertions: Vec<HeaderAssertionSpec>,
+	/// Golden JSON file, relative to the suite, that the whole result must match.
+	pub snapshot: Option<String>,
+	/// Paths replaced with a placeholder before `assertions` and `snapshot` run.
+	#[serde(default)]
+	pub redact: Vec<String>,
+}
+
+/// Runs `steps` in order as one actor, checking each like a `sql_expect` case.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BulkSqlExpectCase {
+	pub actor: Option<String>,
+	pub steps: Vec<BulkStep>,
+	/// Stop at the first step that does not behave as expected.
+	#[serde(default)]
+	pub fail_fast_on_step: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BulkStep {
+	pub name: String,
+	pub sql: String,
+	#[serde(default = "default_true")]
+	pub allow: bool,
+	pub error_contains: Option<String>,
 	pub error_code: Option<String>,
 	#[serde(default)]
 	pub assertions: Vec<JsonAssertionSpec>,
 }
 
+/// `actor` value that runs a `permissions_matrix` case as every actor of the suite.
+pub const ALL_ACTORS: &str = "all";
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct PermissionsMatrixCase {
+	/// One actor, or `"all"` to run the rules as every actor of the suite.
 	pub actor: Option<String>,
+	/// Runs the rules as each of these actors instead of a single `actor`.
+	#[serde(default)]
+	pub actors: Vec<String>,
 	pub table: String,
 	pub record_id: Option<String>,
 	#[serde(default)]
@@ -176,10 +425,20 @@ pub struct SchemaMetadataCase {
 	pub sql: Option<String>,
 	#[serde(default)]
 	pub contains: Vec<String>,
+	pub field_comment: Option<FieldCommentSpec>,
 	#[serde(default)]
 	pub assertions: Vec<JsonAssertionSpec>,
 }
 
+/// Checks the `COMMENT` of field `name` in `INFO FOR TABLE` output.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCommentSpec {
+	pub name: String,
+	pub equals: Option<String>,
+	pub contains: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SchemaBehaviorCase {
@@ -206,32 +465,163 @@ pub struct ApiRequestCase {
 	#[serde(default)]
 	pub headers: BTreeMap<String, String>,
 	pub body: Option<serde_json::Value>,
+	/// JSON file holding the request body, relative to the suite file; loaded into `body`.
+	pub body_file: Option<String>,
 	pub timeout_ms: Option<u64>,
+	/// Fails the case when the response takes longer than this to arrive.
+	pub max_duration_ms: Option<u64>,
 	#[serde(default)]
 	pub body_assertions: Vec<JsonAssertionSpec>,
 	#[serde(default)]
 	pub header_assertions: Vec<HeaderAssertionSpec>,
 }
 
+/// POSTs `query` to `<base_url>/graphql`; `data_assertions` and `error_assertions` are
+/// checked against the response's `data` and `errors`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraphQLRequestCase {
+	pub actor: Option<String>,
+	pub query: String,
+	pub variables: Option<serde_json::Value>,
+	pub operation_name: Option<String>,
+	#[serde(default = "default_ok_status")]
+	pub expected_status: u16,
+	/// Extra request headers, e.g. `surreal-ns`/`surreal-db`.
+	#[serde(default)]
+	pub headers: BTreeMap<String, String>,
+	pub timeout_ms: Option<u64>,
+	#[serde(default)]
+	pub data_assertions: Vec<JsonAssertionSpec>,
+	#[serde(default)]
+	pub error_assertions: Vec<JsonAssertionSpec>,
+	/// Fails the case when the response has a non-empty `errors` array.
+	#[serde(default)]
+	pub expect_no_errors: bool,
+}
+
+/// Runs `statements` in one transaction; `verify_sql` runs afterwards, committed or not.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
+pub struct TransactionCase {
+	pub actor: Option<String>,
+	pub statements: Vec<String>,
+	#[serde(default = "default_true")]
+	pub expect_commit: bool,
+	pub error_contains: Option<String>,
+	pub verify_sql: Option<String>,
+	#[serde(default)]
+	pub assertions: Vec<JsonAssertionSpec>,
+}
+
+/// Subscribes with `live_sql`, runs `statements`, then asserts on the captured events,
+/// each shaped as `{ action, data }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LiveQueryCase {
+	pub actor: Option<String>,
+	pub live_sql: String,
+	#[serde(default)]
+	pub statements: Vec<String>,
+	/// Single trigger statement, run after `statements`.
+	pub trigger_sql: Option<String>,
+	/// Expected actions in order (`CREATE`, `UPDATE`, `DELETE`).
+	#[serde(default)]
+	pub expect_actions: Vec<String>,
+	/// Require exactly this many events; collection stops after this many (defaulting
+	/// to `expect_actions.len()`) plus a short wait for unexpected extra events.
+	#[serde(alias = "expected_notifications")]
+	pub expect_count: Option<usize>,
+	pub timeout_ms: Option<u64>,
+	/// Checked against the first notification, `{ action, data }`.
+	#[serde(default)]
+	pub assertions: Vec<JsonAssertionSpec>,
+	/// Checked against the array of all notifications.
+	#[serde(default)]
+	pub events_assertions: Vec<JsonAssertionSpec>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct JsonAssertionSpec {
 	pub path: String,
 	pub exists: Option<bool>,
 	pub equals: Option<serde_json::Value>,
+	/// Values the path may equal; passes when it equals one of them.
+	pub any_of: Option<Vec<serde_json::Value>>,
+	/// Values the path must not equal.
+	pub none_of: Option<Vec<serde_json::Value>>,
+	/// JSON type of the value: `string`, `number`, `boolean`, `null`, `array` or `object`.
+	pub type_equals: Option<String>,
 	pub equals_auth: Option<String>,
 	pub contains: Option<String>,
 	pub regex: Option<String>,
+	/// Inline flags for `regex`: `i` (case-insensitive), `m` (multiline), `s` (dot-all).
+	pub regex_flags: Option<String>,
+	pub approx: Option<ApproxSpec>,
+	pub gt: Option<serde_json::Number>,
+	pub gte: Option<serde_json::Number>,
+	pub lt: Option<serde_json::Number>,
+	pub lte: Option<serde_json::Number>,
+	/// Exact number of elements in an array or object, or of characters (not bytes) in a
+	/// string. Other values fail.
+	pub length: Option<usize>,
+	/// An element the array must contain.
+	pub includes: Option<serde_json::Value>,
+	/// A value every element of the array must equal.
+	pub all_match: Option<serde_json::Value>,
+	/// Passes when some element of the array satisfies all of these, with paths relative
+	/// to the element.
+	pub contains_item: Option<Vec<JsonAssertionSpec>>,
+	/// Compares two paths relative to the value at `path`.
+	pub compare: Option<CompareSpec>,
+	/// Inverts the `equals`, `contains` and `regex` checks.
+	#[serde(default)]
+	pub negate: bool,
+	/// How a `[*]` path combines its per-element results; defaults to `all`.
+	#[serde(default)]
+	pub wildcard: WildcardMode,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WildcardMode {
+	#[default]
+	All,
+	Any,
+}
+
+/// `left_path <op> right_path`, numerically for numbers and lexically for strings. `op`
+/// is one of `==`, `!=`, `<`, `<=`, `>`, `>=`.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
+pub struct CompareSpec {
+	pub left_path: String,
+	pub op: String,
+	pub right_path: String,
+}
+
+/// Numeric match within `epsilon`, treating integers and floats alike.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApproxSpec {
+	pub value: f64,
+	pub epsilon: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HeaderAssertionSpec {
 	pub name: String,
 	pub exists: Option<bool>,
 	pub equals: Option<String>,
 	pub contains: Option<String>,
 	pub regex: Option<String>,
+	/// Inline flags for `regex`: `i` (case-insensitive), `m` (multiline), `s` (dot-all).
+	pub regex_flags: Option<String>,
+	/// Inverts the `equals`, `contains` and `regex` checks.
+	#[serde(default)]
+	pub negate: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -256,6 +646,9 @@ pub struct RunReport {
 	pub cases_total: usize,
 	pub cases_passed: usize,
 	pub cases_failed: usize,
+	pub cases_skipped: usize,
+	/// The run stopped early because `--bail` was reached.
+	pub bailed: bool,
 	pub suites: Vec<SuiteReport>,
 }
 
@@ -266,10 +659,39 @@ pub struct SuiteReport {
 	pub namespace: String,
 	pub database: String,
 	pub duration_ms: u128,
+	/// Time spent in `before_all` / `after_all`.
+	pub setup_duration_ms: u128,
+	pub teardown_duration_ms: u128,
 	pub cases_total: usize,
 	pub cases_passed: usize,
 	pub cases_failed: usize,
+	pub cases_skipped: usize,
 	pub cases: Vec<CaseReport>,
+	/// The suite's `max_total_ms` check, when it sets one.
+	pub budget: Option<BudgetReport>,
+}
+
+impl SuiteReport {
+	pub fn failed(&self) -> bool {
+		self.cases_failed > 0 || self.budget.as_ref().is_some_and(|budget| !budget.passed)
+	}
+}
+
+/// Summed API and GraphQL response times of a suite against its `max_total_ms`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetReport {
+	pub total_ms: u128,
+	pub max_total_ms: u64,
+	pub passed: bool,
+}
+
+impl BudgetReport {
+	pub fn message(&self) -> String {
+		format!(
+			"API responses took {}ms in total, budget {}ms",
+			self.total_ms, self.max_total_ms
+		)
+	}
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -280,6 +702,32 @@ pub struct CaseReport {
 	pub passed: bool,
 	pub message: Option<String>,
 	pub assertions: Vec<AssertionReport>,
+	/// Attempt that produced this result, starting at 1.
+	pub attempt: u32,
+	/// Retries the case was allowed.
+	pub retry_count: u32,
+	/// Response time of an `api_request` or `graphql_request` case.
+	pub latency_ms: Option<u128>,
+}
+
+impl CaseReport {
+	pub fn skipped(case: &CaseSpec) -> Self {
+		Self {
+			name: case.name.clone(),
+			kind: case.kind.label().to_string(),
+			duration_ms: 0,
+			passed: true,
+			message: Some("skipped".to_string()),
+			assertions: Vec::new(),
+			attempt: 1,
+			retry_count: 0,
+			latency_ms: None,
+		}
+	}
+
+	pub fn is_skipped(&self) -> bool {
+		self.passed && self.message.as_deref() == Some("skipped")
+	}
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -294,19 +742,26 @@ pub struct FilterInput {
 	pub suite_pattern: Option<String>,
 	pub case_pattern: Option<String>,
 	pub tags: Vec<String>,
+	pub exclude_tags: Vec<String>,
+	/// Print why each suite and case was kept or dropped.
+	pub explain: bool,
 }
 
 pub fn default_true() -> bool {
 	true
 }
 
+fn default_ok_status() -> u16 {
+	200
+}
+
 fn default_get() -> String {
 	"GET".to_string()
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{CaseKind, SuiteSpec};
+	use super::{CaseKind, PrepSteps, SuiteSpec, TestOpts};
 
 	#[test]
 	fn parses_case_kind_inside_cases_array() {
@@ -327,4 +782,131 @@ error_contains = "permission"
 		assert_eq!(suite.cases.len(), 1);
 		assert!(matches!(suite.cases[0].kind, CaseKind::SqlExpect(_)));
 	}
+
+	#[test]
+	fn parses_transaction_case() {
+		let raw = r#"
+[[cases]]
+name = "constraint_violation_rolls_back"
+kind = "transaction"
+statements = [
+  "CREATE account:a SET balance = 10;",
+  "CREATE account:a SET balance = 20;",
+]
+expect_commit = false
+verify_sql = "SELECT * FROM account;"
+
+[[cases.assertions]]
+path = "0"
+exists = false
+"#;
+
+		let suite: SuiteSpec = toml::from_str(raw).expect("suite should parse");
+		let CaseKind::Transaction(spec) = &suite.cases[0].kind else {
+			panic!("expected transaction case");
+		};
+		assert_eq!(spec.statements.len(), 2);
+		assert!(!spec.expect_commit);
+		assert_eq!(spec.assertions.len(), 1);
+	}
+
+	#[test]
+	fn multi_actor_permission_matrices_run_their_hooks_as_root() {
+		let raw = r#"
+[[cases]]
+name = "everyone"
+kind = "permissions_matrix"
+actor = "all"
+table = "post"
+rules = [{ action = "select" }]
+
+[[cases]]
+name = "editors"
+kind = "permissions_matrix"
+actors = ["editor", "viewer"]
+table = "post"
+rules = [{ action = "select" }]
+
+[[cases]]
+name = "viewer_only"
+kind = "permissions_matrix"
+actor = "viewer"
+table = "post"
+rules = [{ action = "select" }]
+"#;
+
+		let suite: SuiteSpec = toml::from_str(raw).expect("suite should parse");
+		let actors: Vec<_> = suite.cases.iter().map(|case| case.kind.actor()).collect();
+		assert_eq!(actors, vec![None, None, Some("viewer")]);
+		let CaseKind::PermissionsMatrix(editors) = &suite.cases[1].kind else {
+			panic!("expected permissions_matrix");
+		};
+		assert_eq!(editors.actors, vec!["editor", "viewer"]);
+	}
+
+	#[test]
+	fn parses_suite_and_case_hooks_alongside_kind_fields() {
+		let raw = r#"
+before_each = ["DELETE temp;"]
+after_all = ["REMOVE TABLE temp;"]
+
+[[cases]]
+name = "reads_temp_record"
+kind = "sql_expect"
+actor = "user_alice"
+before_sql = ["CREATE temp:one SET n = 1;"]
+sql = "SELECT * FROM temp:one;"
+after_sql = ["DELETE temp:one;"]
+
+[[cases]]
+name = "temp_record_was_removed"
+kind = "sql_expect"
+sql = "SELECT * FROM temp:one;"
+"#;
+
+		let suite: SuiteSpec = toml::from_str(raw).expect("suite should parse");
+		let case = &suite.cases[0];
+		assert_eq!(case.before_sql, vec!["CREATE temp:one SET n = 1;"]);
+		assert_eq!(case.after_sql, vec!["DELETE temp:one;"]);
+		assert_eq!(case.kind.actor(), Some("user_alice"));
+		assert!(suite.cases[1].before_sql.is_empty());
+		assert_eq!(suite.before_each, vec!["DELETE temp;"]);
+		assert_eq!(suite.after_all, vec!["REMOVE TABLE temp;"]);
+		assert!(suite.before_all.is_empty());
+
+		let aliased: SuiteSpec =
+			toml::from_str("setup_sql = [\"CREATE temp;\"]\nteardown_sql = [\"DELETE temp;\"]")
+				.expect("aliases should parse");
+		assert_eq!(aliased.before_all, vec!["CREATE temp;"]);
+		assert_eq!(aliased.after_all, vec!["DELETE temp;"]);
+	}
+
+	#[test]
+	fn only_flags_select_exactly_those_prep_steps() {
+		let only_sync = TestOpts {
+			only_sync: true,
+			..TestOpts::default()
+		};
+		assert_eq!(
+			only_sync.prep_steps(),
+			PrepSteps {
+				setup: false,
+				sync: true,
+				seed: false,
+			}
+		);
+
+		let no_seed = TestOpts {
+			no_seed: true,
+			..TestOpts::default()
+		};
+		assert_eq!(
+			no_seed.prep_steps(),
+			PrepSteps {
+				setup: true,
+				sync: true,
+				seed: false,
+			}
+		);
+	}
 }
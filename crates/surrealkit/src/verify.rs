@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use surrealdb::{Surreal, engine::any::Any};
+
+use crate::core::sha256_hex;
+use crate::setup::run_setup;
+use crate::sync::load_sync_hashes;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityViolation {
+	pub file: String,
+	pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+	Missing,
+	HashMismatch { expected: String, actual: String },
+}
+
+pub async fn run_verify(db: &Surreal<Any>) -> Result<()> {
+	let violations = verify_integrity(db).await?;
+	if violations.is_empty() {
+		println!("All tracked schema files are present and unchanged.");
+		return Ok(());
+	}
+
+	for violation in &violations {
+		match &violation.kind {
+			ViolationKind::Missing => println!("missing: {}", violation.file),
+			ViolationKind::HashMismatch { expected, actual } => println!(
+				"modified: {} (tracked {}, current {})",
+				violation.file, expected, actual
+			),
+		}
+	}
+	bail!("{} integrity violation(s) found", violations.len())
+}
+
+pub async fn verify_integrity(db: &Surreal<Any>) -> Result<Vec<IntegrityViolation>> {
+	run_setup(db).await?;
+	let tracked = load_sync_hashes(db).await?;
+	check_tracked_files(&tracked)
+}
+
+fn check_tracked_files(tracked: &BTreeMap<String, String>) -> Result<Vec<IntegrityViolation>> {
+	let mut violations = Vec::new();
+	for (file, expected) in tracked {
+		let path = Path::new(file);
+		if !path.exists() {
+			violations.push(IntegrityViolation {
+				file: file.clone(),
+				kind: ViolationKind::Missing,
+			});
+			continue;
+		}
+
+		let raw = fs::read(path).with_context(|| format!("reading {}", file))?;
+		let actual = sha256_hex(&raw);
+		if &actual != expected {
+			violations.push(IntegrityViolation {
+				file: file.clone(),
+				kind: ViolationKind::HashMismatch {
+					expected: expected.clone(),
+					actual,
+				},
+			});
+		}
+	}
+	Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_missing_and_modified_files() {
+		let tmp = tempfile::tempdir().expect("create temp dir");
+		let dir = tmp.path();
+		let unchanged = dir.join("unchanged.surql");
+		let modified = dir.join("modified.surql");
+		fs::write(&unchanged, "DEFINE TABLE a;").expect("write unchanged");
+		fs::write(&modified, "DEFINE TABLE b SCHEMAFULL;").expect("write modified");
+
+		let path_str = |p: &Path| p.to_string_lossy().into_owned();
+		let tracked = BTreeMap::from([
+			(path_str(&unchanged), sha256_hex(b"DEFINE TABLE a;")),
+			(path_str(&modified), sha256_hex(b"DEFINE TABLE b;")),
+			(path_str(&dir.join("gone.surql")), "x".to_string()),
+		]);
+
+		let violations = check_tracked_files(&tracked).expect("check should run");
+
+		assert_eq!(violations.len(), 2);
+		assert!(
+			violations
+				.iter()
+				.any(|v| v.file.ends_with("gone.surql") && v.kind == ViolationKind::Missing)
+		);
+		assert!(violations.iter().any(|v| {
+			v.file.ends_with("modified.surql")
+				&& matches!(&v.kind, ViolationKind::HashMismatch { expected, .. }
+					if expected == &sha256_hex(b"DEFINE TABLE b;"))
+		}));
+	}
+}